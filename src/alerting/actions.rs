@@ -0,0 +1,570 @@
+use super::rule::Unit;
+use super::{AlertTransition, Severity, TransitionKind};
+use crate::config::{AlertActionConfig, AlertsConfig};
+use crate::shutdown::{join_with_timeout, ShutdownSignal};
+use crate::state::data_types::Timestamp;
+use crate::utils::format_unix_time;
+
+use crossbeam_channel::{unbounded, Receiver, RecvTimeoutError, Sender};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+// How often a running command is checked for completion.
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Hands alert transitions to the action threads. Cheap to clone.
+#[derive(Debug, Clone, Default)]
+pub struct AlertNotifier {
+    senders: Vec<Sender<AlertTransition>>,
+}
+
+impl AlertNotifier {
+    pub fn notify(&self, transition: &AlertTransition) {
+        for sender in &self.senders {
+            // A closed channel means the action thread is already gone.
+            let _ = sender.send(transition.clone());
+        }
+    }
+}
+
+/// Runs each configured alert action on its own thread, so a slow webhook or
+/// hook script never holds up the app or the other actions.
+pub struct AlertActions {
+    notifier: AlertNotifier,
+    handles: Vec<(String, JoinHandle<()>)>,
+}
+
+impl AlertActions {
+    pub fn spawn(config: &AlertsConfig, hostname: Option<String>, shutdown_signal: ShutdownSignal) -> Self {
+        let mut notifier = AlertNotifier::default();
+        let mut handles = Vec::new();
+        for action in &config.actions {
+            let (sender, receiver) = unbounded();
+            let worker = ActionWorker::new(action, hostname.clone(), shutdown_signal.clone());
+            log::info!("Alert action: {}", worker.target);
+            notifier.senders.push(sender);
+            let name = format!("Alert action {}", worker.target);
+            handles.push((name, std::thread::spawn(move || worker.run(receiver))));
+        }
+        AlertActions { notifier, handles }
+    }
+
+    pub fn notifier(&self) -> AlertNotifier {
+        self.notifier.clone()
+    }
+
+    /// Waits until `deadline` for the action threads to finish what they were
+    /// already sent. Every notifier handed out must have been dropped first.
+    pub fn join(self, deadline: Instant) {
+        drop(self.notifier);
+        join_with_timeout(self.handles, deadline);
+    }
+}
+
+enum Target {
+    Command(String),
+    LogFile(PathBuf),
+    Webhook(String),
+}
+
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Target::Command(command) => write!(f, "command `{}`", command),
+            Target::LogFile(path) => write!(f, "log file {}", path.display()),
+            Target::Webhook(url) => write!(f, "webhook {}", url),
+        }
+    }
+}
+
+/// What actions receive, as JSON for webhooks and as environment variables
+/// for commands.
+#[derive(Debug, Serialize)]
+struct Notification<'a> {
+    alert: &'a str,
+    rule: &'a str,
+    severity: Severity,
+    state: TransitionKind,
+    value: Option<f64>,
+    unit: Unit,
+    // `value` formatted for people, e.g. "93.1%".
+    value_text: String,
+    timestamp: Timestamp,
+    hostname: Option<&'a str>,
+    // Firings never reported because of the rate limit since the last notification.
+    suppressed: u32,
+}
+
+enum Failure {
+    Retry(String),
+    GiveUp(String),
+}
+
+// Rate limiting state for one alert.
+#[derive(Default)]
+struct Delivery {
+    // When a firing notification was last sent.
+    last_fired: Option<Instant>,
+    // The receiving end was told the alert is firing and not yet that it resolved.
+    reported_firing: bool,
+    // The latest firing held back by the rate limit, sent once the interval
+    // is up unless the alert resolves first.
+    held: Option<AlertTransition>,
+    // Held firings dropped because the alert resolved before their turn.
+    suppressed: u32,
+}
+
+struct ActionWorker {
+    target: Target,
+    // Alert names to act on; all when empty.
+    alerts: Vec<String>,
+    resolved: bool,
+    min_interval: Duration,
+    retries: u32,
+    timeout: Duration,
+    hostname: Option<String>,
+    shutdown_signal: ShutdownSignal,
+    deliveries: HashMap<String, Delivery>,
+}
+
+impl ActionWorker {
+    fn new(config: &AlertActionConfig, hostname: Option<String>, shutdown_signal: ShutdownSignal) -> Self {
+        // The config is validated to set exactly one target.
+        let target = match (&config.command, &config.log_file, &config.webhook) {
+            (Some(command), _, _) => Target::Command(command.clone()),
+            (_, Some(path), _) => Target::LogFile(path.clone()),
+            (_, _, url) => Target::Webhook(url.clone().unwrap_or_default()),
+        };
+        ActionWorker {
+            target,
+            alerts: config.alerts.clone(),
+            resolved: config.resolved,
+            min_interval: Duration::from_secs(config.min_interval_secs),
+            retries: config.retries,
+            timeout: Duration::from_secs(config.timeout_secs),
+            hostname,
+            shutdown_signal,
+            deliveries: HashMap::new(),
+        }
+    }
+
+    fn run(mut self, receiver: Receiver<AlertTransition>) {
+        loop {
+            let message = match self.next_release() {
+                Some(deadline) => receiver.recv_deadline(deadline),
+                None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match message {
+                Ok(transition) => self.handle(transition),
+                Err(RecvTimeoutError::Timeout) => self.release_held(),
+                // Held notifications are dropped on shutdown.
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    }
+
+    fn handle(&mut self, transition: AlertTransition) {
+        if !self.alerts.is_empty() && !self.alerts.contains(&transition.name) {
+            return;
+        }
+        let min_interval = self.min_interval;
+        let delivery = self.deliveries.entry(transition.name.clone()).or_default();
+        match transition.kind {
+            TransitionKind::Firing => {
+                if delivery.last_fired.is_some_and(|last| last.elapsed() < min_interval) {
+                    delivery.held = Some(transition);
+                } else {
+                    self.fire(transition);
+                }
+            }
+            TransitionKind::Resolved => {
+                // A held firing was never reported, so neither is its end.
+                if delivery.held.take().is_some() {
+                    delivery.suppressed += 1;
+                }
+                let reported = std::mem::take(&mut delivery.reported_firing);
+                if reported && self.resolved {
+                    self.deliver(&transition, 0);
+                }
+            }
+        }
+    }
+
+    fn fire(&mut self, transition: AlertTransition) {
+        let delivery = self.deliveries.entry(transition.name.clone()).or_default();
+        delivery.last_fired = Some(Instant::now());
+        delivery.reported_firing = true;
+        let suppressed = std::mem::take(&mut delivery.suppressed);
+        self.deliver(&transition, suppressed);
+    }
+
+    fn next_release(&self) -> Option<Instant> {
+        self.deliveries
+            .values()
+            .filter(|delivery| delivery.held.is_some())
+            .filter_map(|delivery| delivery.last_fired.map(|last| last + self.min_interval))
+            .min()
+    }
+
+    fn release_held(&mut self) {
+        let now = Instant::now();
+        let due: Vec<AlertTransition> = self
+            .deliveries
+            .values_mut()
+            .filter(|delivery| delivery.last_fired.is_none_or(|last| last + self.min_interval <= now))
+            .filter_map(|delivery| delivery.held.take())
+            .collect();
+        for transition in due {
+            self.fire(transition);
+        }
+    }
+
+    fn deliver(&self, transition: &AlertTransition, suppressed: u32) {
+        let notification = Notification {
+            alert: &transition.name,
+            rule: &transition.rule,
+            severity: transition.severity,
+            state: transition.kind,
+            value: transition.value,
+            unit: transition.unit,
+            value_text: transition.value.map_or_else(|| "no data".to_string(), |value| transition.unit.format(value)),
+            timestamp: transition.at,
+            hostname: self.hostname.as_deref(),
+            suppressed,
+        };
+        let mut delay = FIRST_RETRY_DELAY;
+        for attempt in 0..=self.retries {
+            let message = match self.send(&notification) {
+                Ok(()) => return,
+                Err(Failure::GiveUp(message)) => {
+                    log::error!("Alert action {} failed for {}: {}", self.target, transition.name, message);
+                    return;
+                }
+                Err(Failure::Retry(message)) => message,
+            };
+            if attempt == self.retries || self.shutdown_signal.is_set() {
+                log::error!("Alert action {} failed for {}, giving up: {}", self.target, transition.name, message);
+                return;
+            }
+            log::warn!("Alert action {} failed for {}, retrying in {:?}: {}", self.target, transition.name, delay, message);
+            if self.shutdown_signal.sleep(delay) {
+                log::error!("Alert action {} failed for {}, giving up on shutdown: {}", self.target, transition.name, message);
+                return;
+            }
+            delay = (delay * 2).min(MAX_RETRY_DELAY);
+        }
+    }
+
+    fn send(&self, notification: &Notification) -> Result<(), Failure> {
+        match &self.target {
+            Target::Command(command) => self.run_command(command, notification),
+            Target::LogFile(path) => append_log_line(path, notification),
+            Target::Webhook(url) => self.post_webhook(url, notification),
+        }
+    }
+
+    fn run_command(&self, command: &str, notification: &Notification) -> Result<(), Failure> {
+        // Output would land in the middle of the TUI.
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .env("MONOXIDE_ALERT_NAME", notification.alert)
+            .env("MONOXIDE_ALERT_RULE", notification.rule)
+            .env("MONOXIDE_ALERT_SEVERITY", notification.severity.name())
+            .env("MONOXIDE_ALERT_STATE", notification.state.name())
+            .env("MONOXIDE_ALERT_VALUE", &notification.value_text)
+            .env("MONOXIDE_ALERT_TIMESTAMP", notification.timestamp.unix_millis().to_string())
+            .env("MONOXIDE_ALERT_HOSTNAME", notification.hostname.unwrap_or_default())
+            .env("MONOXIDE_ALERT_SUPPRESSED", notification.suppressed.to_string())
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| Failure::Retry(format!("failed to start: {}", e)))?;
+
+        let started = Instant::now();
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) if status.success() => return Ok(()),
+                Ok(Some(status)) => return Err(Failure::Retry(format!("exited with {}", status))),
+                Ok(None) if started.elapsed() >= self.timeout => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(Failure::Retry(format!("timed out after {:?}", self.timeout)));
+                }
+                Ok(None) => std::thread::sleep(COMMAND_POLL_INTERVAL),
+                Err(e) => return Err(Failure::Retry(e.to_string())),
+            }
+        }
+    }
+
+    fn post_webhook(&self, url: &str, notification: &Notification) -> Result<(), Failure> {
+        let body = serde_json::to_string(notification).map_err(|e| Failure::GiveUp(e.to_string()))?;
+        let agent = ureq::AgentBuilder::new().timeout(self.timeout).build();
+        match agent.post(url).set("Content-Type", "application/json").send_string(&body) {
+            Ok(_) => Ok(()),
+            // The endpoint rejected the request itself; sending it again won't help.
+            Err(ureq::Error::Status(code, _)) if (400..500).contains(&code) && code != 429 => {
+                Err(Failure::GiveUp(format!("HTTP {}", code)))
+            }
+            Err(ureq::Error::Status(code, _)) => Err(Failure::Retry(format!("HTTP {}", code))),
+            Err(e) => Err(Failure::Retry(e.to_string())),
+        }
+    }
+}
+
+// e.g. `2024-03-01 14:05:09 UTC FIRING root-full [critical] filesystem / used > 95% (96.2%)`
+fn append_log_line(path: &Path, notification: &Notification) -> Result<(), Failure> {
+    let mut line = format!(
+        "{} {} {} [{}] {} ({})",
+        format_unix_time(notification.timestamp.unix_millis() / 1000),
+        notification.state.name().to_uppercase(),
+        notification.alert,
+        notification.severity.name(),
+        notification.rule,
+        notification.value_text
+    );
+    if notification.suppressed > 0 {
+        let _ = write!(line, ", {} earlier firing suppressed", notification.suppressed);
+    }
+    line.push('\n');
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| Failure::Retry(e.to_string()))?;
+    file.write_all(line.as_bytes()).map_err(|e| Failure::Retry(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    // 2023-11-14 22:13:20 UTC
+    const AT_MS: u64 = 1_700_000_000_000;
+
+    fn transition(name: &str, kind: TransitionKind, value: f64) -> AlertTransition {
+        AlertTransition {
+            at: Timestamp { monotonic: Instant::now(), wall: UNIX_EPOCH + Duration::from_millis(AT_MS) },
+            name: name.to_string(),
+            rule: "filesystem / used > 95%".to_string(),
+            severity: Severity::Critical,
+            kind,
+            value: Some(value),
+            unit: Unit::Percent,
+        }
+    }
+
+    fn worker(config: AlertActionConfig) -> ActionWorker {
+        ActionWorker::new(&config, Some("testhost".to_string()), ShutdownSignal::new())
+    }
+
+    // A stand-in webhook endpoint answering with `statuses` in turn, the last
+    // one repeating. Returns its URL and a channel of the requests it got.
+    fn stand_in(statuses: Vec<u16>) -> (String, Receiver<(String, String)>) {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/alerts", server.server_addr().to_ip().unwrap());
+        let (sender, receiver) = unbounded();
+        std::thread::spawn(move || {
+            for (index, mut request) in server.incoming_requests().enumerate() {
+                let content_type = request
+                    .headers()
+                    .iter()
+                    .find(|header| header.field.equiv("Content-Type"))
+                    .map(|header| header.value.to_string())
+                    .unwrap_or_default();
+                let mut body = String::new();
+                request.as_reader().read_to_string(&mut body).unwrap();
+                let _ = sender.send((content_type, body));
+                let status = statuses[index.min(statuses.len() - 1)];
+                let _ = request.respond(tiny_http::Response::empty(status));
+            }
+        });
+        (url, receiver)
+    }
+
+    fn webhook(url: &str, retries: u32) -> AlertActionConfig {
+        AlertActionConfig { webhook: Some(url.to_string()), retries, ..AlertActionConfig::default() }
+    }
+
+    fn log_file(path: &Path, min_interval_secs: u64) -> AlertActionConfig {
+        AlertActionConfig { log_file: Some(path.to_path_buf()), min_interval_secs, ..AlertActionConfig::default() }
+    }
+
+    fn lines(path: &Path) -> Vec<String> {
+        std::fs::read_to_string(path).unwrap_or_default().lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn posts_the_alert_as_json() {
+        let (url, requests) = stand_in(vec![200]);
+        worker(webhook(&url, 0)).handle(transition("root-full", TransitionKind::Firing, 96.25));
+
+        let (content_type, body) = requests.try_recv().unwrap();
+        assert_eq!(content_type, "application/json");
+        let payload: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(
+            payload,
+            serde_json::json!({
+                "alert": "root-full",
+                "rule": "filesystem / used > 95%",
+                "severity": "critical",
+                "state": "firing",
+                "value": 96.25,
+                "unit": "percent",
+                "value_text": "96.2%",
+                "timestamp": AT_MS,
+                "hostname": "testhost",
+                "suppressed": 0,
+            })
+        );
+    }
+
+    #[test]
+    fn retries_server_errors() {
+        let (url, requests) = stand_in(vec![500, 503, 200]);
+        worker(webhook(&url, 3)).handle(transition("root-full", TransitionKind::Firing, 96.0));
+        assert_eq!(requests.try_iter().count(), 3);
+    }
+
+    #[test]
+    fn gives_up_after_the_configured_retries() {
+        let (url, requests) = stand_in(vec![500]);
+        worker(webhook(&url, 1)).handle(transition("root-full", TransitionKind::Firing, 96.0));
+        assert_eq!(requests.try_iter().count(), 2);
+    }
+
+    #[test]
+    fn does_not_retry_client_errors() {
+        let (url, requests) = stand_in(vec![404, 200]);
+        worker(webhook(&url, 3)).handle(transition("root-full", TransitionKind::Firing, 96.0));
+        assert_eq!(requests.try_iter().count(), 1);
+    }
+
+    #[test]
+    fn retries_too_many_requests() {
+        let (url, requests) = stand_in(vec![429, 200]);
+        worker(webhook(&url, 3)).handle(transition("root-full", TransitionKind::Firing, 96.0));
+        assert_eq!(requests.try_iter().count(), 2);
+    }
+
+    #[test]
+    fn only_acts_on_the_listed_alerts() {
+        let (url, requests) = stand_in(vec![200]);
+        let config = AlertActionConfig { alerts: vec!["cpu-busy".to_string()], ..webhook(&url, 0) };
+        let mut worker = worker(config);
+        worker.handle(transition("root-full", TransitionKind::Firing, 96.0));
+        worker.handle(transition("cpu-busy", TransitionKind::Firing, 96.0));
+        let alerts: Vec<String> = requests
+            .try_iter()
+            .map(|(_, body)| serde_json::from_str::<serde_json::Value>(&body).unwrap()["alert"].to_string())
+            .collect();
+        assert_eq!(alerts, ["\"cpu-busy\""]);
+    }
+
+    #[test]
+    fn writes_one_log_line_per_notification() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("alerts.log");
+        let mut worker = worker(log_file(&path, 0));
+        worker.handle(transition("root-full", TransitionKind::Firing, 96.25));
+        worker.handle(transition("root-full", TransitionKind::Resolved, 91.0));
+        assert_eq!(
+            lines(&path),
+            [
+                "2023-11-14 22:13:20 UTC FIRING root-full [critical] filesystem / used > 95% (96.2%)",
+                "2023-11-14 22:13:20 UTC RESOLVED root-full [critical] filesystem / used > 95% (91.0%)",
+            ]
+        );
+    }
+
+    #[test]
+    fn holds_a_refiring_alert_until_the_interval_is_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("alerts.log");
+        let mut worker = worker(log_file(&path, 300));
+
+        worker.handle(transition("root-full", TransitionKind::Firing, 96.0));
+        worker.handle(transition("root-full", TransitionKind::Resolved, 90.0));
+        worker.handle(transition("root-full", TransitionKind::Firing, 97.0));
+        assert_eq!(lines(&path).len(), 2);
+        assert!(worker.deliveries["root-full"].held.is_some());
+        let release = worker.next_release().unwrap();
+        assert!(release > Instant::now() + Duration::from_secs(290));
+
+        // Once the interval is up, the held firing goes out.
+        worker.deliveries.get_mut("root-full").unwrap().last_fired = Some(Instant::now() - Duration::from_secs(301));
+        worker.release_held();
+        let lines = lines(&path);
+        assert_eq!(lines.len(), 3);
+        assert!(lines[2].contains("FIRING root-full") && lines[2].ends_with("(97.0%)"), "{}", lines[2]);
+        assert_eq!(worker.next_release(), None);
+    }
+
+    #[test]
+    fn counts_held_firings_dropped_by_a_resolve() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("alerts.log");
+        let mut worker = worker(log_file(&path, 300));
+
+        worker.handle(transition("root-full", TransitionKind::Firing, 96.0));
+        worker.handle(transition("root-full", TransitionKind::Resolved, 90.0));
+        // Flaps twice within the interval; neither firing nor its resolve is sent.
+        for _ in 0..2 {
+            worker.handle(transition("root-full", TransitionKind::Firing, 97.0));
+            worker.handle(transition("root-full", TransitionKind::Resolved, 90.0));
+        }
+        assert_eq!(lines(&path).len(), 2);
+        assert_eq!(worker.deliveries["root-full"].suppressed, 2);
+        assert_eq!(worker.next_release(), None);
+
+        worker.deliveries.get_mut("root-full").unwrap().last_fired = Some(Instant::now() - Duration::from_secs(301));
+        worker.handle(transition("root-full", TransitionKind::Firing, 98.0));
+        let lines = lines(&path);
+        assert_eq!(lines.len(), 3);
+        assert!(lines[2].ends_with("(98.0%), 2 earlier firing suppressed"), "{}", lines[2]);
+        assert_eq!(worker.deliveries["root-full"].suppressed, 0);
+    }
+
+    #[test]
+    fn skips_resolved_notifications_when_disabled() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("alerts.log");
+        let mut worker = worker(AlertActionConfig { resolved: false, ..log_file(&path, 0) });
+        worker.handle(transition("root-full", TransitionKind::Firing, 96.0));
+        worker.handle(transition("root-full", TransitionKind::Resolved, 90.0));
+        assert_eq!(lines(&path).len(), 1);
+    }
+
+    #[test]
+    fn passes_alert_details_to_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("env");
+        let command = format!("env | grep ^MONOXIDE_ALERT_ | sort > {}", path.display());
+        let mut worker = worker(AlertActionConfig { command: Some(command), ..AlertActionConfig::default() });
+        worker.handle(transition("root-full", TransitionKind::Firing, 96.25));
+        assert_eq!(
+            lines(&path),
+            [
+                "MONOXIDE_ALERT_HOSTNAME=testhost",
+                "MONOXIDE_ALERT_NAME=root-full",
+                "MONOXIDE_ALERT_RULE=filesystem / used > 95%",
+                "MONOXIDE_ALERT_SEVERITY=critical",
+                "MONOXIDE_ALERT_STATE=firing",
+                "MONOXIDE_ALERT_SUPPRESSED=0",
+                "MONOXIDE_ALERT_TIMESTAMP=1700000000000",
+                "MONOXIDE_ALERT_VALUE=96.2%",
+            ]
+        );
+    }
+}
@@ -0,0 +1,394 @@
+pub mod actions;
+pub mod rule;
+
+use crate::config::{config_error, AlertsConfig};
+use crate::error::AppError;
+use crate::state::app_state::AppState;
+use crate::state::data_types::{DataSource, Timestamp};
+use rule::{Rule, Unit};
+
+use serde::{Deserialize, Serialize};
+
+// Transitions kept in `AppState::alert_log` for the UI.
+const ALERT_LOG_CAPACITY: usize = 200;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    #[default]
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Warning => "warning",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertState {
+    Ok,
+    // The condition holds but not yet for the rule's duration.
+    Pending,
+    Firing,
+}
+
+/// Where one configured rule currently stands.
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertStatus {
+    pub name: String,
+    // The rule as written in the config.
+    pub rule: String,
+    pub severity: Severity,
+    pub state: AlertState,
+    // When the alert entered its current state; unset while it has never left `Ok`.
+    pub since: Option<Timestamp>,
+    pub value: Option<f64>,
+    pub unit: Unit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransitionKind {
+    Firing,
+    Resolved,
+}
+
+impl TransitionKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TransitionKind::Firing => "firing",
+            TransitionKind::Resolved => "resolved",
+        }
+    }
+}
+
+/// An alert starting or stopping to fire.
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertTransition {
+    pub at: Timestamp,
+    pub name: String,
+    pub rule: String,
+    pub severity: Severity,
+    pub kind: TransitionKind,
+    pub value: Option<f64>,
+    pub unit: Unit,
+}
+
+struct AlertRule {
+    name: String,
+    text: String,
+    severity: Severity,
+    rule: Rule,
+    hysteresis: f64,
+}
+
+/// Evaluates the configured rules against the state as updates arrive. The
+/// engine keeps no state of its own: each rule's progress lives in
+/// `AppState::alerts`, so a fresh state starts every alert over.
+pub struct AlertEngine {
+    rules: Vec<AlertRule>,
+}
+
+impl AlertEngine {
+    pub fn new(config: &AlertsConfig) -> Result<Self, AppError> {
+        let mut rules: Vec<AlertRule> = Vec::new();
+        for (index, rule_config) in config.rules.iter().enumerate() {
+            let key = |field: &str| format!("alerts.rules[{}].{}", index, field);
+            if rules.iter().any(|rule| rule.name == rule_config.name) {
+                return Err(config_error(key("name"), format!("duplicate alert name `{}`", rule_config.name)));
+            }
+            let rule: Rule = rule_config.rule.parse().map_err(|e: String| config_error(key("rule"), e))?;
+            let hysteresis = rule_config.hysteresis.unwrap_or_else(|| rule.default_hysteresis());
+            if hysteresis < 0.0 {
+                return Err(config_error(key("hysteresis"), "must not be negative"));
+            }
+            rules.push(AlertRule {
+                name: rule_config.name.clone(),
+                text: rule_config.rule.clone(),
+                severity: rule_config.severity,
+                rule,
+                hysteresis,
+            });
+        }
+        Ok(AlertEngine { rules })
+    }
+
+    /// Puts every alert back to `Ok`.
+    pub fn reset(&self, state: &mut AppState) {
+        state.alerts = self
+            .rules
+            .iter()
+            .map(|rule| AlertStatus {
+                name: rule.name.clone(),
+                rule: rule.text.clone(),
+                severity: rule.severity,
+                state: AlertState::Ok,
+                since: None,
+                value: None,
+                unit: rule.rule.unit(),
+            })
+            .collect();
+    }
+
+    /// Re-evaluates the rules that depend on `source` after it was updated at
+    /// `at`. Transitions are logged, added to `state.alert_log` and returned.
+    pub fn evaluate(&self, source: DataSource, at: Timestamp, state: &mut AppState) -> Vec<AlertTransition> {
+        let mut transitions = Vec::new();
+        for (index, rule) in self.rules.iter().enumerate() {
+            if rule.rule.source() != source {
+                continue;
+            }
+            let evaluation = rule.rule.evaluate(state, rule.hysteresis);
+            let status = &mut state.alerts[index];
+            status.value = evaluation.value;
+            let next = match status.state {
+                AlertState::Ok | AlertState::Pending if !evaluation.met => AlertState::Ok,
+                AlertState::Ok if rule.rule.duration.is_zero() => AlertState::Firing,
+                AlertState::Ok => AlertState::Pending,
+                AlertState::Pending => {
+                    let pending_since = status.since.map_or(at.monotonic, |since| since.monotonic);
+                    if at.monotonic.saturating_duration_since(pending_since) >= rule.rule.duration {
+                        AlertState::Firing
+                    } else {
+                        AlertState::Pending
+                    }
+                }
+                AlertState::Firing if evaluation.cleared => AlertState::Ok,
+                AlertState::Firing => AlertState::Firing,
+            };
+            if next == status.state {
+                continue;
+            }
+            let kind = match (status.state, next) {
+                (_, AlertState::Firing) => Some(TransitionKind::Firing),
+                (AlertState::Firing, _) => Some(TransitionKind::Resolved),
+                _ => None,
+            };
+            status.state = next;
+            status.since = Some(at);
+            if let Some(kind) = kind {
+                transitions.push(AlertTransition {
+                    at,
+                    name: rule.name.clone(),
+                    rule: rule.text.clone(),
+                    severity: rule.severity,
+                    kind,
+                    value: evaluation.value,
+                    unit: status.unit,
+                });
+            }
+        }
+
+        for transition in &transitions {
+            log_transition(transition);
+            if state.alert_log.len() == ALERT_LOG_CAPACITY {
+                state.alert_log.pop_front();
+            }
+            state.alert_log.push_back(transition.clone());
+        }
+        transitions
+    }
+}
+
+fn log_transition(transition: &AlertTransition) {
+    let value = transition.value.map_or_else(|| "no data".to_string(), |value| transition.unit.format(value));
+    match transition.kind {
+        TransitionKind::Firing => {
+            log::warn!("Alert {} firing: {} (value {})", transition.name, transition.rule, value)
+        }
+        TransitionKind::Resolved => log::info!("Alert {} resolved (value {})", transition.name, value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AlertRuleConfig;
+    use crate::state::data_types::{CpuData, FilesystemData, FilesystemMetrics, GlobalCpuMetrics};
+    use std::time::{Duration, Instant};
+
+    // Rules as (name, rule, hysteresis).
+    fn config(rules: &[(&str, &str, Option<f64>)]) -> AlertsConfig {
+        AlertsConfig {
+            rules: rules
+                .iter()
+                .map(|(name, rule, hysteresis)| AlertRuleConfig {
+                    name: name.to_string(),
+                    rule: rule.to_string(),
+                    severity: Severity::Critical,
+                    hysteresis: *hysteresis,
+                })
+                .collect(),
+            ..AlertsConfig::default()
+        }
+    }
+
+    fn engine(rules: &[(&str, &str, Option<f64>)]) -> AlertEngine {
+        AlertEngine::new(&config(rules)).unwrap()
+    }
+
+    // Feeds CPU samples one second apart, starting at `start`, and returns the
+    // state after each with any transitions it caused.
+    fn run(
+        engine: &AlertEngine,
+        state: &mut AppState,
+        start: Instant,
+        samples: &[f32],
+    ) -> Vec<(AlertState, Option<TransitionKind>)> {
+        samples
+            .iter()
+            .enumerate()
+            .map(|(second, total)| {
+                state.cpu = Some(CpuData::GlobalCpuMetrics(GlobalCpuMetrics {
+                    total_usage_percent: *total,
+                    core_usages_percent: Vec::new(),
+                    core_frequencies_mhz: Vec::new(),
+                    times: None,
+                }));
+                let at = Timestamp::at(start + Duration::from_secs(second as u64));
+                let transitions = engine.evaluate(DataSource::Cpu, at, state);
+                assert!(transitions.len() <= 1);
+                (state.alerts[0].state, transitions.first().map(|transition| transition.kind))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn goes_through_pending_before_firing() {
+        let engine = engine(&[("cpu-busy", "cpu.total > 90% for 2s", Some(5.0))]);
+        let mut state = AppState::default();
+        engine.reset(&mut state);
+        let steps = run(&engine, &mut state, Instant::now(), &[50.0, 95.0, 96.0, 97.0, 97.0, 80.0]);
+        assert_eq!(
+            steps,
+            [
+                (AlertState::Ok, None),
+                (AlertState::Pending, None),
+                (AlertState::Pending, None),
+                (AlertState::Firing, Some(TransitionKind::Firing)),
+                (AlertState::Firing, None),
+                (AlertState::Ok, Some(TransitionKind::Resolved)),
+            ]
+        );
+        let kinds: Vec<_> = state.alert_log.iter().map(|transition| transition.kind).collect();
+        assert_eq!(kinds, [TransitionKind::Firing, TransitionKind::Resolved]);
+        let firing = &state.alert_log[0];
+        assert_eq!((firing.name.as_str(), firing.value), ("cpu-busy", Some(97.0)));
+    }
+
+    #[test]
+    fn a_dip_while_pending_starts_over() {
+        let engine = engine(&[("cpu-busy", "cpu.total > 90% for 2s", Some(5.0))]);
+        let mut state = AppState::default();
+        engine.reset(&mut state);
+        let steps = run(&engine, &mut state, Instant::now(), &[95.0, 95.0, 89.0, 95.0, 95.0, 95.0]);
+        let states: Vec<_> = steps.iter().map(|(state, _)| *state).collect();
+        assert_eq!(
+            states,
+            [
+                AlertState::Pending,
+                AlertState::Pending,
+                AlertState::Ok,
+                AlertState::Pending,
+                AlertState::Pending,
+                AlertState::Firing,
+            ]
+        );
+    }
+
+    #[test]
+    fn stays_firing_within_the_hysteresis_band() {
+        let engine = engine(&[("cpu-busy", "cpu.total > 90%", Some(5.0))]);
+        let mut state = AppState::default();
+        engine.reset(&mut state);
+        // Dips below the threshold but not past 85 until the fourth sample.
+        let steps = run(&engine, &mut state, Instant::now(), &[91.0, 89.0, 86.0, 91.0, 84.0, 89.0, 91.0]);
+        assert_eq!(
+            steps,
+            [
+                (AlertState::Firing, Some(TransitionKind::Firing)),
+                (AlertState::Firing, None),
+                (AlertState::Firing, None),
+                (AlertState::Firing, None),
+                (AlertState::Ok, Some(TransitionKind::Resolved)),
+                (AlertState::Ok, None),
+                (AlertState::Firing, Some(TransitionKind::Firing)),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_evaluates_rules_for_the_updated_source() {
+        let engine = engine(&[("cpu-busy", "cpu.total > 90%", None)]);
+        let mut state = AppState::default();
+        engine.reset(&mut state);
+        state.cpu = Some(CpuData::GlobalCpuMetrics(GlobalCpuMetrics {
+            total_usage_percent: 99.0,
+            core_usages_percent: Vec::new(),
+            core_frequencies_mhz: Vec::new(),
+            times: None,
+        }));
+        assert!(engine.evaluate(DataSource::Memory, Timestamp::now(), &mut state).is_empty());
+        assert_eq!(state.alerts[0].state, AlertState::Ok);
+        assert_eq!(engine.evaluate(DataSource::Cpu, Timestamp::now(), &mut state).len(), 1);
+    }
+
+    #[test]
+    fn keeps_firing_while_the_metric_is_gone() {
+        let engine = engine(&[("root-full", "filesystem /data used > 95%", None)]);
+        let mut state = AppState::default();
+        engine.reset(&mut state);
+        let mounted = |state: &mut AppState, used: Option<u64>| {
+            let filesystems = used.map(|used_bytes| FilesystemMetrics {
+                mount_point: "/data".to_string(),
+                device: "/dev/sdb1".to_string(),
+                fs_type: "ext4".to_string(),
+                total_bytes: 100,
+                used_bytes,
+                available_bytes: 100 - used_bytes,
+                total_inodes: 0,
+                used_inodes: 0,
+                free_inodes: 0,
+            });
+            state.filesystems = Some(FilesystemData::Global(filesystems.into_iter().collect()));
+            let transitions = engine.evaluate(DataSource::Filesystems, Timestamp::now(), state);
+            (state.alerts[0].state, transitions.first().map(|transition| transition.kind))
+        };
+
+        assert_eq!(mounted(&mut state, Some(97)), (AlertState::Firing, Some(TransitionKind::Firing)));
+        // Unmounted while full: no value, but no resolve either.
+        assert_eq!(mounted(&mut state, None), (AlertState::Firing, None));
+        assert_eq!(state.alerts[0].value, None);
+        assert_eq!(mounted(&mut state, None), (AlertState::Firing, None));
+        // Mounted again with room to spare.
+        assert_eq!(mounted(&mut state, Some(50)), (AlertState::Ok, Some(TransitionKind::Resolved)));
+    }
+
+    #[test]
+    fn rejects_bad_rule_configs() {
+        let error = |rules: &[(&str, &str, Option<f64>)]| {
+            match AlertEngine::new(&config(rules)) {
+                Err(AppError::ConfigError { key, message }) => (key, message),
+                _ => panic!("expected a config error for {:?}", rules),
+            }
+        };
+        assert_eq!(
+            error(&[("a", "cpu.total > 90", None), ("a", "load.1 > 4", None)]),
+            ("alerts.rules[1].name".to_string(), "duplicate alert name `a`".to_string())
+        );
+        assert_eq!(
+            error(&[("a", "cpu.total >> 90", None)]),
+            ("alerts.rules[0].rule".to_string(), "expected one of >, >=, <, <=, found `>>`".to_string())
+        );
+        assert_eq!(
+            error(&[("a", "cpu.total > 90", Some(-1.0))]),
+            ("alerts.rules[0].hysteresis".to_string(), "must not be negative".to_string())
+        );
+    }
+}
@@ -0,0 +1,740 @@
+use crate::state::app_state::AppState;
+use crate::state::data_types::*;
+use crate::utils::format_rate;
+
+use serde::Serialize;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// A parsed alert rule such as `cpu.total > 90% for 30s`.
+///
+/// Grammar: `<metric> <op> <value> [for <duration>]` or
+/// `process <name> missing [for <duration>]`. Names containing spaces can be
+/// double-quoted, e.g. `sensor "coretemp/Package id 0" > 90`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub condition: Condition,
+    // How long the condition must hold before the alert fires.
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Threshold { metric: Metric, comparison: Comparison, threshold: f64 },
+    // No process with exactly this name is running.
+    ProcessMissing(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Above,
+    AtLeast,
+    Below,
+    AtMost,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Metric {
+    CpuTotal,
+    CpuCore(usize),
+    CpuIowait,
+    CpuSteal,
+    MemoryUsed,
+    SwapUsed,
+    // 1, 5 or 15 minute load average.
+    Load(u32),
+    FilesystemUsed(String),
+    FilesystemInodes(String),
+    DiskUtilization(String),
+    NetworkRx(String),
+    NetworkTx(String),
+    GpuTemperature(usize),
+    GpuUtilization(usize),
+    GpuMemory(usize),
+    Sensor { chip: String, label: String },
+    // System-wide `some` stall percentage over the last 10 seconds.
+    Pressure(&'static str),
+}
+
+/// How a rule's values are written in rules and shown in the UI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Unit {
+    Percent,
+    Celsius,
+    BytesPerSec,
+    // Unitless, or a unit that varies per sensor.
+    Number,
+    // Matching process count, for `missing` rules.
+    Processes,
+}
+
+/// What a rule saw in the latest state.
+pub struct Evaluation {
+    pub value: Option<f64>,
+    // The condition holds.
+    pub met: bool,
+    // The value is back past the hysteresis band, so a firing alert resolves.
+    // Never set without a value: a metric that disappears, such as an
+    // unmounted filesystem, leaves a firing alert firing.
+    pub cleared: bool,
+}
+
+impl Rule {
+    /// The collector whose updates can change this rule's outcome.
+    pub fn source(&self) -> DataSource {
+        match &self.condition {
+            Condition::Threshold { metric, .. } => metric.source(),
+            Condition::ProcessMissing(_) => DataSource::Processes,
+        }
+    }
+
+    pub fn unit(&self) -> Unit {
+        match &self.condition {
+            Condition::Threshold { metric, .. } => metric.unit(),
+            Condition::ProcessMissing(_) => Unit::Processes,
+        }
+    }
+
+    pub fn evaluate(&self, state: &AppState, hysteresis: f64) -> Evaluation {
+        match &self.condition {
+            Condition::Threshold { metric, comparison, threshold } => {
+                let value = metric.value(state);
+                let resolve_at = match comparison {
+                    Comparison::Above | Comparison::AtLeast => threshold - hysteresis,
+                    Comparison::Below | Comparison::AtMost => threshold + hysteresis,
+                };
+                Evaluation {
+                    value,
+                    met: value.is_some_and(|v| comparison.holds(v, *threshold)),
+                    cleared: value.is_some_and(|v| !comparison.holds(v, resolve_at)),
+                }
+            }
+            Condition::ProcessMissing(name) => {
+                // Without a process list there is nothing to say yet.
+                let count = state.processes.as_ref().map(|processes| {
+                    processes.iter().filter(|p| !p.is_thread && process_is_named(p, name)).count() as f64
+                });
+                Evaluation { value: count, met: count == Some(0.0), cleared: count.is_some_and(|count| count > 0.0) }
+            }
+        }
+    }
+
+    /// The default hysteresis: a twentieth of the threshold.
+    pub fn default_hysteresis(&self) -> f64 {
+        match &self.condition {
+            Condition::Threshold { threshold, .. } => threshold.abs() / 20.0,
+            Condition::ProcessMissing(_) => 0.0,
+        }
+    }
+}
+
+// The kernel cuts a process's own name to 15 bytes, so longer names are
+// looked for in its executable path and command line too.
+fn process_is_named(process: &ProcessInfo, name: &str) -> bool {
+    let basename = |path: &str| path.rsplit('/').next() == Some(name);
+    process.name == name
+        || process.exe.as_deref().is_some_and(basename)
+        || process.cmd.first().is_some_and(|arg| basename(arg))
+}
+
+impl Comparison {
+    fn holds(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::Above => value > threshold,
+            Comparison::AtLeast => value >= threshold,
+            Comparison::Below => value < threshold,
+            Comparison::AtMost => value <= threshold,
+        }
+    }
+}
+
+impl Metric {
+    pub fn source(&self) -> DataSource {
+        match self {
+            Metric::CpuTotal | Metric::CpuCore(_) | Metric::CpuIowait | Metric::CpuSteal => DataSource::Cpu,
+            Metric::MemoryUsed | Metric::SwapUsed => DataSource::Memory,
+            Metric::Load(_) => DataSource::SystemStats,
+            Metric::FilesystemUsed(_) | Metric::FilesystemInodes(_) => DataSource::Filesystems,
+            Metric::DiskUtilization(_) => DataSource::Disk,
+            Metric::NetworkRx(_) | Metric::NetworkTx(_) => DataSource::Network,
+            Metric::GpuTemperature(_) | Metric::GpuUtilization(_) | Metric::GpuMemory(_) => DataSource::Gpu,
+            Metric::Sensor { .. } => DataSource::Sensors,
+            Metric::Pressure(_) => DataSource::Pressure,
+        }
+    }
+
+    pub fn unit(&self) -> Unit {
+        match self {
+            Metric::Load(_) | Metric::Sensor { .. } => Unit::Number,
+            Metric::NetworkRx(_) | Metric::NetworkTx(_) => Unit::BytesPerSec,
+            Metric::GpuTemperature(_) => Unit::Celsius,
+            _ => Unit::Percent,
+        }
+    }
+
+    fn value(&self, state: &AppState) -> Option<f64> {
+        match self {
+            Metric::CpuTotal | Metric::CpuCore(_) | Metric::CpuIowait | Metric::CpuSteal => {
+                let CpuData::GlobalCpuMetrics(cpu) = state.cpu.as_ref()?;
+                match self {
+                    Metric::CpuTotal => Some(cpu.total_usage_percent as f64),
+                    Metric::CpuCore(core) => cpu.core_usages_percent.get(*core).map(|usage| *usage as f64),
+                    Metric::CpuIowait => cpu.times.as_ref().map(|times| times.total.iowait),
+                    _ => cpu.times.as_ref().map(|times| times.total.steal),
+                }
+            }
+            Metric::MemoryUsed | Metric::SwapUsed => {
+                let MemoryData::Global(memory) = state.memory.as_ref()?;
+                let (used, total) = match self {
+                    Metric::MemoryUsed => (memory.used_memory_kb, memory.total_memory_kb),
+                    _ => (memory.used_swap_kb, memory.total_swap_kb),
+                };
+                (total > 0).then(|| used as f64 / total as f64 * 100.0)
+            }
+            Metric::Load(minutes) => {
+                let SystemStatsData::Global(stats) = state.system_stats.as_ref()?;
+                let (one, five, fifteen) = stats.load_average;
+                Some(match minutes {
+                    1 => one,
+                    5 => five,
+                    _ => fifteen,
+                })
+            }
+            Metric::FilesystemUsed(mount_point) | Metric::FilesystemInodes(mount_point) => {
+                let FilesystemData::Global(filesystems) = state.filesystems.as_ref()?;
+                let filesystem = filesystems.iter().find(|fs| fs.mount_point == *mount_point)?;
+                match self {
+                    Metric::FilesystemUsed(_) => Some(filesystem.usage_percent()),
+                    _ => (filesystem.total_inodes > 0).then(|| filesystem.inode_usage_percent()),
+                }
+            }
+            Metric::DiskUtilization(device) => {
+                let DiskData::Global(disks) = state.disk.as_ref()?;
+                disks.iter().find(|disk| disk.device_name == *device).map(|disk| disk.utilization_percent)
+            }
+            Metric::NetworkRx(interface) | Metric::NetworkTx(interface) => {
+                let NetworkData::Global(interfaces) = state.network.as_ref()?;
+                let iface = interfaces.iter().find(|iface| iface.interface_name == *interface)?;
+                match self {
+                    Metric::NetworkRx(_) => Some(iface.received_bytes_per_sec),
+                    _ => Some(iface.transmitted_bytes_per_sec),
+                }
+            }
+            Metric::GpuTemperature(index) | Metric::GpuUtilization(index) | Metric::GpuMemory(index) => {
+                let GpuData::Nvidia(gpus) = state.gpu.as_ref()?;
+                let gpu = gpus.get(*index)?;
+                match self {
+                    Metric::GpuTemperature(_) => Some(gpu.temperature_celsius as f64),
+                    Metric::GpuUtilization(_) => Some(gpu.utilization_percent as f64),
+                    _ => (gpu.memory_total_mb > 0)
+                        .then(|| gpu.memory_used_mb as f64 / gpu.memory_total_mb as f64 * 100.0),
+                }
+            }
+            Metric::Sensor { chip, label } => {
+                let SensorData::Global(readings) = state.sensors.as_ref()?;
+                readings.iter().find(|r| r.chip == *chip && r.label == *label).map(|reading| reading.value)
+            }
+            Metric::Pressure(resource) => {
+                let PressureData::Global(pressure) = state.pressure.as_ref()?;
+                let (_, psi) = pressure.system.resources().into_iter().find(|(name, _)| name == resource)?;
+                psi.map(|psi| psi.some.avg10)
+            }
+        }
+    }
+}
+
+impl Unit {
+    pub fn format(&self, value: f64) -> String {
+        match self {
+            Unit::Percent => format!("{:.1}%", value),
+            Unit::Celsius => format!("{:.0}°C", value),
+            Unit::BytesPerSec => format_rate(value),
+            Unit::Number => format!("{:.2}", value),
+            Unit::Processes => format!("{:.0} running", value),
+        }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Rule, String> {
+        let tokens = tokenize(text)?;
+        let mut tokens = tokens.iter().map(String::as_str);
+        let mut next = |what: &str| tokens.next().ok_or_else(|| format!("expected {}", what));
+
+        let condition = match next("a metric")? {
+            "process" => {
+                let name = next("a process name")?.to_string();
+                match next("`missing`")? {
+                    "missing" => Condition::ProcessMissing(name),
+                    other => return Err(format!("expected `missing` after the process name, found `{}`", other)),
+                }
+            }
+            first => {
+                let metric = parse_metric(first, &mut next)?;
+                let comparison = match next("a comparison")? {
+                    ">" => Comparison::Above,
+                    ">=" => Comparison::AtLeast,
+                    "<" => Comparison::Below,
+                    "<=" => Comparison::AtMost,
+                    other => return Err(format!("expected one of >, >=, <, <=, found `{}`", other)),
+                };
+                let threshold = parse_value(next("a threshold")?, metric.unit())?;
+                Condition::Threshold { metric, comparison, threshold }
+            }
+        };
+
+        let duration = match tokens.next() {
+            None => Duration::ZERO,
+            Some("for") => parse_duration(tokens.next().ok_or("expected a duration after `for`")?)?,
+            Some(other) => return Err(format!("unexpected `{}`", other)),
+        };
+        if let Some(extra) = tokens.next() {
+            return Err(format!("unexpected `{}`", extra));
+        }
+        Ok(Rule { condition, duration })
+    }
+}
+
+fn parse_metric<'a>(first: &str, next: &mut impl FnMut(&str) -> Result<&'a str, String>) -> Result<Metric, String> {
+    let metric = match first {
+        "filesystem" => {
+            let mount_point = next("a mount point")?.to_string();
+            match next("`used` or `inodes`")? {
+                "used" => Metric::FilesystemUsed(mount_point),
+                "inodes" => Metric::FilesystemInodes(mount_point),
+                other => return Err(format!("expected `used` or `inodes`, found `{}`", other)),
+            }
+        }
+        "disk" => {
+            let device = next("a device name")?.to_string();
+            match next("`utilization`")? {
+                "utilization" => Metric::DiskUtilization(device),
+                other => return Err(format!("expected `utilization`, found `{}`", other)),
+            }
+        }
+        "network" => {
+            let interface = next("an interface name")?.to_string();
+            match next("`rx` or `tx`")? {
+                "rx" => Metric::NetworkRx(interface),
+                "tx" => Metric::NetworkTx(interface),
+                other => return Err(format!("expected `rx` or `tx`, found `{}`", other)),
+            }
+        }
+        "sensor" => {
+            let name = next("a sensor as chip/label")?;
+            let (chip, label) = name.split_once('/').ok_or_else(|| format!("expected chip/label, found `{}`", name))?;
+            Metric::Sensor { chip: chip.to_string(), label: label.to_string() }
+        }
+        "cpu.total" => Metric::CpuTotal,
+        "cpu.iowait" => Metric::CpuIowait,
+        "cpu.steal" => Metric::CpuSteal,
+        "memory.used" => Metric::MemoryUsed,
+        "swap.used" => Metric::SwapUsed,
+        "load.1" => Metric::Load(1),
+        "load.5" => Metric::Load(5),
+        "load.15" => Metric::Load(15),
+        "pressure.cpu" => Metric::Pressure("cpu"),
+        "pressure.memory" => Metric::Pressure("memory"),
+        "pressure.io" => Metric::Pressure("io"),
+        name => {
+            let unknown = || format!("unknown metric `{}`", name);
+            if let Some(core) = name.strip_prefix("cpu.core") {
+                Metric::CpuCore(core.parse().map_err(|_| unknown())?)
+            } else if let Some((gpu, field)) = name.strip_prefix("gpu").and_then(|rest| rest.split_once('.')) {
+                let index = gpu.parse().map_err(|_| unknown())?;
+                match field {
+                    "temperature" => Metric::GpuTemperature(index),
+                    "utilization" => Metric::GpuUtilization(index),
+                    "memory" => Metric::GpuMemory(index),
+                    _ => return Err(unknown()),
+                }
+            } else {
+                return Err(unknown());
+            }
+        }
+    };
+    Ok(metric)
+}
+
+// Splits on whitespace, keeping double-quoted runs together.
+fn tokenize(text: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut token = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some(c) => token.push(c),
+                    None => return Err("unterminated quote".to_string()),
+                }
+            }
+            tokens.push(token);
+        } else {
+            let mut token = String::new();
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                token.push(c);
+            }
+            tokens.push(token);
+        }
+    }
+    Ok(tokens)
+}
+
+// Percentages may carry a `%`; rates may carry a byte unit, e.g. `10MiB/s`.
+fn parse_value(text: &str, unit: Unit) -> Result<f64, String> {
+    let split = text.find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-')).unwrap_or(text.len());
+    let (number, suffix) = text.split_at(split);
+    let value: f64 = number.parse().map_err(|_| format!("invalid number `{}`", text))?;
+    let multiplier = match (unit, suffix.strip_suffix("/s").unwrap_or(suffix)) {
+        (_, "") => 1.0,
+        (Unit::Percent, "%") => 1.0,
+        (Unit::BytesPerSec, "B") => 1.0,
+        (Unit::BytesPerSec, "KiB") => 1024.0,
+        (Unit::BytesPerSec, "MiB") => 1024.0 * 1024.0,
+        (Unit::BytesPerSec, "GiB") => 1024.0 * 1024.0 * 1024.0,
+        _ => return Err(format!("unexpected unit in `{}`", text)),
+    };
+    Ok(value * multiplier)
+}
+
+fn parse_duration(text: &str) -> Result<Duration, String> {
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (number, suffix) = text.split_at(split);
+    let value: u64 = number.parse().map_err(|_| format!("invalid duration `{}`", text))?;
+    let multiplier = match suffix {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        _ => return Err(format!("invalid duration `{}`, expected e.g. 30s, 5m or 1h", text)),
+    };
+    let secs = value.checked_mul(multiplier).ok_or_else(|| format!("duration too large: `{}`", text))?;
+    Ok(Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Rule {
+        text.parse().unwrap_or_else(|e| panic!("`{}`: {}", text, e))
+    }
+
+    fn parse_error(text: &str) -> String {
+        text.parse::<Rule>().expect_err(text)
+    }
+
+    fn threshold(metric: Metric, comparison: Comparison, threshold: f64) -> Condition {
+        Condition::Threshold { metric, comparison, threshold }
+    }
+
+    fn cpu(total: f32) -> CpuData {
+        CpuData::GlobalCpuMetrics(GlobalCpuMetrics {
+            total_usage_percent: total,
+            core_usages_percent: vec![total],
+            core_frequencies_mhz: vec![0],
+            times: None,
+        })
+    }
+
+    fn filesystem(mount_point: &str, used_bytes: u64, available_bytes: u64) -> FilesystemMetrics {
+        FilesystemMetrics {
+            mount_point: mount_point.to_string(),
+            device: "/dev/sda1".to_string(),
+            fs_type: "ext4".to_string(),
+            total_bytes: used_bytes + available_bytes,
+            used_bytes,
+            available_bytes,
+            total_inodes: 0,
+            used_inodes: 0,
+            free_inodes: 0,
+        }
+    }
+
+    fn gpu(temperature_celsius: u32) -> GpuMetrics {
+        GpuMetrics {
+            name: "Test GPU".to_string(),
+            memory_total_mb: 8192,
+            memory_used_mb: 1024,
+            utilization_percent: 10,
+            temperature_celsius,
+            fan_speed_percent: 30,
+        }
+    }
+
+    fn process(name: &str, is_thread: bool) -> ProcessInfo {
+        ProcessInfo {
+            pid: 100,
+            ppid: Some(1),
+            name: name.to_string(),
+            cmd: vec![name.to_string()],
+            exe: None,
+            cwd: None,
+            cpu_usage_percent: 0.0,
+            memory_usage_kb: 0,
+            virtual_memory_kb: 0,
+            status: ProcessStatus::Sleep,
+            is_thread,
+            thread_count: None,
+            user: None,
+            group: None,
+            start_time_secs: 0,
+            run_time_secs: 0,
+            disk_read_bytes_total: 0,
+            disk_written_bytes_total: 0,
+            disk_read_bytes_per_sec: 0.0,
+            disk_written_bytes_per_sec: 0.0,
+        }
+    }
+
+    #[test]
+    fn tokenize_splits_on_whitespace_and_keeps_quoted_runs() {
+        assert_eq!(tokenize("  cpu.total >\t90% ").unwrap(), ["cpu.total", ">", "90%"]);
+        assert_eq!(
+            tokenize(r#"sensor "coretemp/Package id 0" > 90"#).unwrap(),
+            ["sensor", "coretemp/Package id 0", ">", "90"]
+        );
+        assert_eq!(tokenize(r#"process "" missing"#).unwrap(), ["process", "", "missing"]);
+        assert_eq!(tokenize("").unwrap(), Vec::<String>::new());
+        assert_eq!(tokenize(r#"process "my app missing"#).unwrap_err(), "unterminated quote");
+    }
+
+    #[test]
+    fn parses_every_metric() {
+        let cases = [
+            ("cpu.total", Metric::CpuTotal),
+            ("cpu.core3", Metric::CpuCore(3)),
+            ("cpu.iowait", Metric::CpuIowait),
+            ("cpu.steal", Metric::CpuSteal),
+            ("memory.used", Metric::MemoryUsed),
+            ("swap.used", Metric::SwapUsed),
+            ("load.1", Metric::Load(1)),
+            ("load.5", Metric::Load(5)),
+            ("load.15", Metric::Load(15)),
+            ("filesystem /home used", Metric::FilesystemUsed("/home".to_string())),
+            ("filesystem / inodes", Metric::FilesystemInodes("/".to_string())),
+            ("disk nvme0n1 utilization", Metric::DiskUtilization("nvme0n1".to_string())),
+            ("network eth0 rx", Metric::NetworkRx("eth0".to_string())),
+            ("network eth0 tx", Metric::NetworkTx("eth0".to_string())),
+            ("gpu1.temperature", Metric::GpuTemperature(1)),
+            ("gpu0.utilization", Metric::GpuUtilization(0)),
+            ("gpu0.memory", Metric::GpuMemory(0)),
+            (
+                r#"sensor "coretemp/Package id 0""#,
+                Metric::Sensor { chip: "coretemp".to_string(), label: "Package id 0".to_string() },
+            ),
+            ("pressure.cpu", Metric::Pressure("cpu")),
+            ("pressure.memory", Metric::Pressure("memory")),
+            ("pressure.io", Metric::Pressure("io")),
+        ];
+        for (text, metric) in cases {
+            let rule = parse(&format!("{} > 1", text));
+            assert_eq!(rule.condition, threshold(metric, Comparison::Above, 1.0), "{}", text);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_metrics() {
+        for text in ["cpu.bogus > 1", "cpu.corex > 1", "gpu.temperature > 1", "gpu0.fan > 1", "nonsense > 1"] {
+            let metric = text.split_once(' ').unwrap().0;
+            assert_eq!(parse_error(text), format!("unknown metric `{}`", metric));
+        }
+        assert_eq!(parse_error("filesystem / free > 1"), "expected `used` or `inodes`, found `free`");
+        assert_eq!(parse_error("network eth0 both > 1"), "expected `rx` or `tx`, found `both`");
+        assert_eq!(parse_error("disk sda busy > 1"), "expected `utilization`, found `busy`");
+        assert_eq!(parse_error("sensor coretemp > 1"), "expected chip/label, found `coretemp`");
+    }
+
+    #[test]
+    fn parses_comparisons() {
+        for (op, comparison) in [
+            (">", Comparison::Above),
+            (">=", Comparison::AtLeast),
+            ("<", Comparison::Below),
+            ("<=", Comparison::AtMost),
+        ] {
+            let rule = parse(&format!("load.1 {} 4", op));
+            assert_eq!(rule.condition, threshold(Metric::Load(1), comparison, 4.0), "{}", op);
+        }
+        assert_eq!(parse_error("load.1 == 4"), "expected one of >, >=, <, <=, found `==`");
+    }
+
+    #[test]
+    fn parses_values_with_their_units() {
+        assert_eq!(parse_value("90", Unit::Percent), Ok(90.0));
+        assert_eq!(parse_value("90%", Unit::Percent), Ok(90.0));
+        assert_eq!(parse_value("2.5", Unit::Number), Ok(2.5));
+        assert_eq!(parse_value("-5", Unit::Celsius), Ok(-5.0));
+        assert_eq!(parse_value("100B/s", Unit::BytesPerSec), Ok(100.0));
+        assert_eq!(parse_value("10KiB/s", Unit::BytesPerSec), Ok(10.0 * 1024.0));
+        assert_eq!(parse_value("10MiB", Unit::BytesPerSec), Ok(10.0 * 1024.0 * 1024.0));
+        assert_eq!(parse_value("1.5GiB/s", Unit::BytesPerSec), Ok(1.5 * 1024.0 * 1024.0 * 1024.0));
+
+        assert_eq!(parse_value("90%", Unit::Celsius), Err("unexpected unit in `90%`".to_string()));
+        assert_eq!(parse_value("10MiB/s", Unit::Percent), Err("unexpected unit in `10MiB/s`".to_string()));
+        assert_eq!(parse_value("10MB/s", Unit::BytesPerSec), Err("unexpected unit in `10MB/s`".to_string()));
+        assert_eq!(parse_value("%", Unit::Percent), Err("invalid number `%`".to_string()));
+        assert_eq!(parse_value("1.2.3", Unit::Number), Err("invalid number `1.2.3`".to_string()));
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse("cpu.total > 90").duration, Duration::ZERO);
+        assert_eq!(parse("cpu.total > 90 for 30s").duration, Duration::from_secs(30));
+        assert_eq!(parse("cpu.total > 90 for 5m").duration, Duration::from_secs(300));
+        assert_eq!(parse("cpu.total > 90 for 2h").duration, Duration::from_secs(7200));
+
+        assert_eq!(parse_error("cpu.total > 90 for"), "expected a duration after `for`");
+        assert_eq!(parse_error("cpu.total > 90 for 30"), "invalid duration `30`, expected e.g. 30s, 5m or 1h");
+        assert_eq!(parse_error("cpu.total > 90 for 1d"), "invalid duration `1d`, expected e.g. 30s, 5m or 1h");
+        assert_eq!(parse_error("cpu.total > 90 for s"), "invalid duration `s`");
+        let huge = format!("{}h", u64::MAX / 60);
+        assert_eq!(parse_duration(&huge), Err(format!("duration too large: `{}`", huge)));
+        assert_eq!(parse_duration(&format!("{}s", u64::MAX)), Ok(Duration::from_secs(u64::MAX)));
+    }
+
+    #[test]
+    fn rejects_incomplete_and_trailing_input() {
+        assert_eq!(parse_error(""), "expected a metric");
+        assert_eq!(parse_error("cpu.total"), "expected a comparison");
+        assert_eq!(parse_error("cpu.total >"), "expected a threshold");
+        assert_eq!(parse_error("cpu.total > 90 during 30s"), "unexpected `during`");
+        assert_eq!(parse_error("cpu.total > 90 for 30s please"), "unexpected `please`");
+        assert_eq!(parse_error("process"), "expected a process name");
+        assert_eq!(parse_error("process nginx"), "expected `missing`");
+        assert_eq!(parse_error("process nginx running"), "expected `missing` after the process name, found `running`");
+    }
+
+    #[test]
+    fn quotes_process_names() {
+        let rule = parse(r#"process "Web Content" missing for 1m"#);
+        assert_eq!(rule.condition, Condition::ProcessMissing("Web Content".to_string()));
+        assert_eq!(rule.duration, Duration::from_secs(60));
+    }
+
+    #[test]
+    fn evaluates_example_rules() {
+        let state = AppState {
+            cpu: Some(cpu(93.0)),
+            filesystems: Some(FilesystemData::Global(vec![filesystem("/", 96, 4), filesystem("/home", 10, 90)])),
+            gpu: Some(GpuData::Nvidia(vec![gpu(80)])),
+            processes: Some(vec![process("sshd", false), process("nginx", true)]),
+            ..AppState::default()
+        };
+
+        // Rule, parsed condition, duration, value seen, condition met.
+        let cases = [
+            (
+                "cpu.total > 90% for 30s",
+                threshold(Metric::CpuTotal, Comparison::Above, 90.0),
+                Duration::from_secs(30),
+                Some(93.0),
+                true,
+            ),
+            (
+                "filesystem / used > 95%",
+                threshold(Metric::FilesystemUsed("/".to_string()), Comparison::Above, 95.0),
+                Duration::ZERO,
+                Some(96.0),
+                true,
+            ),
+            (
+                "filesystem /home used > 95%",
+                threshold(Metric::FilesystemUsed("/home".to_string()), Comparison::Above, 95.0),
+                Duration::ZERO,
+                Some(10.0),
+                false,
+            ),
+            (
+                "gpu0.temperature > 85",
+                threshold(Metric::GpuTemperature(0), Comparison::Above, 85.0),
+                Duration::ZERO,
+                Some(80.0),
+                false,
+            ),
+            ("gpu1.temperature > 85", threshold(Metric::GpuTemperature(1), Comparison::Above, 85.0), Duration::ZERO, None, false),
+            // Only a thread is called nginx, which doesn't count.
+            ("process nginx missing", Condition::ProcessMissing("nginx".to_string()), Duration::ZERO, Some(0.0), true),
+            ("process sshd missing", Condition::ProcessMissing("sshd".to_string()), Duration::ZERO, Some(1.0), false),
+        ];
+        for (text, condition, duration, value, met) in cases {
+            let rule = parse(text);
+            assert_eq!(rule, Rule { condition, duration }, "{}", text);
+            let evaluation = rule.evaluate(&state, rule.default_hysteresis());
+            assert_eq!(evaluation.value, value, "{}", text);
+            assert_eq!(evaluation.met, met, "{}", text);
+        }
+    }
+
+    #[test]
+    fn finds_processes_with_long_names() {
+        // Running as `/usr/lib/systemd/systemd-resolved`, seen by the kernel
+        // as `systemd-resolve`.
+        let resolved = ProcessInfo {
+            exe: Some("/usr/lib/systemd/systemd-resolved".to_string()),
+            cmd: vec!["/usr/lib/systemd/systemd-resolved".to_string()],
+            ..process("systemd-resolve", false)
+        };
+        // Another user's process, whose executable path can't be read.
+        let exporter = ProcessInfo {
+            cmd: vec!["./prometheus-node-exporter".to_string(), "--web.listen-address=:9100".to_string()],
+            ..process("prometheus-node", false)
+        };
+        let state = AppState { processes: Some(vec![resolved, exporter]), ..AppState::default() };
+
+        for (name, running) in [
+            ("systemd-resolved", 1.0),
+            ("systemd-resolve", 1.0),
+            ("prometheus-node-exporter", 1.0),
+            ("systemd", 0.0),
+            ("resolved", 0.0),
+        ] {
+            let evaluation = parse(&format!("process {} missing", name)).evaluate(&state, 0.0);
+            assert_eq!(evaluation.value, Some(running), "{}", name);
+        }
+    }
+
+    #[test]
+    fn waits_for_a_process_list() {
+        let evaluation = parse("process nginx missing").evaluate(&AppState::default(), 0.0);
+        assert!(evaluation.value.is_none() && !evaluation.met && !evaluation.cleared);
+    }
+
+    #[test]
+    fn clears_only_past_the_hysteresis_band() {
+        let mut state = AppState::default();
+        let above = parse("cpu.total > 90");
+        let below = parse("cpu.total < 10");
+        for (total, above_met, above_cleared, below_met, below_cleared) in [
+            (95.0, true, false, false, true),
+            (88.0, false, false, false, true),
+            (85.0, false, true, false, true),
+            (12.0, false, true, false, false),
+            (5.0, false, true, true, false),
+        ] {
+            state.cpu = Some(cpu(total));
+            let evaluation = above.evaluate(&state, 5.0);
+            assert_eq!((evaluation.met, evaluation.cleared), (above_met, above_cleared), "> 90 at {}", total);
+            let evaluation = below.evaluate(&state, 5.0);
+            assert_eq!((evaluation.met, evaluation.cleared), (below_met, below_cleared), "< 10 at {}", total);
+        }
+        // Losing the data neither meets nor clears the condition.
+        state.cpu = None;
+        let evaluation = above.evaluate(&state, 5.0);
+        assert!(!evaluation.met && !evaluation.cleared);
+    }
+
+    #[test]
+    fn default_hysteresis_is_a_twentieth_of_the_threshold() {
+        assert_eq!(parse("cpu.total > 90").default_hysteresis(), 4.5);
+        assert_eq!(parse("gpu0.temperature > -20").default_hysteresis(), 1.0);
+        assert_eq!(parse("process nginx missing").default_hysteresis(), 0.0);
+    }
+}
@@ -1,37 +1,265 @@
+use crate::alerting::AlertEngine;
+use crate::alerting::actions::AlertNotifier;
+use crate::config::{MAX_REFRESH_INTERVAL_MS, MIN_REFRESH_INTERVAL_MS};
+use crate::data_sources::channel::UpdateReceiver;
+use crate::data_sources::scheduler::IntervalHandle;
+use crate::data_sources::self_usage::SelfMonitor;
+use crate::error::AppError;
+use crate::output::OutputSink;
+use crate::replay::Replay;
+use crate::shutdown::ShutdownSignal;
 use crate::state::app_state::AppState;
-use crate::state::data_types::AppStateUpdate;
-use std::sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}};
-use crossbeam_channel::Receiver;
+use crate::state::data_types::{AppStateUpdate, DataSource, Sample};
+use crate::ui::Tui;
+use crate::ui::input_handlers::InputAction;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use crossbeam_channel::{Receiver, at, never, select, tick};
+use crossterm::event::Event;
 
 pub struct AppCore {
     pub state: Arc<Mutex<AppState>>,
-    pub update_receiver: Receiver<AppStateUpdate>,
-    pub shutdown_signal: Arc<AtomicBool>,
+    pub updates: UpdateReceiver,
+    pub input_receiver: Receiver<Event>,
+    pub shutdown_signal: ShutdownSignal,
+    pub intervals: HashMap<DataSource, IntervalHandle>,
+    sinks: Vec<Box<dyn OutputSink>>,
+    snapshot_ticker: Receiver<Instant>,
+    // Set with `--replay`; updates then come from the recording instead.
+    replay: Option<Replay>,
+    history_capacity: usize,
+    alerts: AlertEngine,
+    notifier: AlertNotifier,
+    self_monitor: SelfMonitor,
 }
 
 impl AppCore {
-    pub fn new(update_receiver: Receiver<AppStateUpdate>, shutdown_signal: Arc<AtomicBool>) -> Self {
+    pub fn new(
+        updates: UpdateReceiver,
+        input_receiver: Receiver<Event>,
+        shutdown_signal: ShutdownSignal,
+        intervals: HashMap<DataSource, IntervalHandle>,
+        history_capacity: usize,
+        snapshot_interval: Duration,
+        alerts: AlertEngine,
+    ) -> Self {
+        let mut state = AppState {
+            refresh_intervals_ms: intervals.iter().map(|(source, interval)| (*source, interval.get_ms())).collect(),
+            ..AppState::new(history_capacity)
+        };
+        alerts.reset(&mut state);
         Self {
-            state: Arc::new(Mutex::new(AppState::default())),
-            update_receiver,
+            state: Arc::new(Mutex::new(state)),
+            updates,
+            input_receiver,
             shutdown_signal,
+            intervals,
+            sinks: Vec::new(),
+            snapshot_ticker: tick(snapshot_interval),
+            replay: None,
+            history_capacity,
+            alerts,
+            notifier: AlertNotifier::default(),
+            self_monitor: SelfMonitor::new(),
         }
     }
 
-    pub fn run(&self) {
-        while !self.shutdown_signal.load(Ordering::Relaxed) {
-            if let Ok(update) = self.update_receiver.recv() {
-                let mut state = self.state.lock().unwrap();
-                match update {
-                    AppStateUpdate::Cpu(data) => state.cpu = Some(data),
-                    AppStateUpdate::Processes(data) => state.processes = Some(data),
-                    AppStateUpdate::Memory(data) => state.memory = Some(data),
-                    AppStateUpdate::Network(data) => state.network = Some(data),
-                    AppStateUpdate::Gpu(data) => state.gpu = Some(data),
-                    AppStateUpdate::SystemStats(data) => state.system_stats = Some(data),
+    /// Plays `replay` back instead of waiting on the collectors.
+    pub fn set_replay(&mut self, replay: Replay) {
+        self.state.lock().unwrap().replay = Some(replay.status());
+        self.replay = Some(replay);
+    }
+
+    /// Passes alert transitions on to the configured alert actions.
+    pub fn set_alert_notifier(&mut self, notifier: AlertNotifier) {
+        self.notifier = notifier;
+    }
+
+    /// Adds an output that receives every update alongside the TUI.
+    pub fn add_sink(&mut self, sink: Box<dyn OutputSink>) {
+        self.sinks.push(sink);
+    }
+
+    /// Runs until quit or shutdown. Without a `tui` the app runs headless and
+    /// only feeds the output sinks, which are flushed before returning.
+    pub fn run(&mut self, tui: Option<&mut Tui>) -> Result<(), AppError> {
+        let result = self.event_loop(tui);
+        let state = self.state.lock().unwrap();
+        let finished = self.sinks.iter_mut().try_for_each(|sink| sink.finish(&state));
+        result.and(finished)
+    }
+
+    fn event_loop(&mut self, mut tui: Option<&mut Tui>) -> Result<(), AppError> {
+        self.redraw(&mut tui)?;
+        while !self.shutdown_signal.is_set() {
+            let replay_timer = match self.replay.as_ref().and_then(Replay::next_deadline) {
+                Some(deadline) => at(deadline),
+                None => never(),
+            };
+            select! {
+                recv(self.shutdown_signal.receiver()) -> _ => break,
+                recv(self.updates.ready()) -> ready => {
+                    let Ok(()) = ready else { break };
+                    // Everything queued since the last wake-up results in a
+                    // single redraw.
+                    for update in self.updates.drain() {
+                        self.apply_update(update)?;
+                    }
+                    self.sync_collector_stats(&mut self.state.lock().unwrap());
+                    self.redraw(&mut tui)?;
+                }
+                recv(replay_timer) -> _ => {
+                    self.play_due()?;
+                    // Headless playback has nothing left to do at the end.
+                    if tui.is_none() && self.replay.as_ref().is_some_and(Replay::is_finished) {
+                        break;
+                    }
+                    self.redraw(&mut tui)?;
+                }
+                recv(self.snapshot_ticker) -> _ => {
+                    let mut state = self.state.lock().unwrap();
+                    // Failing collectors send nothing, so their health is
+                    // picked up here rather than with the updates.
+                    self.sync_collector_stats(&mut state);
+                    if let Some(usage) = self.self_monitor.sample() {
+                        state.self_usage = Some(usage);
+                    }
+                    for sink in &mut self.sinks {
+                        sink.on_tick(&state)?;
+                    }
+                    // Keeps the replay position moving between sparse updates.
+                    if let Some(replay) = &self.replay {
+                        state.replay = Some(replay.status());
+                        drop(state);
+                        self.redraw(&mut tui)?;
+                    }
                 }
-                // TODO: trigger UI redraw here
+                recv(self.input_receiver) -> event => {
+                    let Ok(event) = event else { break };
+                    let Some(tui) = tui.as_deref_mut() else { continue };
+                    let action = tui.handle_event(&event, &self.state.lock().unwrap());
+                    match action {
+                        InputAction::Quit => self.shutdown_signal.trigger(),
+                        InputAction::Redraw => tui.draw(&self.state.lock().unwrap())?,
+                        InputAction::FasterRefresh => {
+                            self.scale_intervals(|ms| ms / 2);
+                            tui.draw(&self.state.lock().unwrap())?;
+                        }
+                        InputAction::SlowerRefresh => {
+                            self.scale_intervals(|ms| ms * 2);
+                            tui.draw(&self.state.lock().unwrap())?;
+                        }
+                        InputAction::TogglePause | InputAction::CycleSpeed | InputAction::SeekBackward
+                        | InputAction::SeekForward | InputAction::SeekBackwardFar | InputAction::SeekForwardFar => {
+                            self.control_replay(action)?;
+                            tui.draw(&self.state.lock().unwrap())?;
+                        }
+                        // Everything else is handled inside the UI.
+                        _ => {}
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_update(&mut self, update: Sample<AppStateUpdate>) -> Result<(), AppError> {
+        let mut state = self.state.lock().unwrap();
+        let (source, timestamp) = (update.data.source(), update.timestamp);
+        if self.sinks.is_empty() {
+            state.apply(update);
+        } else {
+            state.apply(update.clone());
+            for sink in &mut self.sinks {
+                sink.on_update(&update, &state)?;
             }
         }
+        for transition in self.alerts.evaluate(source, timestamp, &mut state) {
+            self.notifier.notify(&transition);
+        }
+        Ok(())
+    }
+
+    fn sync_collector_stats(&self, state: &mut AppState) {
+        for (source, dropped) in self.updates.dropped() {
+            if dropped > 0 {
+                state.dropped_updates.insert(source, dropped);
+            }
+        }
+        // Sources that never started have nothing to report.
+        for (source, health) in self.updates.health() {
+            if !state.unavailable.contains_key(&source) {
+                state.collector_health.insert(source, health);
+            }
+        }
+    }
+
+    fn play_due(&mut self) -> Result<(), AppError> {
+        let Some(replay) = &mut self.replay else { return Ok(()) };
+        let samples = replay.due()?;
+        let status = replay.status();
+        for sample in samples {
+            self.apply_update(sample)?;
+        }
+        self.state.lock().unwrap().replay = Some(status);
+        Ok(())
+    }
+
+    // Playback controls; does nothing outside a replay.
+    fn control_replay(&mut self, action: InputAction) -> Result<(), AppError> {
+        const MINUTE_MS: i64 = 60 * 1000;
+        let Some(replay) = &mut self.replay else { return Ok(()) };
+        let delta_ms = match action {
+            InputAction::TogglePause => {
+                replay.toggle_pause();
+                self.state.lock().unwrap().replay = Some(replay.status());
+                return Ok(());
+            }
+            InputAction::CycleSpeed => {
+                replay.cycle_speed();
+                self.state.lock().unwrap().replay = Some(replay.status());
+                return Ok(());
+            }
+            InputAction::SeekBackward => -MINUTE_MS,
+            InputAction::SeekForward => MINUTE_MS,
+            InputAction::SeekBackwardFar => -10 * MINUTE_MS,
+            InputAction::SeekForwardFar => 10 * MINUTE_MS,
+            _ => return Ok(()),
+        };
+
+        // Rebuild from scratch so values and history from the old position
+        // don't linger. Sinks already saw these updates, so they are skipped.
+        let samples = replay.seek(delta_ms)?;
+        let mut state = self.state.lock().unwrap();
+        let refresh_intervals_ms = std::mem::take(&mut state.refresh_intervals_ms);
+        *state = AppState { refresh_intervals_ms, ..AppState::new(self.history_capacity) };
+        self.alerts.reset(&mut state);
+        for sample in samples {
+            let (source, timestamp) = (sample.data.source(), sample.timestamp);
+            state.apply(sample);
+            self.alerts.evaluate(source, timestamp, &mut state);
+        }
+        state.replay = Some(replay.status());
+        Ok(())
+    }
+
+    // Applies `scale` to every collector's interval; the collector threads
+    // pick up the new value on their next tick.
+    fn scale_intervals(&self, scale: impl Fn(u64) -> u64) {
+        let mut state = self.state.lock().unwrap();
+        for (source, interval) in &self.intervals {
+            let new_ms = scale(interval.get_ms()).clamp(MIN_REFRESH_INTERVAL_MS, MAX_REFRESH_INTERVAL_MS);
+            interval.set_ms(new_ms);
+            state.refresh_intervals_ms.insert(*source, new_ms);
+            log::info!("{} refresh interval set to {} ms", source, new_ms);
+        }
+    }
+
+    fn redraw(&self, tui: &mut Option<&mut Tui>) -> Result<(), AppError> {
+        match tui {
+            Some(tui) => tui.draw(&self.state.lock().unwrap()),
+            None => Ok(()),
+        }
     }
 }
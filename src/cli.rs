@@ -0,0 +1,33 @@
+use crate::output::json::JsonMode;
+
+use clap::Parser;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+/// Terminal system monitor.
+#[derive(Debug, Parser)]
+#[command(name = "monoxide", version)]
+pub struct Cli {
+    /// Path to the config file. Defaults to `$XDG_CONFIG_HOME/monoxide/config.toml`.
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Run without the TUI and write newline-delimited JSON to stdout: one
+    /// record per update, or a merged snapshot per refresh interval.
+    #[arg(long, value_name = "MODE", num_args = 0..=1, default_missing_value = "updates")]
+    pub json: Option<JsonMode>,
+
+    /// Serve Prometheus metrics on this address, e.g. `127.0.0.1:9184`.
+    /// Overrides `prometheus.listen` from the config.
+    #[arg(long, value_name = "ADDR")]
+    pub prometheus: Option<SocketAddr>,
+
+    /// Append every update to this file for later `--replay`.
+    #[arg(long, value_name = "FILE", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Play back a recording made with `--record` instead of collecting live
+    /// metrics.
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<PathBuf>,
+}
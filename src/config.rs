@@ -1,4 +1,333 @@
-#[derive(Debug, Clone, Default)]
+use crate::alerting::{AlertEngine, Severity};
+use crate::error::AppError;
+use crate::state::app_state::DEFAULT_HISTORY_CAPACITY;
+use crate::state::data_types::DataSource;
+use crate::ui::input_handlers::KeyMap;
+use crate::ui::theme::Theme;
+
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_REFRESH_INTERVAL_MS: u64 = 1000;
+// Anything faster than this just burns CPU without producing useful samples.
+pub const MIN_REFRESH_INTERVAL_MS: u64 = 10;
+pub const MAX_REFRESH_INTERVAL_MS: u64 = 60_000;
+const MAX_HISTORY_SIZE: usize = 100_000;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Config {
     pub refresh_interval_ms:u64,
-}
\ No newline at end of file
+    // Number of samples kept per metric for graphs and trends.
+    pub history_size: usize,
+    // Per-collector overrides, keyed by `DataSource::name`.
+    pub collectors: HashMap<String, CollectorConfig>,
+    pub processes: ProcessFilterConfig,
+    pub filesystems: FilesystemFilterConfig,
+    pub sensors: SensorsConfig,
+    pub pressure: PressureConfig,
+    pub alerts: AlertsConfig,
+    pub ui: UiConfig,
+    pub prometheus: PrometheusConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CollectorConfig {
+    pub enabled: bool,
+    // Falls back to the global `refresh_interval_ms` when unset.
+    pub refresh_interval_ms: Option<u64>,
+    // Random delay of up to this many ms added to each tick, to keep
+    // collectors from all waking at once.
+    pub jitter_ms: u64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProcessFilterConfig {
+    // Regexes; processes whose name matches any of them are not reported.
+    pub exclude_names: Vec<String>,
+    // Skip individual threads that sysinfo lists alongside processes.
+    pub hide_threads: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FilesystemFilterConfig {
+    // Filesystem types that are not reported. Replaces the default list of
+    // pseudo and in-memory filesystems when set.
+    pub exclude_types: Vec<String>,
+    // Regexes; filesystems whose mount point matches any of them are not reported.
+    pub exclude_mount_points: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SensorsConfig {
+    // Where sysfs is mounted; hwmon and thermal zones are read from below it.
+    // Useful when monitoring a host from inside a container.
+    pub sysfs_root: PathBuf,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PressureConfig {
+    // Cgroups to report PSI for, as paths below the cgroup2 mount, e.g.
+    // "system.slice/docker.service". Defaults to the top-level cgroups.
+    pub cgroups: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertsConfig {
+    pub rules: Vec<AlertRuleConfig>,
+    // What to do when alerts fire or resolve.
+    pub actions: Vec<AlertActionConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertRuleConfig {
+    pub name: String,
+    // e.g. "cpu.total > 90% for 30s"; see `alerting::rule::Rule` for the syntax.
+    pub rule: String,
+    #[serde(default)]
+    pub severity: Severity,
+    // How far back past the threshold the value must go, in the metric's own
+    // units, before a firing alert resolves. Defaults to 5% of the threshold.
+    pub hysteresis: Option<f64>,
+}
+
+/// One alert action. Exactly one of `command`, `log_file` and `webhook` is set.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertActionConfig {
+    // Run through `sh -c`, with the alert details in `MONOXIDE_ALERT_*` variables.
+    pub command: Option<String>,
+    // Append a line per notification to this file.
+    pub log_file: Option<PathBuf>,
+    // POST a JSON payload to this http:// or https:// URL.
+    pub webhook: Option<String>,
+    // Alert names this action is for; all alerts when empty.
+    pub alerts: Vec<String>,
+    // Also notify when an alert resolves.
+    pub resolved: bool,
+    // An alert that fires again within this long of its last notification is
+    // only reported once the interval is up, and only if it is still firing.
+    pub min_interval_secs: u64,
+    // Further attempts after a failed notification, with doubling backoff.
+    pub retries: u32,
+    // How long a command or webhook request may take before it counts as failed.
+    pub timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UiConfig {
+    pub theme: String,
+    // Action name to key, e.g. `quit = "x"`. Overrides the default binding.
+    pub keybindings: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PrometheusConfig {
+    // Address to serve `/metrics` on, e.g. "127.0.0.1:9184". Off when unset.
+    pub listen: Option<SocketAddr>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            refresh_interval_ms: DEFAULT_REFRESH_INTERVAL_MS,
+            history_size: DEFAULT_HISTORY_CAPACITY,
+            collectors: HashMap::new(),
+            processes: ProcessFilterConfig::default(),
+            filesystems: FilesystemFilterConfig::default(),
+            sensors: SensorsConfig::default(),
+            pressure: PressureConfig::default(),
+            alerts: AlertsConfig::default(),
+            ui: UiConfig::default(),
+            prometheus: PrometheusConfig::default(),
+        }
+    }
+}
+
+impl Default for CollectorConfig {
+    fn default() -> Self {
+        CollectorConfig { enabled: true, refresh_interval_ms: None, jitter_ms: 0 }
+    }
+}
+
+impl Default for FilesystemFilterConfig {
+    fn default() -> Self {
+        const PSEUDO_FILESYSTEMS: [&str; 24] = [
+            "autofs", "binfmt_misc", "bpf", "cgroup", "cgroup2", "configfs", "debugfs", "devpts",
+            "devtmpfs", "efivarfs", "fusectl", "hugetlbfs", "mqueue", "nsfs", "overlay", "proc",
+            "pstore", "ramfs", "rpc_pipefs", "securityfs", "selinuxfs", "sysfs", "tmpfs", "tracefs",
+        ];
+        FilesystemFilterConfig {
+            exclude_types: PSEUDO_FILESYSTEMS.iter().map(|name| name.to_string()).collect(),
+            exclude_mount_points: Vec::new(),
+        }
+    }
+}
+
+impl Default for SensorsConfig {
+    fn default() -> Self {
+        SensorsConfig { sysfs_root: PathBuf::from("/sys") }
+    }
+}
+
+impl Default for AlertActionConfig {
+    fn default() -> Self {
+        AlertActionConfig {
+            command: None,
+            log_file: None,
+            webhook: None,
+            alerts: Vec::new(),
+            resolved: true,
+            min_interval_secs: 300,
+            retries: 3,
+            timeout_secs: 10,
+        }
+    }
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        UiConfig { theme: "dark".to_string(), keybindings: HashMap::new() }
+    }
+}
+
+impl Config {
+    /// Loads the config from `path`, or from `$XDG_CONFIG_HOME/monoxide/config.toml`
+    /// when no path is given. A missing default file yields the defaults; a
+    /// missing explicit file is an error.
+    pub fn load(path: Option<&Path>) -> Result<Self, AppError> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match Self::default_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Config::default()),
+            },
+        };
+
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|e| AppError::ConfigFileError(format!("{}: {}", path.display(), e)))?;
+        let config: Config = toml::from_str(&contents)
+            .map_err(|e| AppError::ConfigFileError(format!("{}: {}", path.display(), e)))?;
+        config.validate()?;
+        log::info!("Loaded config from {}", path.display());
+        Ok(config)
+    }
+
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("monoxide").join("config.toml"))
+    }
+
+    pub fn validate(&self) -> Result<(), AppError> {
+        validate_interval("refresh_interval_ms", self.refresh_interval_ms)?;
+        if !(1..=MAX_HISTORY_SIZE).contains(&self.history_size) {
+            return Err(config_error("history_size", format!("must be between 1 and {}", MAX_HISTORY_SIZE)));
+        }
+
+        for (name, collector) in &self.collectors {
+            if DataSource::from_name(name).is_none() {
+                return Err(config_error(format!("collectors.{}", name), "unknown collector"));
+            }
+            if let Some(interval) = collector.refresh_interval_ms {
+                validate_interval(&format!("collectors.{}.refresh_interval_ms", name), interval)?;
+            }
+            if collector.jitter_ms >= self.refresh_interval_ms_for(name) {
+                return Err(config_error(
+                    format!("collectors.{}.jitter_ms", name),
+                    "must be smaller than the collector's refresh interval",
+                ));
+            }
+        }
+
+        for (index, pattern) in self.processes.exclude_names.iter().enumerate() {
+            if let Err(e) = Regex::new(pattern) {
+                return Err(config_error(format!("processes.exclude_names[{}]", index), e.to_string()));
+            }
+        }
+
+        for (index, pattern) in self.filesystems.exclude_mount_points.iter().enumerate() {
+            if let Err(e) = Regex::new(pattern) {
+                return Err(config_error(format!("filesystems.exclude_mount_points[{}]", index), e.to_string()));
+            }
+        }
+
+        for (index, cgroup) in self.pressure.cgroups.iter().enumerate() {
+            if Path::new(cgroup).components().any(|c| matches!(c, std::path::Component::ParentDir)) {
+                return Err(config_error(format!("pressure.cgroups[{}]", index), "must not contain `..`"));
+            }
+        }
+
+        AlertEngine::new(&self.alerts)?;
+        for (index, action) in self.alerts.actions.iter().enumerate() {
+            let key = |field: &str| format!("alerts.actions[{}].{}", index, field);
+            let targets = [action.command.is_some(), action.log_file.is_some(), action.webhook.is_some()];
+            if targets.iter().filter(|set| **set).count() != 1 {
+                return Err(config_error(
+                    format!("alerts.actions[{}]", index),
+                    "set exactly one of `command`, `log_file` and `webhook`",
+                ));
+            }
+            if let Some(url) = &action.webhook
+                && !(url.starts_with("http://") || url.starts_with("https://"))
+            {
+                return Err(config_error(key("webhook"), "must be an http:// or https:// URL"));
+            }
+            if let Some(name) = action.alerts.iter().find(|name| !self.alerts.rules.iter().any(|rule| rule.name == **name)) {
+                return Err(config_error(key("alerts"), format!("no alert rule named `{}`", name)));
+            }
+            if action.timeout_secs == 0 {
+                return Err(config_error(key("timeout_secs"), "must be at least 1"));
+            }
+        }
+
+        if Theme::from_name(&self.ui.theme).is_none() {
+            return Err(config_error(
+                "ui.theme",
+                format!("unknown theme `{}`, expected one of: {}", self.ui.theme, Theme::NAMES.join(", ")),
+            ));
+        }
+        KeyMap::from_config(&self.ui.keybindings)?;
+
+        Ok(())
+    }
+
+    pub fn is_collector_enabled(&self, name: &str) -> bool {
+        self.collectors.get(name).is_none_or(|c| c.enabled)
+    }
+
+    pub fn refresh_interval_ms_for(&self, name: &str) -> u64 {
+        self.collectors
+            .get(name)
+            .and_then(|c| c.refresh_interval_ms)
+            .unwrap_or(self.refresh_interval_ms)
+    }
+
+    pub fn jitter_ms_for(&self, name: &str) -> u64 {
+        self.collectors.get(name).map_or(0, |c| c.jitter_ms)
+    }
+}
+
+pub fn config_error(key: impl Into<String>, message: impl Into<String>) -> AppError {
+    AppError::ConfigError { key: key.into(), message: message.into() }
+}
+
+fn validate_interval(key: &str, interval_ms: u64) -> Result<(), AppError> {
+    if !(MIN_REFRESH_INTERVAL_MS..=MAX_REFRESH_INTERVAL_MS).contains(&interval_ms) {
+        return Err(config_error(
+            key,
+            format!("must be between {} and {} ms", MIN_REFRESH_INTERVAL_MS, MAX_REFRESH_INTERVAL_MS),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,198 @@
+use crate::error::AppError;
+use crate::state::app_state::CollectorHealth;
+use crate::state::data_types::{AppStateUpdate, DataSource, Sample, Timestamp};
+
+use crossbeam_channel::{bounded, Receiver, Sender, TrySendError};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+// Samples queued per collector. Each sample replaces the collector's previous
+// one in the state, so an older sample still waiting when a newer one arrives
+// would only be overwritten; keeping just the newest bounds memory (process
+// lists are large) when the app falls behind.
+const COLLECTOR_QUEUE_CAPACITY: usize = 1;
+
+/// The app's end of every collector's update channel. `ready` wakes the app
+/// whenever any collector has queued a sample, so it can wait on all of them
+/// in one `select!` arm and then `drain` each.
+#[derive(Debug, Clone)]
+pub struct UpdateReceiver {
+    // Kept so new sources can be added; also means `ready` never disconnects.
+    ready_sender: Sender<()>,
+    ready: Receiver<()>,
+    sources: Vec<SourceQueue>,
+}
+
+#[derive(Debug, Clone)]
+struct SourceQueue {
+    source: DataSource,
+    receiver: Receiver<Sample<AppStateUpdate>>,
+    dropped: Arc<AtomicU64>,
+    health: Arc<Mutex<CollectorHealth>>,
+}
+
+impl UpdateReceiver {
+    pub fn new() -> Self {
+        // A single pending wake-up covers any number of queued samples.
+        let (ready_sender, ready) = bounded(1);
+        UpdateReceiver { ready_sender, ready, sources: Vec::new() }
+    }
+
+    /// Creates the channel for `source` and returns its sending end.
+    pub fn add_source(&mut self, source: DataSource) -> UpdateSender {
+        let (sender, receiver) = bounded(COLLECTOR_QUEUE_CAPACITY);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let health = Arc::new(Mutex::new(CollectorHealth::default()));
+        self.sources.push(SourceQueue {
+            source,
+            receiver: receiver.clone(),
+            dropped: dropped.clone(),
+            health: health.clone(),
+        });
+        UpdateSender { sender, oldest: receiver, ready: self.ready_sender.clone(), dropped, health }
+    }
+
+    pub fn ready(&self) -> &Receiver<()> {
+        &self.ready
+    }
+
+    /// Takes every sample queued right now, from all sources.
+    pub fn drain(&self) -> Vec<Sample<AppStateUpdate>> {
+        self.sources.iter().flat_map(|queue| queue.receiver.try_iter()).collect()
+    }
+
+    /// Samples each source has dropped so far because the app fell behind.
+    pub fn dropped(&self) -> impl Iterator<Item = (DataSource, u64)> + '_ {
+        self.sources.iter().map(|queue| (queue.source, queue.dropped.load(Ordering::Relaxed)))
+    }
+
+    /// The latest health of each source's collector.
+    pub fn health(&self) -> impl Iterator<Item = (DataSource, CollectorHealth)> + '_ {
+        self.sources.iter().map(|queue| (queue.source, queue.health.lock().unwrap().clone()))
+    }
+}
+
+impl Default for UpdateReceiver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A collector's end of its update channel. Sending never blocks: when the
+/// queue is full the oldest sample is dropped to make room.
+pub struct UpdateSender {
+    sender: Sender<Sample<AppStateUpdate>>,
+    // Lets the sender evict from its own queue.
+    oldest: Receiver<Sample<AppStateUpdate>>,
+    ready: Sender<()>,
+    dropped: Arc<AtomicU64>,
+    health: Arc<Mutex<CollectorHealth>>,
+}
+
+impl UpdateSender {
+    /// Runs one collection, recording when it started, how long it took and
+    /// whether it failed in the source's health.
+    pub fn track<T>(&self, collect: impl FnOnce() -> Result<T, AppError>) -> Result<T, AppError> {
+        let started = Instant::now();
+        self.health.lock().unwrap().collecting_since = Some(Timestamp::at(started));
+        let result = collect();
+
+        let mut health = self.health.lock().unwrap();
+        health.collecting_since = None;
+        health.last_duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+        match &result {
+            Ok(_) => {
+                health.last_success = Some(Timestamp::now());
+                health.consecutive_failures = 0;
+            }
+            Err(e) => {
+                health.last_error = Some(e.to_string());
+                health.last_error_at = Some(Timestamp::now());
+                health.consecutive_failures += 1;
+            }
+        }
+        result
+    }
+
+    /// Queues `sample` and wakes the app. Never fails: the sender holds a
+    /// receiver of its own queue and the registry keeps the wake-up channel
+    /// open, so neither ever disconnects. Collectors stop through the
+    /// shutdown signal instead.
+    pub fn send<T: Into<AppStateUpdate>>(&self, sample: Sample<T>) {
+        let mut sample = sample.map(Into::into);
+        while let Err(TrySendError::Full(rejected)) = self.sender.try_send(sample) {
+            sample = rejected;
+            // The app may have taken it in the meantime, in which case there
+            // is room now and nothing was lost.
+            if self.oldest.try_recv().is_ok() {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        // A wake-up already pending covers this sample too.
+        let _ = self.ready.try_send(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unavailable(reason: &str) -> Sample<AppStateUpdate> {
+        let update = AppStateUpdate::SourceUnavailable { source: DataSource::Gpu, reason: reason.to_string() };
+        Sample::new(Timestamp::now(), update)
+    }
+
+    fn reasons(samples: Vec<Sample<AppStateUpdate>>) -> Vec<String> {
+        samples
+            .into_iter()
+            .map(|sample| match sample.data {
+                AppStateUpdate::SourceUnavailable { reason, .. } => reason,
+                other => panic!("unexpected update {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn keeps_the_newest_sample_and_counts_the_rest() {
+        let mut updates = UpdateReceiver::new();
+        let sender = updates.add_source(DataSource::Gpu);
+        for reason in ["first", "second", "third"] {
+            sender.send(unavailable(reason));
+        }
+        assert!(updates.ready().try_recv().is_ok());
+        assert_eq!(reasons(updates.drain()), ["third"]);
+        assert_eq!(updates.dropped().collect::<Vec<_>>(), [(DataSource::Gpu, 2)]);
+
+        sender.send(unavailable("fourth"));
+        assert_eq!(reasons(updates.drain()), ["fourth"]);
+        assert_eq!(updates.dropped().collect::<Vec<_>>(), [(DataSource::Gpu, 2)]);
+    }
+
+    #[test]
+    fn sending_outlives_the_app() {
+        let mut updates = UpdateReceiver::new();
+        let sender = updates.add_source(DataSource::Gpu);
+        drop(updates);
+        // Neither blocks nor panics; the collector stops on the shutdown signal.
+        sender.send(unavailable("first"));
+        sender.send(unavailable("second"));
+    }
+
+    #[test]
+    fn tracks_collector_health() {
+        let mut updates = UpdateReceiver::new();
+        let sender = updates.add_source(DataSource::Gpu);
+        let _ = sender.track(|| Err::<(), _>(AppError::CollectionError("no driver".to_string())));
+        let _ = sender.track(|| Err::<(), _>(AppError::CollectionError("no driver".to_string())));
+        let (_, health) = updates.health().next().unwrap();
+        assert_eq!(health.consecutive_failures, 2);
+        assert!(health.last_success.is_none() && health.collecting_since.is_none());
+        assert!(health.last_error.unwrap().contains("no driver"));
+
+        assert_eq!(sender.track(|| Ok(7)).unwrap(), 7);
+        let (_, health) = updates.health().next().unwrap();
+        assert_eq!(health.consecutive_failures, 0);
+        assert!(health.last_success.is_some());
+    }
+}
@@ -1,72 +1,270 @@
+use super::channel::UpdateSender;
+use super::scheduler::Scheduler;
 use super::traits::MetricsCollector;
-use crate::state::data_types::{CpuData, GlobalCpuMetrics};
+use crate::state::data_types::{CpuData, CpuTimeBreakdown, CpuTimes, GlobalCpuMetrics, Sample, Timestamp};
 use crate::error::AppError;
-use crate::config::Config; // Using the placeholder Config
+use crate::config::Config;
+use crate::shutdown::ShutdownSignal;
 
 use sysinfo::{System};
-use crossbeam_channel::Sender;
 use std::sync::Arc;
-use std::sync::atomic::AtomicBool;
 use std::thread::JoinHandle;
+use std::time::Instant;
+
+const PROC_STAT_PATH: &str = "/proc/stat";
+
+// Jiffies per mode, in /proc/stat column order: user, nice, system, idle,
+// iowait, irq, softirq, steal, guest, guest_nice.
+type Jiffies = [u64; 10];
+
+// Cumulative counters from one read of /proc/stat.
+struct ProcStat {
+    total: Jiffies,
+    // Indexed by CPU number; offline CPUs have no line and stay `None`.
+    cores: Vec<Option<Jiffies>>,
+    context_switches: u64,
+    interrupts: u64,
+    at: Instant,
+}
 
 pub struct CpuCollector{
     system: System,
+    // The previous /proc/stat reading, for the time breakdown and rates.
+    previous_stat: Option<ProcStat>,
 }
 
 impl MetricsCollector for CpuCollector {
     type CollectedData = CpuData;
     fn new(_config:Arc<Config>) -> Result<Self, AppError> {
-        Ok(CpuCollector { system: System::new_all() })
+        Ok(CpuCollector { system: System::new_all(), previous_stat: read_proc_stat() })
     }
 
     fn collect(&mut self) -> Result<Self::CollectedData, AppError> {
         self.system.refresh_cpu_usage();
+        self.system.refresh_cpu_frequency();
 
         let total_usage_percent = self.system.global_cpu_usage();
         let core_usages_percent: Vec<f32> = self.system.cpus()
             .iter()
             .map(|cpu| cpu.cpu_usage())
             .collect();
+        let core_frequencies_mhz: Vec<u64> = self.system.cpus().iter().map(|cpu| cpu.frequency()).collect();
+
+        let stat = read_proc_stat();
+        let times = match (&self.previous_stat, &stat) {
+            (Some(previous), Some(current)) => Some(cpu_times(previous, current)),
+            _ => None,
+        };
+        self.previous_stat = stat;
 
-        Ok(CpuData::GlobalCpuMetrics(GlobalCpuMetrics { total_usage_percent, core_usages_percent }))
+        Ok(CpuData::GlobalCpuMetrics(GlobalCpuMetrics {
+            total_usage_percent,
+            core_usages_percent,
+            core_frequencies_mhz,
+            times,
+        }))
     }
 
     fn run_in_thread(
             mut self,
-            sender: Sender<Self::CollectedData>,
-            shutdown_signal: Arc<AtomicBool>,
-            config: Arc<Config>,
+            sender: UpdateSender,
+            shutdown_signal: ShutdownSignal,
+            mut scheduler: Scheduler,
         ) -> JoinHandle<()> where Self:Sized {
         std::thread::spawn(move || {
             log::info!("CPU Collector thread started.");
             let mut first_run = true;
-            loop{
-                if shutdown_signal.load(std::sync::atomic::Ordering::Relaxed) {
-                    break;
-                }
+            while let Some(tick) = scheduler.wait_for_tick(&shutdown_signal) {
                 if first_run {
                     self.system.refresh_cpu_usage();
-                    std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
-                    self.system.refresh_cpu_usage();
+                    if shutdown_signal.sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL) {
+                        break;
+                    }
                     first_run = false;
-                } else {
-                    self.system.refresh_cpu_usage();
                 }
 
-                match self.collect() {
-                    Ok(data) => {
-                        if sender.send(data).is_err() {
-                            log::error!("CPU Collector: Failed to send data, channel closed.");
-                            break;
-                        }
-                    }
+                let collected = sender.track(|| {
+                    self.system.refresh_cpu_usage();
+                    self.collect()
+                });
+                match collected {
+                    Ok(data) => sender.send(Sample::new(Timestamp::at(tick), data)),
                     Err(e) => {
                         log::error!("CPU Collector: Error Collecting data: {:?}",e);
                     }
                 }
-                std::thread::sleep(std::time::Duration::from_millis(config.refresh_interval_ms));
             }
             log::info!("CPU Collector thread stopped.");
         })
     }
-}
\ No newline at end of file
+}
+fn read_proc_stat() -> Option<ProcStat> {
+    let contents = std::fs::read_to_string(PROC_STAT_PATH)
+        .map_err(|e| log::debug!("Failed to read {}: {}", PROC_STAT_PATH, e))
+        .ok()?;
+    Some(parse_proc_stat(&contents, Instant::now()))
+}
+
+fn parse_proc_stat(contents: &str, at: Instant) -> ProcStat {
+    let mut stat = ProcStat { total: [0; 10], cores: Vec::new(), context_switches: 0, interrupts: 0, at };
+    for line in contents.lines() {
+        let mut fields = line.split_whitespace();
+        let Some(key) = fields.next() else { continue };
+        let mut counters = fields.map(|field| field.parse::<u64>().unwrap_or(0));
+        match key {
+            "cpu" => stat.total = jiffies(counters),
+            // Only the first `intr` column is the total; the rest are per IRQ.
+            "intr" => stat.interrupts = counters.next().unwrap_or(0),
+            "ctxt" => stat.context_switches = counters.next().unwrap_or(0),
+            _ => {
+                if let Some(index) = key.strip_prefix("cpu").and_then(|n| n.parse::<usize>().ok()) {
+                    if stat.cores.len() <= index {
+                        stat.cores.resize(index + 1, None);
+                    }
+                    stat.cores[index] = Some(jiffies(counters));
+                }
+            }
+        }
+    }
+    stat
+}
+
+// Older kernels list fewer columns; the missing ones read as zero.
+fn jiffies(counters: impl Iterator<Item = u64>) -> Jiffies {
+    let mut jiffies = [0; 10];
+    for (slot, value) in jiffies.iter_mut().zip(counters) {
+        *slot = value;
+    }
+    jiffies
+}
+
+fn cpu_times(previous: &ProcStat, current: &ProcStat) -> CpuTimes {
+    let elapsed_secs = current.at.duration_since(previous.at).as_secs_f64();
+    let per_sec = |now: u64, before: u64| {
+        if elapsed_secs > 0.0 { now.saturating_sub(before) as f64 / elapsed_secs } else { 0.0 }
+    };
+    let cores = current
+        .cores
+        .iter()
+        .enumerate()
+        .map(|(index, core)| match (previous.cores.get(index).copied().flatten(), core) {
+            (Some(before), Some(now)) => breakdown(&before, now),
+            // Offline now or brought online since the last sample.
+            _ => CpuTimeBreakdown::default(),
+        })
+        .collect();
+    CpuTimes {
+        total: breakdown(&previous.total, &current.total),
+        cores,
+        context_switches_per_sec: per_sec(current.context_switches, previous.context_switches),
+        interrupts_per_sec: per_sec(current.interrupts, previous.interrupts),
+    }
+}
+
+fn breakdown(before: &Jiffies, now: &Jiffies) -> CpuTimeBreakdown {
+    let delta: Vec<u64> = now.iter().zip(before).map(|(now, before)| now.saturating_sub(*before)).collect();
+    // Guest time is already included in user and nice, so it is left out of the total.
+    let elapsed: u64 = delta[..8].iter().sum();
+    if elapsed == 0 {
+        return CpuTimeBreakdown::default();
+    }
+    let share = |index: usize| delta[index] as f64 * 100.0 / elapsed as f64;
+    CpuTimeBreakdown {
+        user: share(0),
+        nice: share(1),
+        system: share(2),
+        idle: share(3),
+        iowait: share(4),
+        irq: share(5),
+        softirq: share(6),
+        steal: share(7),
+        guest: share(8),
+        guest_nice: share(9),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    // Trimmed from a two-core machine, one second apart. The `intr` lines
+    // are cut short; only their first column matters.
+    const FIRST: &str = "\
+cpu  10000 200 3000 80000 500 100 200 0 0 0
+cpu0 5000 100 1500 40000 250 50 100 0 0 0
+cpu1 5000 100 1500 40000 250 50 100 0 0 0
+intr 1000000 9 0 0 0
+ctxt 2000000
+btime 1700000000
+processes 12345
+procs_running 2
+procs_blocked 0
+softirq 500000 0 1 2 3
+";
+
+    const SECOND: &str = "\
+cpu  10300 200 3100 80550 50 0 0 50 100 0
+cpu0 5200 100 1550 40150 250 50 100 0 100 0
+cpu1 5100 100 1550 40300 250 50 100 50 0 0
+intr 1004000 9 0 0 0
+ctxt 2010000
+btime 1700000000
+";
+
+    #[test]
+    fn parses_counters() {
+        let stat = parse_proc_stat(FIRST, Instant::now());
+        assert_eq!(stat.total, [10000, 200, 3000, 80000, 500, 100, 200, 0, 0, 0]);
+        assert_eq!(stat.cores.len(), 2);
+        assert_eq!(stat.cores[1], Some([5000, 100, 1500, 40000, 250, 50, 100, 0, 0, 0]));
+        assert_eq!(stat.interrupts, 1000000);
+        assert_eq!(stat.context_switches, 2000000);
+    }
+
+    #[test]
+    fn pads_short_lines_and_keeps_offline_gaps() {
+        // An old kernel with only four columns, and cpu1 offline.
+        let stat = parse_proc_stat("cpu 40 0 10 50\ncpu0 20 0 5 25\ncpu2 20 0 5 25\n", Instant::now());
+        assert_eq!(stat.total, [40, 0, 10, 50, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(stat.cores.len(), 3);
+        assert_eq!(stat.cores[0], Some([20, 0, 5, 25, 0, 0, 0, 0, 0, 0]));
+        assert!(stat.cores[1].is_none());
+        assert_eq!(stat.context_switches, 0);
+    }
+
+    #[test]
+    fn breaks_time_down_between_samples() {
+        let start = Instant::now();
+        let previous = parse_proc_stat(FIRST, start);
+        let current = parse_proc_stat(SECOND, start + Duration::from_secs(2));
+        let times = cpu_times(&previous, &current);
+
+        // 1000 jiffies passed; iowait, irq and softirq counters went
+        // backwards and count as zero.
+        let total = times.total;
+        assert_eq!((total.user, total.system, total.idle, total.steal), (30.0, 10.0, 55.0, 5.0));
+        assert_eq!((total.iowait, total.irq, total.softirq), (0.0, 0.0, 0.0));
+        // Guest time is part of user, not on top of it.
+        assert_eq!(total.guest, 10.0);
+
+        assert_eq!(times.cores.len(), 2);
+        assert_eq!((times.cores[0].user, times.cores[0].guest), (50.0, 25.0));
+        assert_eq!((times.cores[1].idle, times.cores[1].steal), (60.0, 10.0));
+
+        assert_eq!(times.interrupts_per_sec, 2000.0);
+        assert_eq!(times.context_switches_per_sec, 5000.0);
+    }
+
+    #[test]
+    fn cores_coming_online_read_as_zero() {
+        let start = Instant::now();
+        let previous = parse_proc_stat("cpu 10 0 10 80\ncpu0 10 0 10 80\n", start);
+        let current = parse_proc_stat("cpu 20 0 20 160\ncpu0 20 0 20 160\ncpu1 5 0 5 40\n", start);
+        let times = cpu_times(&previous, &current);
+        assert_eq!(times.cores[0].idle, 80.0);
+        assert_eq!(times.cores[1].idle, 0.0);
+        // No time passed, so there is no rate.
+        assert_eq!(times.context_switches_per_sec, 0.0);
+    }
+}
@@ -0,0 +1,235 @@
+use super::traits::MetricsCollector;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::state::data_types::{DiskData, DiskMetrics};
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+
+const DISKSTATS_PATH: &str = "/proc/diskstats";
+const SYS_BLOCK_DIR: &str = "/sys/block";
+// /proc/diskstats counts in 512-byte sectors whatever the device's real sector size.
+const SECTOR_SIZE: u64 = 512;
+
+// Cumulative counters for one device, as listed in /proc/diskstats.
+#[derive(Debug, Clone, Copy)]
+struct Counters {
+    reads: u64,
+    read_sectors: u64,
+    read_time_ms: u64,
+    writes: u64,
+    written_sectors: u64,
+    write_time_ms: u64,
+    busy_time_ms: u64,
+    weighted_time_ms: u64,
+}
+
+/// Per-device throughput, IOPS, latency and utilization from /proc/diskstats.
+/// Partitions and devices that have never done any I/O are left out.
+pub struct DiskCollector {
+    previous: HashMap<String, Counters>,
+    previous_at: Option<Instant>,
+}
+
+impl MetricsCollector for DiskCollector {
+    type CollectedData = DiskData;
+
+    fn new(_config: Arc<Config>) -> Result<Self, AppError> {
+        if !Path::new(DISKSTATS_PATH).exists() {
+            return Err(AppError::CollectionError(format!("{} not found", DISKSTATS_PATH)));
+        }
+        Ok(DiskCollector { previous: HashMap::new(), previous_at: None })
+    }
+
+    fn collect(&mut self) -> Result<Self::CollectedData, AppError> {
+        let contents = std::fs::read_to_string(DISKSTATS_PATH)
+            .map_err(|e| AppError::CollectionError(format!("Failed to read {}: {}", DISKSTATS_PATH, e)))?;
+        Ok(DiskData::Global(self.update(&contents, Instant::now(), Path::new(SYS_BLOCK_DIR))))
+    }
+}
+
+impl DiskCollector {
+    // Metrics from the diskstats `contents` read at `now`, given the earlier
+    // reading. `sys_block_dir` lists the whole devices.
+    fn update(&mut self, contents: &str, now: Instant, sys_block_dir: &Path) -> Vec<DiskMetrics> {
+        let elapsed_ms = self.previous_at.map(|at| now.duration_since(at).as_secs_f64() * 1000.0).filter(|ms| *ms > 0.0);
+
+        let mut current = HashMap::new();
+        let mut disks = Vec::new();
+        for (name, counters) in contents.lines().filter_map(parse_line) {
+            if !is_whole_device(sys_block_dir, &name) || counters.reads + counters.writes == 0 {
+                continue;
+            }
+            disks.push(metrics(&name, &counters, self.previous.get(&name), elapsed_ms));
+            current.insert(name, counters);
+        }
+        disks.sort_by(|a, b| a.device_name.cmp(&b.device_name));
+
+        self.previous = current;
+        self.previous_at = Some(now);
+        disks
+    }
+}
+
+// Whole devices have an entry in /sys/block; partitions don't. Sysfs spells
+// a `/` in the name, as in `cciss/c0d0`, as `!`.
+fn is_whole_device(sys_block_dir: &Path, name: &str) -> bool {
+    sys_block_dir.join(name.replace('/', "!")).exists()
+}
+
+fn parse_line(line: &str) -> Option<(String, Counters)> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let name = fields.get(2)?.to_string();
+    let field = |index: usize| fields.get(index)?.parse::<u64>().ok();
+    let counters = Counters {
+        reads: field(3)?,
+        read_sectors: field(5)?,
+        read_time_ms: field(6)?,
+        writes: field(7)?,
+        written_sectors: field(9)?,
+        write_time_ms: field(10)?,
+        busy_time_ms: field(12)?,
+        weighted_time_ms: field(13)?,
+    };
+    Some((name, counters))
+}
+
+fn metrics(name: &str, now: &Counters, previous: Option<&Counters>, elapsed_ms: Option<f64>) -> DiskMetrics {
+    // Counters wrap or reset when a device is re-attached; treat that as idle.
+    let delta = |field: fn(&Counters) -> u64| previous.map_or(0, |previous| field(now).saturating_sub(field(previous)));
+    let per_sec = |count: u64| elapsed_ms.map_or(0.0, |ms| count as f64 * 1000.0 / ms);
+    let latency = |time_ms: u64, requests: u64| if requests > 0 { time_ms as f64 / requests as f64 } else { 0.0 };
+
+    let (reads, writes) = (delta(|c| c.reads), delta(|c| c.writes));
+    DiskMetrics {
+        device_name: name.to_string(),
+        total_read_bytes: now.read_sectors * SECTOR_SIZE,
+        total_written_bytes: now.written_sectors * SECTOR_SIZE,
+        read_bytes_per_sec: per_sec(delta(|c| c.read_sectors) * SECTOR_SIZE),
+        written_bytes_per_sec: per_sec(delta(|c| c.written_sectors) * SECTOR_SIZE),
+        reads_per_sec: per_sec(reads),
+        writes_per_sec: per_sec(writes),
+        read_latency_ms: latency(delta(|c| c.read_time_ms), reads),
+        write_latency_ms: latency(delta(|c| c.write_time_ms), writes),
+        queue_depth: elapsed_ms.map_or(0.0, |ms| delta(|c| c.weighted_time_ms) as f64 / ms),
+        utilization_percent: elapsed_ms.map_or(0.0, |ms| (delta(|c| c.busy_time_ms) as f64 / ms * 100.0).min(100.0)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    // Two readings of a disk with a partition, an idle loop device and a
+    // Smart Array volume, two seconds apart.
+    const FIRST: &str = "\
+   8       0 sda 1000 10 80000 500 2000 20 160000 3000 0 1500 3500 0 0 0 0 100 200
+   8       1 sda1 900 10 70000 450 1900 20 150000 2900 0 1400 3350 0 0 0 0 0 0
+   7       0 loop0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0
+ 104       0 cciss/c0d0 50 0 400 10 0 0 0 0 0 10 10
+";
+    const SECOND: &str = "\
+   8       0 sda 1100 10 84000 600 2400 20 176000 3800 2 2500 4500 0 0 0 0 100 200
+   8       1 sda1 1000 10 74000 550 2300 20 166000 3700 0 2400 4250 0 0 0 0 0 0
+   7       0 loop0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0
+ 104       0 cciss/c0d0 50 0 400 10 0 0 0 0 0 10 10
+";
+
+    fn sys_block(devices: &[&str]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for device in devices {
+            std::fs::create_dir(dir.path().join(device)).unwrap();
+        }
+        dir
+    }
+
+    fn collector() -> DiskCollector {
+        DiskCollector { previous: HashMap::new(), previous_at: None }
+    }
+
+    #[test]
+    fn parses_diskstats_lines() {
+        let (name, counters) = parse_line(FIRST.lines().next().unwrap()).unwrap();
+        assert_eq!(name, "sda");
+        assert_eq!((counters.reads, counters.read_sectors, counters.read_time_ms), (1000, 80000, 500));
+        assert_eq!((counters.writes, counters.written_sectors, counters.write_time_ms), (2000, 160000, 3000));
+        assert_eq!((counters.busy_time_ms, counters.weighted_time_ms), (1500, 3500));
+
+        // Kernels before 4.18 stop after the 14th field.
+        let (name, counters) = parse_line(" 104 0 cciss/c0d0 50 0 400 10 0 0 0 0 0 10 10").unwrap();
+        assert_eq!((name.as_str(), counters.reads, counters.weighted_time_ms), ("cciss/c0d0", 50, 10));
+    }
+
+    #[test]
+    fn skips_malformed_lines() {
+        assert!(parse_line("").is_none());
+        assert!(parse_line("   8       0 sda 1000 10 80000").is_none());
+        assert!(parse_line("   8       0 sda 1000 10 x 500 2000 20 160000 3000 0 1500 3500").is_none());
+    }
+
+    #[test]
+    fn keeps_whole_devices_that_have_done_io() {
+        let sys_block = sys_block(&["sda", "loop0", "cciss!c0d0"]);
+        let disks = collector().update(FIRST, Instant::now(), sys_block.path());
+        let names: Vec<&str> = disks.iter().map(|disk| disk.device_name.as_str()).collect();
+        assert_eq!(names, ["cciss/c0d0", "sda"]);
+    }
+
+    #[test]
+    fn first_reading_has_totals_but_no_rates() {
+        let sys_block = sys_block(&["sda"]);
+        let disks = collector().update(FIRST, Instant::now(), sys_block.path());
+        let sda = &disks[0];
+        assert_eq!((sda.total_read_bytes, sda.total_written_bytes), (80000 * 512, 160000 * 512));
+        assert_eq!((sda.read_bytes_per_sec, sda.reads_per_sec, sda.read_latency_ms), (0.0, 0.0, 0.0));
+        assert_eq!((sda.queue_depth, sda.utilization_percent), (0.0, 0.0));
+    }
+
+    #[test]
+    fn computes_rates_over_the_interval() {
+        let sys_block = sys_block(&["sda"]);
+        let mut collector = collector();
+        let start = Instant::now();
+        collector.update(FIRST, start, sys_block.path());
+        let disks = collector.update(SECOND, start + Duration::from_secs(2), sys_block.path());
+        let sda = &disks[0];
+
+        // 100 reads of 4000 sectors and 400 writes of 16000 sectors in 2s.
+        assert_eq!(sda.reads_per_sec, 50.0);
+        assert_eq!(sda.writes_per_sec, 200.0);
+        assert_eq!(sda.read_bytes_per_sec, 4000.0 * 512.0 / 2.0);
+        assert_eq!(sda.written_bytes_per_sec, 16000.0 * 512.0 / 2.0);
+        // 100ms over 100 reads, 800ms over 400 writes.
+        assert_eq!(sda.read_latency_ms, 1.0);
+        assert_eq!(sda.write_latency_ms, 2.0);
+        // Busy 1000ms and 1000ms of weighted time in 2000ms.
+        assert_eq!(sda.utilization_percent, 50.0);
+        assert_eq!(sda.queue_depth, 0.5);
+    }
+
+    #[test]
+    fn treats_counter_resets_as_idle() {
+        let sys_block = sys_block(&["sda"]);
+        let mut collector = collector();
+        let start = Instant::now();
+        collector.update(SECOND, start, sys_block.path());
+        let disks = collector.update(FIRST, start + Duration::from_secs(1), sys_block.path());
+        let sda = &disks[0];
+        assert_eq!((sda.reads_per_sec, sda.writes_per_sec, sda.read_bytes_per_sec), (0.0, 0.0, 0.0));
+        assert_eq!((sda.read_latency_ms, sda.utilization_percent, sda.queue_depth), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn caps_utilization_at_100_percent() {
+        let sys_block = sys_block(&["sda"]);
+        let mut collector = collector();
+        let start = Instant::now();
+        collector.update(FIRST, start, sys_block.path());
+        // 1000ms busy in a 500ms interval, as when the reading lags.
+        let disks = collector.update(SECOND, start + Duration::from_millis(500), sys_block.path());
+        assert_eq!(disks[0].utilization_percent, 100.0);
+    }
+}
@@ -0,0 +1,138 @@
+use super::traits::MetricsCollector;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::state::data_types::{FilesystemData, FilesystemMetrics};
+
+use regex::Regex;
+use std::collections::HashSet;
+use std::ffi::CString;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+const MOUNTS_PATH: &str = "/proc/self/mounts";
+
+// One line of the mount table.
+struct Mount {
+    device: String,
+    mount_point: String,
+    fs_type: String,
+}
+
+/// Size and inode usage of every mounted filesystem, from the mount table and
+/// `statvfs`. Filesystems reporting no blocks at all are left out.
+pub struct FilesystemCollector {
+    exclude_types: HashSet<String>,
+    exclude_mount_points: Vec<Regex>,
+}
+
+impl MetricsCollector for FilesystemCollector {
+    type CollectedData = FilesystemData;
+
+    fn new(config: Arc<Config>) -> Result<Self, AppError> {
+        if !Path::new(MOUNTS_PATH).exists() {
+            return Err(AppError::CollectionError(format!("{} not found", MOUNTS_PATH)));
+        }
+        let exclude_mount_points = config.filesystems.exclude_mount_points
+            .iter()
+            .map(|pattern| Regex::new(pattern))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::CollectionError(format!("Invalid mount point filter: {}", e)))?;
+        Ok(FilesystemCollector {
+            exclude_types: config.filesystems.exclude_types.iter().cloned().collect(),
+            exclude_mount_points,
+        })
+    }
+
+    fn collect(&mut self) -> Result<Self::CollectedData, AppError> {
+        let contents = std::fs::read_to_string(MOUNTS_PATH)
+            .map_err(|e| AppError::CollectionError(format!("Failed to read {}: {}", MOUNTS_PATH, e)))?;
+
+        // A later mount on the same point hides the earlier one, and statvfs
+        // only ever sees the one on top.
+        let mut mounts: Vec<Mount> = Vec::new();
+        for mount in contents.lines().filter_map(parse_line) {
+            mounts.retain(|earlier| earlier.mount_point != mount.mount_point);
+            mounts.push(mount);
+        }
+
+        let mut filesystems = Vec::new();
+        for mount in mounts {
+            if self.exclude_types.contains(&mount.fs_type)
+                || self.exclude_mount_points.iter().any(|pattern| pattern.is_match(&mount.mount_point))
+            {
+                continue;
+            }
+            match statvfs(&mount.mount_point) {
+                Ok(stats) if stats.f_blocks > 0 => filesystems.push(metrics(mount, &stats)),
+                Ok(_) => {}
+                // Typically a mount we may not look into; not worth failing the sample.
+                Err(e) => log::debug!("statvfs({}) failed: {}", mount.mount_point, e),
+            }
+        }
+        filesystems.sort_by(|a, b| a.mount_point.cmp(&b.mount_point));
+        Ok(FilesystemData::Global(filesystems))
+    }
+}
+
+fn parse_line(line: &str) -> Option<Mount> {
+    let mut fields = line.split_whitespace();
+    Some(Mount {
+        device: unescape(fields.next()?),
+        mount_point: unescape(fields.next()?),
+        fs_type: fields.next()?.to_string(),
+    })
+}
+
+// The kernel writes space, tab, newline and backslash in mount table fields
+// as three-digit octal escapes, e.g. `\040`.
+fn unescape(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'\\'
+            && let Some(digits) = bytes.get(index + 1..index + 4)
+            && digits.iter().all(|digit| (b'0'..=b'7').contains(digit))
+        {
+            decoded.push(digits.iter().fold(0u8, |value, digit| value.wrapping_mul(8).wrapping_add(digit - b'0')));
+            index += 4;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn statvfs(path: &str) -> io::Result<libc::statvfs> {
+    let path = CString::new(path).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    // SAFETY: `path` is NUL-terminated and `stats` is plain data that statvfs
+    // fills in completely on success.
+    unsafe {
+        let mut stats: libc::statvfs = std::mem::zeroed();
+        if libc::statvfs(path.as_ptr(), &mut stats) == 0 {
+            Ok(stats)
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+}
+
+// The statvfs field types are 32 bits wide on some targets.
+#[allow(clippy::unnecessary_cast)]
+fn metrics(mount: Mount, stats: &libc::statvfs) -> FilesystemMetrics {
+    let block_size = stats.f_frsize as u64;
+    let total_inodes = stats.f_files as u64;
+    FilesystemMetrics {
+        mount_point: mount.mount_point,
+        device: mount.device,
+        fs_type: mount.fs_type,
+        total_bytes: stats.f_blocks as u64 * block_size,
+        used_bytes: (stats.f_blocks as u64).saturating_sub(stats.f_bfree as u64) * block_size,
+        available_bytes: stats.f_bavail as u64 * block_size,
+        total_inodes,
+        used_inodes: total_inodes.saturating_sub(stats.f_ffree as u64),
+        free_inodes: stats.f_ffree as u64,
+    }
+}
@@ -14,7 +14,7 @@ impl MetricsCollector for GpuCollector {
     type CollectedData = GpuData;
 
     fn new(_config: Arc<Config>) -> Result<Self, AppError> {
-        let nvml = Nvml::init().map_err(|e| AppError::CollectionError(format!("Nvml init failed: {:?}",e)))?;
+        let nvml = Nvml::init().map_err(|e| AppError::CollectionError(format!("Nvml init failed: {}",e)))?;
         Ok(GpuCollector { nvml })
     }
 
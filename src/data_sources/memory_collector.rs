@@ -1,30 +1,56 @@
 use super::traits::MetricsCollector;
 use crate::config::Config;
 use crate::error::AppError;
-use crate::state::data_types::{MemoryData, GlobalMemoryMetrics};
+use crate::state::data_types::{MemoryData, GlobalMemoryMetrics, MemoryDetails};
 
-use sysinfo::{System};
+use sysinfo::System;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
+
+const MEMINFO_PATH: &str = "/proc/meminfo";
+const VMSTAT_PATH: &str = "/proc/vmstat";
+
+// Cumulative pages swapped in and out, from /proc/vmstat.
+#[derive(Debug, Clone, Copy)]
+struct SwapCounters {
+    pages_in: u64,
+    pages_out: u64,
+    at: Instant,
+}
 
 pub struct MemoryCollector {
     system:System,
+    page_size: u64,
+    previous_swap: Option<SwapCounters>,
 }
 
 impl MetricsCollector for MemoryCollector {
     type CollectedData = MemoryData;
 
     fn new(_config: Arc<Config>) -> Result<Self, AppError> {
-        Ok(MemoryCollector { system: System::new() })
+        // SAFETY: sysconf has no memory-safety preconditions.
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        Ok(MemoryCollector {
+            system: System::new(),
+            page_size: u64::try_from(page_size).unwrap_or(4096),
+            previous_swap: read_swap_counters(),
+        })
     }
 
     fn collect(&mut self) -> Result<Self::CollectedData, AppError> {
         self.system.refresh_memory();
 
-        let total_memory_kb = self.system.total_memory();
-        let available_memory_kb = self.system.available_memory();
+        // sysinfo reports bytes.
+        let total_memory_kb = self.system.total_memory() / 1024;
+        let available_memory_kb = self.system.available_memory() / 1024;
         let used_memory_kb = total_memory_kb.saturating_sub(available_memory_kb);
-        let total_swap_kb = self.system.total_swap();
-        let used_swap_kb = self.system.used_swap();
+        let total_swap_kb = self.system.total_swap() / 1024;
+        let used_swap_kb = self.system.used_swap() / 1024;
+
+        let swap = read_swap_counters();
+        let details = read_meminfo().map(|meminfo| self.details(&meminfo, swap));
+        self.previous_swap = swap;
 
         Ok(MemoryData::Global(GlobalMemoryMetrics{
             total_memory_kb,
@@ -32,6 +58,163 @@ impl MetricsCollector for MemoryCollector {
             used_memory_kb,
             total_swap_kb,
             used_swap_kb,
+            details,
         }))
     }
-}
\ No newline at end of file
+}
+
+impl MemoryCollector {
+    fn details(&self, meminfo: &HashMap<String, u64>, swap: Option<SwapCounters>) -> MemoryDetails {
+        let field = |name: &str| meminfo.get(name).copied().unwrap_or(0);
+        let (swap_in_bytes_per_sec, swap_out_bytes_per_sec) = match (self.previous_swap, swap) {
+            (Some(previous), Some(current)) => {
+                let elapsed_secs = current.at.duration_since(previous.at).as_secs_f64();
+                let rate = |now: u64, before: u64| {
+                    if elapsed_secs > 0.0 { (now.saturating_sub(before) * self.page_size) as f64 / elapsed_secs } else { 0.0 }
+                };
+                (rate(current.pages_in, previous.pages_in), rate(current.pages_out, previous.pages_out))
+            }
+            _ => (0.0, 0.0),
+        };
+        MemoryDetails {
+            free_kb: field("MemFree"),
+            buffers_kb: field("Buffers"),
+            cached_kb: field("Cached"),
+            shared_kb: field("Shmem"),
+            slab_reclaimable_kb: field("SReclaimable"),
+            slab_unreclaimable_kb: field("SUnreclaim"),
+            dirty_kb: field("Dirty"),
+            writeback_kb: field("Writeback"),
+            huge_pages_total: field("HugePages_Total"),
+            huge_pages_free: field("HugePages_Free"),
+            huge_page_size_kb: field("Hugepagesize"),
+            committed_as_kb: field("Committed_AS"),
+            commit_limit_kb: field("CommitLimit"),
+            swap_in_bytes_per_sec,
+            swap_out_bytes_per_sec,
+        }
+    }
+}
+
+// Lines look like `MemFree:  2670224 kB`; the huge page counts have no unit.
+fn read_meminfo() -> Option<HashMap<String, u64>> {
+    let contents = std::fs::read_to_string(MEMINFO_PATH)
+        .map_err(|e| log::debug!("Failed to read {}: {}", MEMINFO_PATH, e))
+        .ok()?;
+    Some(parse_meminfo(&contents))
+}
+
+fn parse_meminfo(contents: &str) -> HashMap<String, u64> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (name, rest) = line.split_once(':')?;
+            let value = rest.split_whitespace().next()?.parse().ok()?;
+            Some((name.to_string(), value))
+        })
+        .collect()
+}
+
+fn read_swap_counters() -> Option<SwapCounters> {
+    let contents = std::fs::read_to_string(VMSTAT_PATH).ok()?;
+    parse_swap_counters(&contents, Instant::now())
+}
+
+fn parse_swap_counters(contents: &str, at: Instant) -> Option<SwapCounters> {
+    let mut counters = SwapCounters { pages_in: 0, pages_out: 0, at };
+    for line in contents.lines() {
+        match line.split_once(' ') {
+            Some(("pswpin", value)) => counters.pages_in = value.trim().parse().ok()?,
+            Some(("pswpout", value)) => counters.pages_out = value.trim().parse().ok()?,
+            _ => {}
+        }
+    }
+    Some(counters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    // From an 8 GiB machine, with a few unused fields left out.
+    const MEMINFO: &str = "\
+MemTotal:        8029160 kB
+MemFree:          467712 kB
+MemAvailable:    4421028 kB
+Buffers:          248604 kB
+Cached:          3721820 kB
+SwapCached:        10244 kB
+Active(anon):    2051236 kB
+Shmem:            386452 kB
+Dirty:              1432 kB
+Writeback:             0 kB
+SReclaimable:     300316 kB
+SUnreclaim:       112588 kB
+CommitLimit:     6111724 kB
+Committed_AS:   11345632 kB
+HugePages_Total:      16
+HugePages_Free:       12
+Hugepagesize:       2048 kB
+";
+
+    fn collector(previous_swap: Option<SwapCounters>) -> MemoryCollector {
+        MemoryCollector { system: System::new(), page_size: 4096, previous_swap }
+    }
+
+    #[test]
+    fn parses_meminfo() {
+        let meminfo = parse_meminfo(MEMINFO);
+        assert_eq!(meminfo.len(), 17);
+        assert_eq!(meminfo["MemTotal"], 8029160);
+        // Names keep their parentheses, and unitless counts parse too.
+        assert_eq!(meminfo["Active(anon)"], 2051236);
+        assert_eq!(meminfo["HugePages_Total"], 16);
+    }
+
+    #[test]
+    fn skips_lines_that_do_not_parse() {
+        let meminfo = parse_meminfo("MemFree: lots kB\nno colon here\nBuffers:\nCached: 3721820 kB\n");
+        assert_eq!(meminfo.len(), 1);
+        assert_eq!(meminfo["Cached"], 3721820);
+    }
+
+    #[test]
+    fn fills_in_details() {
+        let details = collector(None).details(&parse_meminfo(MEMINFO), None);
+        assert_eq!(details.free_kb, 467712);
+        assert_eq!(details.buffers_kb, 248604);
+        assert_eq!(details.cached_kb, 3721820);
+        assert_eq!(details.shared_kb, 386452);
+        assert_eq!((details.slab_reclaimable_kb, details.slab_unreclaimable_kb), (300316, 112588));
+        assert_eq!((details.dirty_kb, details.writeback_kb), (1432, 0));
+        assert_eq!((details.huge_pages_total, details.huge_pages_free, details.huge_page_size_kb), (16, 12, 2048));
+        assert_eq!((details.committed_as_kb, details.commit_limit_kb), (11345632, 6111724));
+        assert_eq!((details.swap_in_bytes_per_sec, details.swap_out_bytes_per_sec), (0.0, 0.0));
+
+        // Fields an older kernel doesn't have read as zero.
+        let details = collector(None).details(&parse_meminfo("MemFree: 1024 kB\n"), None);
+        assert_eq!((details.free_kb, details.shared_kb, details.huge_page_size_kb), (1024, 0, 0));
+    }
+
+    #[test]
+    fn swap_rates_are_in_bytes() {
+        let start = Instant::now();
+        let vmstat = "nr_free_pages 116928\npswpin 1000\npswpout 5000\npgfault 123\n";
+        let previous = parse_swap_counters(vmstat, start).unwrap();
+        assert_eq!((previous.pages_in, previous.pages_out), (1000, 5000));
+
+        let vmstat = "nr_free_pages 116928\npswpin 1100\npswpout 5400\npgfault 456\n";
+        let current = parse_swap_counters(vmstat, start + Duration::from_secs(4)).unwrap();
+        let details = collector(Some(previous)).details(&parse_meminfo(MEMINFO), Some(current));
+        assert_eq!(details.swap_in_bytes_per_sec, 100.0 * 4096.0 / 4.0);
+        assert_eq!(details.swap_out_bytes_per_sec, 400.0 * 4096.0 / 4.0);
+    }
+
+    #[test]
+    fn swap_counters_need_numbers() {
+        assert!(parse_swap_counters("pswpin many\n", Instant::now()).is_none());
+        let counters = parse_swap_counters("", Instant::now()).unwrap();
+        assert_eq!((counters.pages_in, counters.pages_out), (0, 0));
+    }
+}
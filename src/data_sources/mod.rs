@@ -1,7 +1,15 @@
 pub mod traits;
+pub mod registry;
+pub mod channel;
+pub mod scheduler;
+pub mod self_usage;
 pub mod cpu_collector;
 pub mod process_collector;
 pub mod memory_collector;
 pub mod network_collector;
 pub mod gpu_collector;
-pub mod system_stat_collector;
\ No newline at end of file
+pub mod system_stats_collector;
+pub mod disk_collector;
+pub mod filesystem_collector;
+pub mod sensors_collector;
+pub mod pressure_collector;
@@ -3,34 +3,103 @@ use crate::config::Config;
 use crate::error::AppError;
 use crate::state::data_types::{NetworkData, GlobalNetworkMetrics};
 
-use sysinfo::{System, Networks};
+use sysinfo::Networks;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 
+// Cumulative counters for one interface at one point in time.
+#[derive(Debug, Clone, Copy)]
+struct Counters {
+    received_bytes: u64,
+    transmitted_bytes: u64,
+    received_packets: u64,
+    transmitted_packets: u64,
+    receive_errors: u64,
+    transmit_errors: u64,
+    receive_drops: Option<u64>,
+    transmit_drops: Option<u64>,
+}
 
 pub struct NetworkCollector {
     networks: Networks,
+    previous: HashMap<String, Counters>,
+    previous_at: Option<Instant>,
 }
 
 impl MetricsCollector for NetworkCollector {
     type CollectedData = NetworkData;
 
     fn new(_config: Arc<Config>) -> Result<Self, AppError> {
-        Ok(NetworkCollector { networks: Networks::new_with_refreshed_list() })
+        Ok(NetworkCollector { networks: Networks::new_with_refreshed_list(), previous: HashMap::new(), previous_at: None })
     }
 
     fn collect(&mut self) -> Result<Self::CollectedData, AppError> {
 
         self.networks.refresh(true);
+        let now = Instant::now();
+        let elapsed = self.previous_at.map(|at| now.duration_since(at).as_secs_f64()).filter(|secs| *secs > 0.0);
+
+        let mut current = HashMap::new();
         let metrics: Vec<GlobalNetworkMetrics> = self.networks.list().iter().map(|(name, data)| {
+            let counters = Counters {
+                received_bytes: data.total_received(),
+                transmitted_bytes: data.total_transmitted(),
+                received_packets: data.total_packets_received(),
+                transmitted_packets: data.total_packets_transmitted(),
+                receive_errors: data.total_errors_on_received(),
+                transmit_errors: data.total_errors_on_transmitted(),
+                receive_drops: read_drop_counter(name, "rx_dropped"),
+                transmit_drops: read_drop_counter(name, "tx_dropped"),
+            };
+            let previous = self.previous.get(name);
+            current.insert(name.clone(), counters);
+
+            // Counters can go backwards when an interface is reset; report
+            // zero rather than a huge bogus rate.
+            let rate = |now: u64, before: Option<u64>| match (before, elapsed) {
+                (Some(before), Some(secs)) => now.saturating_sub(before) as f64 / secs,
+                _ => 0.0,
+            };
+            let optional_rate = |now: Option<u64>, before: Option<Option<u64>>| {
+                now.map(|now| rate(now, before.flatten()))
+            };
+
             GlobalNetworkMetrics {
                 interface_name: name.clone(),
-                received_bytes: data.received(),
-                transmitted_bytes: data.transmitted(),
-                received_packets: data.packets_received(),
-                transmitted_packets: data.packets_transmitted(),
+                total_received_bytes: counters.received_bytes,
+                total_transmitted_bytes: counters.transmitted_bytes,
+                total_received_packets: counters.received_packets,
+                total_transmitted_packets: counters.transmitted_packets,
+                total_receive_errors: counters.receive_errors,
+                total_transmit_errors: counters.transmit_errors,
+                total_receive_drops: counters.receive_drops,
+                total_transmit_drops: counters.transmit_drops,
+                received_bytes_per_sec: rate(counters.received_bytes, previous.map(|p| p.received_bytes)),
+                transmitted_bytes_per_sec: rate(counters.transmitted_bytes, previous.map(|p| p.transmitted_bytes)),
+                received_packets_per_sec: rate(counters.received_packets, previous.map(|p| p.received_packets)),
+                transmitted_packets_per_sec: rate(counters.transmitted_packets, previous.map(|p| p.transmitted_packets)),
+                receive_errors_per_sec: rate(counters.receive_errors, previous.map(|p| p.receive_errors)),
+                transmit_errors_per_sec: rate(counters.transmit_errors, previous.map(|p| p.transmit_errors)),
+                receive_drops_per_sec: optional_rate(counters.receive_drops, previous.map(|p| p.receive_drops)),
+                transmit_drops_per_sec: optional_rate(counters.transmit_drops, previous.map(|p| p.transmit_drops)),
             }
         }).collect();
 
+        self.previous = current;
+        self.previous_at = Some(now);
         Ok(NetworkData::Global(metrics))
     }
-}
\ No newline at end of file
+}
+
+// sysinfo does not expose drop counters, so read them from sysfs where available.
+#[cfg(target_os = "linux")]
+fn read_drop_counter(interface: &str, counter: &str) -> Option<u64> {
+    let path = format!("/sys/class/net/{}/statistics/{}", interface, counter);
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_drop_counter(_interface: &str, _counter: &str) -> Option<u64> {
+    None
+}
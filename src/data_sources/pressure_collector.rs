@@ -0,0 +1,203 @@
+use super::traits::MetricsCollector;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::state::data_types::{CgroupPressure, Pressure, PressureData, PressureMetrics, PsiAverages, PsiResource};
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const PRESSURE_DIR: &str = "/proc/pressure";
+const MOUNTS_PATH: &str = "/proc/self/mounts";
+
+/// Pressure stall information for the whole system and for selected cgroups.
+pub struct PressureCollector {
+    // Where cgroup2 is mounted; per-cgroup PSI is skipped without it.
+    cgroup_root: Option<PathBuf>,
+    // Configured cgroups; when empty the top-level ones are listed on each sample.
+    cgroups: Vec<String>,
+}
+
+impl MetricsCollector for PressureCollector {
+    type CollectedData = PressureData;
+
+    fn new(config: Arc<Config>) -> Result<Self, AppError> {
+        // The files exist but fail to read when the kernel was booted with psi=0.
+        let probe = Path::new(PRESSURE_DIR).join("cpu");
+        fs::read_to_string(&probe)
+            .map_err(|e| AppError::CollectionError(format!("PSI not available ({}: {})", probe.display(), e)))?;
+        let cgroups = config.pressure.cgroups.iter().map(|cgroup| cgroup.trim_matches('/').to_string()).collect();
+        Ok(PressureCollector { cgroup_root: find_cgroup2_mount(), cgroups })
+    }
+
+    fn collect(&mut self) -> Result<Self::CollectedData, AppError> {
+        let system = read_pressure(Path::new(PRESSURE_DIR), "");
+        if system.cpu.is_none() && system.memory.is_none() && system.io.is_none() {
+            return Err(AppError::CollectionError(format!("Failed to read {}", PRESSURE_DIR)));
+        }
+
+        let mut cgroups = Vec::new();
+        if let Some(root) = &self.cgroup_root {
+            let names = if self.cgroups.is_empty() { top_level_cgroups(root) } else { self.cgroups.clone() };
+            for cgroup in names {
+                let pressure = read_pressure(&root.join(&cgroup), ".pressure");
+                // Gone, or PSI disabled for it; either way there is nothing to show.
+                if pressure.cpu.is_some() || pressure.memory.is_some() || pressure.io.is_some() {
+                    cgroups.push(CgroupPressure { cgroup, pressure });
+                }
+            }
+        }
+        Ok(PressureData::Global(PressureMetrics { system, cgroups }))
+    }
+}
+
+// /proc/pressure has files named `cpu`, cgroups `cpu.pressure`.
+fn read_pressure(dir: &Path, suffix: &str) -> Pressure {
+    let read = |resource: &str| parse_psi(&fs::read_to_string(dir.join(format!("{}{}", resource, suffix))).ok()?);
+    Pressure { cpu: read("cpu"), memory: read("memory"), io: read("io") }
+}
+
+// A `some` line and, except for CPU on older kernels, a `full` one. Lines
+// that don't parse are skipped rather than losing the whole resource.
+fn parse_psi(contents: &str) -> Option<PsiResource> {
+    let mut some = None;
+    let mut full = None;
+    for line in contents.lines() {
+        match parse_psi_line(line) {
+            Some(("some", averages)) => some = Some(averages),
+            Some(("full", averages)) => full = Some(averages),
+            _ => {}
+        }
+    }
+    Some(PsiResource { some: some?, full })
+}
+
+// Lines look like `some avg10=4.03 avg60=3.22 avg300=3.34 total=100191144`.
+fn parse_psi_line(line: &str) -> Option<(&str, PsiAverages)> {
+    let mut fields = line.split_whitespace();
+    let kind = fields.next()?;
+    let (mut avg10, mut avg60, mut avg300, mut total_us) = (None, None, None, None);
+    for field in fields {
+        match field.split_once('=')? {
+            ("avg10", value) => avg10 = Some(value.parse().ok()?),
+            ("avg60", value) => avg60 = Some(value.parse().ok()?),
+            ("avg300", value) => avg300 = Some(value.parse().ok()?),
+            ("total", value) => total_us = Some(value.parse().ok()?),
+            _ => {}
+        }
+    }
+    Some((kind, PsiAverages { avg10: avg10?, avg60: avg60?, avg300: avg300?, total_us: total_us? }))
+}
+
+// On hybrid setups cgroup2 lives below the v1 hierarchy, e.g. /sys/fs/cgroup/unified.
+fn find_cgroup2_mount() -> Option<PathBuf> {
+    let mounts = fs::read_to_string(MOUNTS_PATH).ok()?;
+    mounts.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let mount_point = fields.nth(1)?;
+        (fields.next()? == "cgroup2").then(|| PathBuf::from(mount_point))
+    })
+}
+
+fn top_level_cgroups(root: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            entry.file_type().ok()?.is_dir().then_some(())?;
+            entry.file_name().into_string().ok()
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // As read from /proc/pressure/memory on a busy machine.
+    const MEMORY: &str = "\
+some avg10=4.03 avg60=3.22 avg300=3.34 total=100191144
+full avg10=1.50 avg60=0.98 avg300=0.75 total=40276331
+";
+
+    fn averages(resource: &PsiAverages) -> (f64, f64, f64, u64) {
+        (resource.avg10, resource.avg60, resource.avg300, resource.total_us)
+    }
+
+    #[test]
+    fn parses_some_and_full_lines() {
+        let psi = parse_psi(MEMORY).unwrap();
+        assert_eq!(averages(&psi.some), (4.03, 3.22, 3.34, 100191144));
+        assert_eq!(psi.full.as_ref().map(averages), Some((1.5, 0.98, 0.75, 40276331)));
+    }
+
+    #[test]
+    fn full_is_optional() {
+        // CPU before Linux 5.13 has only a `some` line.
+        let psi = parse_psi("some avg10=0.00 avg60=0.12 avg300=0.08 total=8391740\n").unwrap();
+        assert_eq!(averages(&psi.some), (0.0, 0.12, 0.08, 8391740));
+        assert!(psi.full.is_none());
+    }
+
+    #[test]
+    fn skips_lines_that_do_not_parse() {
+        let contents = "\
+some avg10=4.03 avg60=3.22 avg300=3.34 total=100191144
+full avg10=garbage avg60=0.98 avg300=0.75 total=40276331
+";
+        let psi = parse_psi(contents).unwrap();
+        assert_eq!(psi.some.total_us, 100191144);
+        assert!(psi.full.is_none());
+
+        // A broken `some` line loses only itself, and the resource with it.
+        let contents = "\
+some avg10=4.03 avg60=3.22 total=100191144
+
+full avg10=1.50 avg60=0.98 avg300=0.75 total=40276331
+";
+        assert!(parse_psi(contents).is_none());
+        assert_eq!(parse_psi_line("full avg10=1.50 avg60=0.98 avg300=0.75 total=40276331").map(|(kind, _)| kind), Some("full"));
+    }
+
+    #[test]
+    fn ignores_unknown_lines_and_fields() {
+        let contents = "\
+some avg10=4.03 avg60=3.22 avg300=3.34 avg900=3.00 total=100191144
+partial avg10=1.00 avg60=1.00 avg300=1.00 total=1
+full avg10=1.50 avg60=0.98 avg300=0.75 total=40276331
+";
+        let psi = parse_psi(contents).unwrap();
+        assert_eq!(averages(&psi.some), (4.03, 3.22, 3.34, 100191144));
+        assert_eq!(psi.full.map(|full| full.total_us), Some(40276331));
+    }
+
+    #[test]
+    fn later_lines_of_a_kind_win() {
+        let contents = "\
+some avg10=1.00 avg60=1.00 avg300=1.00 total=1
+some avg10=2.00 avg60=2.00 avg300=2.00 total=2
+";
+        assert_eq!(parse_psi(contents).unwrap().some.total_us, 2);
+    }
+
+    #[test]
+    fn needs_a_some_line() {
+        assert!(parse_psi("").is_none());
+        assert!(parse_psi("full avg10=1.50 avg60=0.98 avg300=0.75 total=40276331\n").is_none());
+    }
+
+    #[test]
+    fn reads_cgroup_pressure_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("cpu.pressure"), "some avg10=0.50 avg60=0.25 avg300=0.10 total=5000\n").unwrap();
+        fs::write(dir.path().join("memory.pressure"), MEMORY).unwrap();
+        let pressure = read_pressure(dir.path(), ".pressure");
+        assert_eq!(pressure.cpu.map(|cpu| cpu.some.total_us), Some(5000));
+        assert_eq!(pressure.memory.and_then(|memory| memory.full).map(|full| full.avg10), Some(1.5));
+        assert!(pressure.io.is_none());
+    }
+}
@@ -1,36 +1,122 @@
 use super::traits::MetricsCollector;
 use crate::config::Config;
 use crate::error::AppError;
-use crate::state::data_types::{ProcessInfo, ProcessStatus};
+use crate::state::data_types::ProcessInfo;
 
-use sysinfo::System; // Import necessary traits
+use regex::Regex;
+use sysinfo::{Gid, Groups, Pid, Process, ProcessRefreshKind, System, ThreadKind, UpdateKind, Users};
+use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Instant;
 
 pub struct ProcessCollector {
     system:System,
+    users: Users,
+    groups: Groups,
+    exclude_names: Vec<Regex>,
+    hide_threads: bool,
+    // When the processes were last refreshed, for disk I/O rates.
+    previous_at: Option<Instant>,
 }
 
 impl MetricsCollector for ProcessCollector {
     type CollectedData = Vec<ProcessInfo>;
 
-    fn new(_config: Arc<Config>) -> Result<Self, AppError> {
-        Ok(ProcessCollector { system: System::new_all() })
+    fn new(config: Arc<Config>) -> Result<Self, AppError> {
+        let exclude_names = config.processes.exclude_names
+            .iter()
+            .map(|pattern| Regex::new(pattern))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::CollectionError(format!("Invalid process filter: {}", e)))?;
+        let mut system = System::new();
+        // Prime CPU and disk counters so the first sample has usable values.
+        system.refresh_processes_specifics(sysinfo::ProcessesToUpdate::All, true, refresh_kind());
+        Ok(ProcessCollector {
+            system,
+            users: Users::new_with_refreshed_list(),
+            groups: Groups::new_with_refreshed_list(),
+            exclude_names,
+            hide_threads: config.processes.hide_threads,
+            previous_at: Some(Instant::now()),
+        })
     }
 
     fn collect(&mut self) -> Result<Self::CollectedData, AppError> {
-        self.system.refresh_processes(sysinfo::ProcessesToUpdate::All, true);
+        self.system.refresh_processes_specifics(sysinfo::ProcessesToUpdate::All, true, refresh_kind());
+        // Accounts may have been added since the last refresh.
+        self.users.refresh();
+        self.groups.refresh();
 
+        let now = Instant::now();
+        let elapsed_secs = self.previous_at.map(|at| now.duration_since(at).as_secs_f64());
+        self.previous_at = Some(now);
+
+        let group_names: HashMap<&Gid, &str> = self.groups.list().iter().map(|g| (g.id(), g.name())).collect();
         let processes: Vec<ProcessInfo> = self.system
         .processes()
         .iter()
-        .map(|(pid, process)| ProcessInfo {
+        .filter(|(_, process)| !(self.hide_threads && process.thread_kind().is_some()))
+        .filter(|(_, process)| {
+            let name = process.name().to_string_lossy();
+            !self.exclude_names.iter().any(|pattern| pattern.is_match(&name))
+        })
+        .map(|(pid, process)| self.process_info(*pid, process, &group_names, elapsed_secs))
+        .collect();
+
+        Ok(processes)
+    }
+}
+
+impl ProcessCollector {
+    fn process_info(&self, pid: Pid, process: &Process, group_names: &HashMap<&Gid, &str>, elapsed_secs: Option<f64>) -> ProcessInfo {
+        // sysinfo leaves the owner unset on threads; they share their process's.
+        let owner = process.parent().and_then(|parent| self.system.process(parent));
+        let user_id = process.user_id().or_else(|| owner?.user_id());
+        let group_id = process.group_id().or_else(|| owner?.group_id());
+
+        let disk_usage = process.disk_usage();
+        let rate = |bytes: u64| elapsed_secs.filter(|secs| *secs > 0.0).map_or(0.0, |secs| bytes as f64 / secs);
+
+        ProcessInfo {
             pid: pid.as_u32(),
-            name: process.name().to_str().unwrap().to_string(),
+            ppid: process.parent().map(|parent| parent.as_u32()),
+            name: process.name().to_string_lossy().into_owned(),
+            cmd: process.cmd().iter().map(|arg| arg.to_string_lossy().into_owned()).collect(),
+            exe: process.exe().map(lossy_path),
+            cwd: process.cwd().map(lossy_path),
             cpu_usage_percent: process.cpu_usage(),
-            memory_usage_kb: process.memory(),
+            memory_usage_kb: process.memory() / 1024,
+            virtual_memory_kb: process.virtual_memory() / 1024,
             status: process.status().into(),
-        }).collect();
-
-        Ok(processes)
+            is_thread: process.thread_kind() == Some(ThreadKind::Userland),
+            thread_count: process.tasks().map(|tasks| tasks.len()),
+            user: user_id.and_then(|uid| self.users.get_user_by_id(uid)).map(|user| user.name().to_string()),
+            group: group_id.and_then(|gid| group_names.get(&gid)).map(|name| name.to_string()),
+            start_time_secs: process.start_time(),
+            run_time_secs: process.run_time(),
+            disk_read_bytes_total: disk_usage.total_read_bytes,
+            disk_written_bytes_total: disk_usage.total_written_bytes,
+            disk_read_bytes_per_sec: rate(disk_usage.read_bytes),
+            disk_written_bytes_per_sec: rate(disk_usage.written_bytes),
+        }
     }
-}
\ No newline at end of file
+}
+
+// Command line, exe and owner don't change after exec, so they are only read
+// for new processes. The working directory can.
+fn refresh_kind() -> ProcessRefreshKind {
+    ProcessRefreshKind::nothing()
+        .with_memory()
+        .with_cpu()
+        .with_disk_usage()
+        .with_exe(UpdateKind::OnlyIfNotSet)
+        .with_cmd(UpdateKind::OnlyIfNotSet)
+        .with_user(UpdateKind::OnlyIfNotSet)
+        .with_cwd(UpdateKind::Always)
+        .with_tasks()
+}
+
+fn lossy_path(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
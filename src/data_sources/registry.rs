@@ -0,0 +1,83 @@
+use super::channel::UpdateReceiver;
+use super::scheduler::{IntervalHandle, Scheduler};
+use super::traits::MetricsCollector;
+use crate::config::Config;
+use crate::shutdown::{join_with_timeout, ShutdownSignal};
+use crate::state::data_types::{AppStateUpdate, DataSource, Sample, Timestamp};
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Instant;
+
+/// Starts collectors, each sending into its own channel of `updates`. Owns
+/// every thread it spawns so they can be joined on shutdown.
+pub struct CollectorRegistry {
+    config: Arc<Config>,
+    updates: UpdateReceiver,
+    shutdown_signal: ShutdownSignal,
+    handles: Vec<(DataSource, JoinHandle<()>)>,
+    intervals: HashMap<DataSource, IntervalHandle>,
+}
+
+impl CollectorRegistry {
+    pub fn new(config: Arc<Config>, shutdown_signal: ShutdownSignal) -> Self {
+        CollectorRegistry {
+            config,
+            updates: UpdateReceiver::new(),
+            shutdown_signal,
+            handles: Vec::new(),
+            intervals: HashMap::new(),
+        }
+    }
+
+    /// Creates collector `C` for `source` and starts it, unless the source is
+    /// disabled in the config. Sources that are disabled or fail to initialise
+    /// are reported to the app as unavailable rather than treated as fatal.
+    pub fn register<C: MetricsCollector>(&mut self, source: DataSource) {
+        if !self.config.is_collector_enabled(source.name()) {
+            log::info!("{} collector disabled by config.", source);
+            self.report_unavailable(source, "disabled in config".to_string());
+            return;
+        }
+
+        let collector = match C::new(self.config.clone()) {
+            Ok(collector) => collector,
+            Err(e) => {
+                log::warn!("{} collector unavailable: {}", source, e);
+                self.report_unavailable(source, e.to_string());
+                return;
+            }
+        };
+
+        let interval = IntervalHandle::new(self.config.refresh_interval_ms_for(source.name()));
+        let scheduler = Scheduler::new(interval.clone(), self.config.jitter_ms_for(source.name()));
+        self.intervals.insert(source, interval);
+
+        let sender = self.updates.add_source(source);
+        let handle = collector.run_in_thread(sender, self.shutdown_signal.clone(), scheduler);
+        self.handles.push((source, handle));
+    }
+
+    /// Where the app receives updates from every registered source.
+    pub fn updates(&self) -> UpdateReceiver {
+        self.updates.clone()
+    }
+
+    /// Handles for changing each running collector's interval at runtime.
+    pub fn intervals(&self) -> HashMap<DataSource, IntervalHandle> {
+        self.intervals.clone()
+    }
+
+    /// Waits until `deadline` for every collector thread to finish. Call after
+    /// triggering the shutdown signal.
+    pub fn join(self, deadline: Instant) {
+        let handles = self.handles.into_iter().map(|(source, handle)| (format!("{} collector", source), handle)).collect();
+        join_with_timeout(handles, deadline);
+    }
+
+    fn report_unavailable(&mut self, source: DataSource, reason: String) {
+        let update = AppStateUpdate::SourceUnavailable { source, reason };
+        self.updates.add_source(source).send(Sample::new(Timestamp::now(), update));
+    }
+}
@@ -0,0 +1,250 @@
+use crate::shutdown::ShutdownSignal;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+// Upper bound on a single sleep so interval changes are noticed promptly even
+// when the interval is long. Shutdown interrupts the sleep directly.
+const MAX_SLEEP_SLICE: Duration = Duration::from_millis(100);
+
+/// A collector's refresh interval, shared between its thread and whoever
+/// wants to change it at runtime.
+#[derive(Debug, Clone)]
+pub struct IntervalHandle(Arc<AtomicU64>);
+
+impl IntervalHandle {
+    pub fn new(interval_ms: u64) -> Self {
+        IntervalHandle(Arc::new(AtomicU64::new(interval_ms.max(1))))
+    }
+
+    pub fn get_ms(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn set_ms(&self, interval_ms: u64) {
+        self.0.store(interval_ms.max(1), Ordering::Relaxed);
+    }
+}
+
+/// Paces a collector loop on a fixed tick grid. Ticks fall on
+/// `anchor + n * interval`, so time spent collecting does not push later
+/// samples back; ticks missed because a collection overran are skipped.
+pub struct Scheduler {
+    interval: IntervalHandle,
+    jitter: Duration,
+    anchor: Instant,
+    anchor_interval_ms: u64,
+    last_tick: Option<Instant>,
+}
+
+impl Scheduler {
+    pub fn new(interval: IntervalHandle, jitter_ms: u64) -> Self {
+        let anchor_interval_ms = interval.get_ms();
+        Scheduler {
+            interval,
+            jitter: Duration::from_millis(jitter_ms),
+            anchor: Instant::now(),
+            anchor_interval_ms,
+            last_tick: None,
+        }
+    }
+
+    /// Blocks until the next tick and returns its grid time, or `None` once
+    /// the shutdown signal is set. The first call returns immediately.
+    pub fn wait_for_tick(&mut self, shutdown_signal: &ShutdownSignal) -> Option<Instant> {
+        let Some(last_tick) = self.last_tick else {
+            let now = Instant::now();
+            self.anchor = now;
+            self.last_tick = Some(now);
+            return Some(now);
+        };
+
+        'schedule: loop {
+            // An interval change starts a new grid from the last tick.
+            let interval_ms = self.interval.get_ms();
+            if interval_ms != self.anchor_interval_ms {
+                self.anchor = last_tick;
+                self.anchor_interval_ms = interval_ms;
+            }
+
+            let tick = self.next_grid_point(last_tick, Instant::now());
+            let wake_at = tick + self.random_jitter();
+            loop {
+                if shutdown_signal.is_set() {
+                    return None;
+                }
+                if self.interval.get_ms() != self.anchor_interval_ms {
+                    continue 'schedule;
+                }
+                let now = Instant::now();
+                if now >= wake_at {
+                    self.last_tick = Some(tick);
+                    return Some(tick);
+                }
+                if shutdown_signal.sleep((wake_at - now).min(MAX_SLEEP_SLICE)) {
+                    return None;
+                }
+            }
+        }
+    }
+
+    // The first grid point after `last_tick` that has not passed by `now`.
+    fn next_grid_point(&self, last_tick: Instant, now: Instant) -> Instant {
+        let interval = Duration::from_millis(self.anchor_interval_ms);
+        let from = now.max(last_tick);
+        let elapsed = from.duration_since(self.anchor).as_nanos();
+        let ticks = elapsed.div_ceil(interval.as_nanos());
+        let mut tick = self.anchor + Duration::from_nanos((ticks * interval.as_nanos()) as u64);
+        if tick <= last_tick {
+            tick += interval;
+        }
+        tick
+    }
+
+    fn random_jitter(&self) -> Duration {
+        if self.jitter.is_zero() {
+            return Duration::ZERO;
+        }
+        Duration::from_millis(fastrand::u64(0..self.jitter.as_millis() as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    // A scheduler whose grid starts at `anchor`, as if its first tick was then.
+    fn scheduler(interval_ms: u64, jitter_ms: u64, anchor: Instant) -> Scheduler {
+        let mut scheduler = Scheduler::new(IntervalHandle::new(interval_ms), jitter_ms);
+        scheduler.anchor = anchor;
+        scheduler.last_tick = Some(anchor);
+        scheduler
+    }
+
+    #[test]
+    fn ticks_fall_on_the_grid() {
+        let anchor = Instant::now();
+        let scheduler = scheduler(100, 0, anchor);
+        // Collection time doesn't push the next tick back.
+        assert_eq!(scheduler.next_grid_point(anchor, anchor + 30 * MS), anchor + 100 * MS);
+        assert_eq!(scheduler.next_grid_point(anchor + 100 * MS, anchor + 199 * MS), anchor + 200 * MS);
+        // A tick due right now is still taken.
+        assert_eq!(scheduler.next_grid_point(anchor + 100 * MS, anchor + 200 * MS), anchor + 200 * MS);
+    }
+
+    #[test]
+    fn skips_ticks_missed_by_an_overrun() {
+        let anchor = Instant::now();
+        let scheduler = scheduler(100, 0, anchor);
+        assert_eq!(scheduler.next_grid_point(anchor, anchor + 250 * MS), anchor + 300 * MS);
+        assert_eq!(scheduler.next_grid_point(anchor, anchor + 1001 * MS), anchor + 1100 * MS);
+    }
+
+    #[test]
+    fn never_repeats_the_last_tick() {
+        let anchor = Instant::now();
+        let scheduler = scheduler(100, 0, anchor);
+        assert_eq!(scheduler.next_grid_point(anchor, anchor), anchor + 100 * MS);
+        // A clock reading from before the last tick counts from the tick.
+        assert_eq!(scheduler.next_grid_point(anchor + 100 * MS, anchor + 50 * MS), anchor + 200 * MS);
+    }
+
+    #[test]
+    fn jitter_stays_below_its_bound() {
+        let anchor = Instant::now();
+        assert_eq!(scheduler(100, 0, anchor).random_jitter(), Duration::ZERO);
+        let scheduler = scheduler(100, 20, anchor);
+        assert!((0..1000).all(|_| scheduler.random_jitter() < 20 * MS));
+    }
+
+    #[test]
+    fn intervals_are_at_least_a_millisecond() {
+        let interval = IntervalHandle::new(0);
+        assert_eq!(interval.get_ms(), 1);
+        interval.set_ms(0);
+        assert_eq!(interval.get_ms(), 1);
+    }
+
+    #[test]
+    fn first_tick_is_immediate() {
+        let shutdown_signal = ShutdownSignal::new();
+        let mut scheduler = Scheduler::new(IntervalHandle::new(10_000), 0);
+        let started = Instant::now();
+        let tick = scheduler.wait_for_tick(&shutdown_signal).unwrap();
+        assert!(tick >= started && started.elapsed() < 50 * MS);
+    }
+
+    #[test]
+    fn waits_for_the_next_tick() {
+        let shutdown_signal = ShutdownSignal::new();
+        let mut scheduler = Scheduler::new(IntervalHandle::new(50), 0);
+        let first = scheduler.wait_for_tick(&shutdown_signal).unwrap();
+        let second = scheduler.wait_for_tick(&shutdown_signal).unwrap();
+        assert_eq!(second, first + 50 * MS);
+        assert!(Instant::now() >= second);
+    }
+
+    #[test]
+    fn shutdown_interrupts_a_long_wait() {
+        let shutdown_signal = ShutdownSignal::new();
+        let mut scheduler = Scheduler::new(IntervalHandle::new(60_000), 0);
+        scheduler.wait_for_tick(&shutdown_signal).unwrap();
+
+        let trigger = shutdown_signal.clone();
+        let thread = std::thread::spawn(move || {
+            std::thread::sleep(30 * MS);
+            trigger.trigger();
+        });
+        let started = Instant::now();
+        assert_eq!(scheduler.wait_for_tick(&shutdown_signal), None);
+        // Woken by the signal itself, well within one sleep slice.
+        assert!(started.elapsed() < 30 * MS + MAX_SLEEP_SLICE);
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn an_interval_change_moves_the_next_tick() {
+        let shutdown_signal = ShutdownSignal::new();
+        let interval = IntervalHandle::new(60_000);
+        let mut scheduler = Scheduler::new(interval.clone(), 0);
+        let first = scheduler.wait_for_tick(&shutdown_signal).unwrap();
+
+        let changer = interval.clone();
+        let thread = std::thread::spawn(move || {
+            std::thread::sleep(30 * MS);
+            changer.set_ms(200);
+        });
+        // Noticed at the end of the current sleep slice; the new grid starts
+        // from the last tick.
+        let second = scheduler.wait_for_tick(&shutdown_signal).unwrap();
+        assert_eq!(second, first + 200 * MS);
+        assert!(first.elapsed() < 200 * MS + MAX_SLEEP_SLICE * 2);
+        thread.join().unwrap();
+
+        let third = scheduler.wait_for_tick(&shutdown_signal).unwrap();
+        assert_eq!(third, first + 400 * MS);
+    }
+
+    #[test]
+    fn a_shorter_interval_takes_effect_within_a_slice() {
+        let shutdown_signal = ShutdownSignal::new();
+        let interval = IntervalHandle::new(60_000);
+        let mut scheduler = Scheduler::new(interval.clone(), 0);
+        let first = scheduler.wait_for_tick(&shutdown_signal).unwrap();
+
+        let changer = interval.clone();
+        let thread = std::thread::spawn(move || {
+            std::thread::sleep(150 * MS);
+            changer.set_ms(10);
+        });
+        // The ticks at 10ms, 20ms, ... have passed by the time the change is
+        // seen, so the next one is the first still ahead.
+        let second = scheduler.wait_for_tick(&shutdown_signal).unwrap();
+        let waited = second.duration_since(first);
+        assert!(waited >= 150 * MS && waited < 150 * MS + MAX_SLEEP_SLICE + 20 * MS, "{:?}", waited);
+        assert_eq!(waited.as_nanos() % (10 * MS).as_nanos(), 0);
+        thread.join().unwrap();
+    }
+}
@@ -0,0 +1,62 @@
+use crate::state::app_state::SelfUsage;
+
+use std::time::Instant;
+
+const SELF_STAT_PATH: &str = "/proc/self/stat";
+
+/// Measures monoxide's own CPU and memory use from `/proc/self/stat`. Not a
+/// collector: the app samples it directly so the numbers stay available even
+/// when every collector is stuck.
+pub struct SelfMonitor {
+    clock_ticks_per_sec: f64,
+    page_size: u64,
+    // CPU ticks used so far and when they were read.
+    previous: Option<(Instant, u64)>,
+}
+
+impl SelfMonitor {
+    pub fn new() -> Self {
+        // SAFETY: sysconf has no memory-safety preconditions.
+        let (ticks, page_size) = unsafe { (libc::sysconf(libc::_SC_CLK_TCK), libc::sysconf(libc::_SC_PAGESIZE)) };
+        SelfMonitor {
+            clock_ticks_per_sec: if ticks > 0 { ticks as f64 } else { 100.0 },
+            page_size: u64::try_from(page_size).unwrap_or(4096),
+            previous: None,
+        }
+    }
+
+    /// Usage since the previous call; `None` on the first call, when there is
+    /// nothing to compare against yet, or if the stat file can't be read.
+    pub fn sample(&mut self) -> Option<SelfUsage> {
+        let contents = std::fs::read_to_string(SELF_STAT_PATH)
+            .map_err(|e| log::debug!("Failed to read {}: {}", SELF_STAT_PATH, e))
+            .ok()?;
+        // The command name may contain spaces, so fields are counted from
+        // after its closing parenthesis, starting at field 3 (state).
+        let fields: Vec<&str> = contents.rsplit_once(')')?.1.split_whitespace().collect();
+        let field = |number: usize| fields.get(number - 3)?.parse::<u64>().ok();
+        let cpu_ticks = field(14)? + field(15)?;
+        let threads = field(20)?;
+        let rss_pages = field(24)?;
+
+        let now = Instant::now();
+        let previous = self.previous.replace((now, cpu_ticks));
+        let (then, previous_ticks) = previous?;
+        let elapsed = now.duration_since(then).as_secs_f64();
+        if elapsed <= 0.0 {
+            return None;
+        }
+        let cpu_secs = cpu_ticks.saturating_sub(previous_ticks) as f64 / self.clock_ticks_per_sec;
+        Some(SelfUsage {
+            cpu_percent: (cpu_secs / elapsed * 100.0) as f32,
+            rss_bytes: rss_pages * self.page_size,
+            threads: threads as u32,
+        })
+    }
+}
+
+impl Default for SelfMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,326 @@
+use super::traits::MetricsCollector;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::state::data_types::{SensorData, SensorKind, SensorReading};
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Temperatures, fan speeds and voltages from hwmon chips, plus the kernel's
+/// thermal zones. Readings the driver refuses to report are skipped.
+pub struct SensorsCollector {
+    hwmon_dir: PathBuf,
+    thermal_dir: PathBuf,
+}
+
+impl MetricsCollector for SensorsCollector {
+    type CollectedData = SensorData;
+
+    fn new(config: Arc<Config>) -> Result<Self, AppError> {
+        let class_dir = config.sensors.sysfs_root.join("class");
+        let collector = SensorsCollector { hwmon_dir: class_dir.join("hwmon"), thermal_dir: class_dir.join("thermal") };
+        if !collector.hwmon_dir.is_dir() && !collector.thermal_dir.is_dir() {
+            return Err(AppError::CollectionError(format!("no hwmon or thermal class in {}", class_dir.display())));
+        }
+        Ok(collector)
+    }
+
+    fn collect(&mut self) -> Result<Self::CollectedData, AppError> {
+        let chips: Vec<(PathBuf, String)> = numbered_entries(&self.hwmon_dir, "hwmon")
+            .into_iter()
+            .map(|chip_dir| {
+                let name = chip_name(&chip_dir);
+                (chip_dir, name)
+            })
+            .collect();
+        let mut readings = Vec::new();
+        for (chip_dir, name) in &chips {
+            // Two-socket machines have two `coretemp` chips and most with
+            // several NVMe drives have one `nvme` chip per drive, with the
+            // same labels; the hwmon directory tells them apart.
+            let chip = if chips.iter().filter(|(_, other)| other == name).count() > 1 {
+                let dir_name = chip_dir.file_name().unwrap_or_default().to_string_lossy();
+                format!("{}-{}", name, dir_name)
+            } else {
+                name.clone()
+            };
+            read_hwmon_chip(chip_dir, &chip, &mut readings);
+        }
+        for zone_dir in numbered_entries(&self.thermal_dir, "thermal_zone") {
+            readings.extend(read_thermal_zone(&zone_dir));
+        }
+        Ok(SensorData::Global(readings))
+    }
+}
+
+// Entries of `dir` named `<prefix><n>`, ordered by `n`.
+fn numbered_entries(dir: &Path, prefix: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut numbered: Vec<(u32, PathBuf)> = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let index = entry.file_name().to_str()?.strip_prefix(prefix)?.parse().ok()?;
+            Some((index, entry.path()))
+        })
+        .collect();
+    numbered.sort();
+    numbered.into_iter().map(|(_, path)| path).collect()
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|contents| contents.trim().to_string())
+}
+
+fn read_number(path: &Path) -> Option<f64> {
+    read_trimmed(path)?.parse().ok()
+}
+
+// Where a chip's attributes live. Older drivers keep them on the parent
+// device instead.
+fn attribute_dir(chip_dir: &Path) -> PathBuf {
+    if chip_dir.join("name").exists() { chip_dir.to_path_buf() } else { chip_dir.join("device") }
+}
+
+// The driver's name for the chip, e.g. `coretemp`, or the hwmon directory's.
+fn chip_name(chip_dir: &Path) -> String {
+    read_trimmed(&attribute_dir(chip_dir).join("name"))
+        .or_else(|| chip_dir.file_name().map(|name| name.to_string_lossy().into_owned()))
+        .unwrap_or_default()
+}
+
+fn read_hwmon_chip(chip_dir: &Path, chip: &str, readings: &mut Vec<SensorReading>) {
+    let attribute_dir = attribute_dir(chip_dir);
+    let Ok(entries) = fs::read_dir(&attribute_dir) else {
+        return;
+    };
+
+    // (kind, index, attribute prefix such as `temp1`)
+    let mut inputs: Vec<(SensorKind, u32, String)> = entries
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name().into_string().ok()?;
+            let prefix = name.strip_suffix("_input")?;
+            let split = prefix.find(|c: char| c.is_ascii_digit())?;
+            let kind = match &prefix[..split] {
+                "temp" => SensorKind::Temperature,
+                "fan" => SensorKind::Fan,
+                "in" => SensorKind::Voltage,
+                _ => return None,
+            };
+            Some((kind, prefix[split..].parse().ok()?, prefix.to_string()))
+        })
+        .collect();
+    inputs.sort_by_key(|(kind, index, _)| (*kind as u8, *index));
+
+    for (kind, _, prefix) in inputs {
+        // Temperatures are in millidegrees and voltages in millivolts.
+        let scale = match kind {
+            SensorKind::Temperature | SensorKind::Voltage => 1000.0,
+            SensorKind::Fan => 1.0,
+        };
+        let attribute = |suffix: &str| read_number(&attribute_dir.join(format!("{}_{}", prefix, suffix))).map(|v| v / scale);
+        let Some(value) = attribute("input") else {
+            continue;
+        };
+        readings.push(SensorReading {
+            chip: chip.to_string(),
+            label: read_trimmed(&attribute_dir.join(format!("{}_label", prefix))).unwrap_or_else(|| prefix.clone()),
+            kind,
+            value,
+            high: attribute("max"),
+            critical: attribute("crit"),
+        });
+    }
+}
+
+fn read_thermal_zone(zone_dir: &Path) -> Option<SensorReading> {
+    let value = read_number(&zone_dir.join("temp"))? / 1000.0;
+    let zone = zone_dir.file_name()?.to_string_lossy().into_owned();
+    let label = read_trimmed(&zone_dir.join("type")).unwrap_or_else(|| zone.clone());
+
+    // Trip points are numbered from 0; the first hot and critical ones win.
+    let mut high = None;
+    let mut critical = None;
+    for index in 0.. {
+        let Some(trip_type) = read_trimmed(&zone_dir.join(format!("trip_point_{}_type", index))) else {
+            break;
+        };
+        let temp = || read_number(&zone_dir.join(format!("trip_point_{}_temp", index))).map(|t| t / 1000.0);
+        match trip_type.as_str() {
+            "hot" if high.is_none() => high = temp(),
+            "critical" if critical.is_none() => critical = temp(),
+            _ => {}
+        }
+    }
+    Some(SensorReading { chip: zone, label, kind: SensorKind::Temperature, value, high, critical })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SensorsConfig;
+
+    // Writes `files` (path relative to `dir`, contents) below `dir`.
+    fn write_files(dir: &Path, files: &[(&str, &str)]) {
+        for (path, contents) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+    }
+
+    fn collect(sysfs_root: &Path) -> Vec<SensorReading> {
+        let config = Config { sensors: SensorsConfig { sysfs_root: sysfs_root.to_path_buf() }, ..Config::default() };
+        let mut collector = SensorsCollector::new(Arc::new(config)).unwrap();
+        let SensorData::Global(readings) = collector.collect().unwrap();
+        readings
+    }
+
+    fn find<'a>(readings: &'a [SensorReading], chip: &str, label: &str) -> &'a SensorReading {
+        readings
+            .iter()
+            .find(|reading| reading.chip == chip && reading.label == label)
+            .unwrap_or_else(|| panic!("no reading {}/{} in {:?}", chip, label, readings))
+    }
+
+    // A fake sysfs with one hwmon chip and one thermal zone.
+    fn fake_sysfs() -> tempfile::TempDir {
+        let root = tempfile::tempdir().unwrap();
+        write_files(
+            &root.path().join("class/hwmon/hwmon0"),
+            &[
+                ("name", "coretemp\n"),
+                ("temp1_input", "45500\n"),
+                ("temp1_label", "Package id 0\n"),
+                ("temp1_max", "80000\n"),
+                ("temp1_crit", "100000\n"),
+                ("temp2_input", "38000\n"),
+                ("fan1_input", "1200\n"),
+                ("in0_input", "1250\n"),
+                ("in0_label", "Vcore\n"),
+            ],
+        );
+        write_files(
+            &root.path().join("class/thermal/thermal_zone0"),
+            &[
+                ("type", "x86_pkg_temp\n"),
+                ("temp", "52000\n"),
+                ("trip_point_0_type", "passive\n"),
+                ("trip_point_0_temp", "70000\n"),
+                ("trip_point_1_type", "hot\n"),
+                ("trip_point_1_temp", "90000\n"),
+                ("trip_point_2_type", "critical\n"),
+                ("trip_point_2_temp", "105000\n"),
+            ],
+        );
+        root
+    }
+
+    #[test]
+    fn scales_hwmon_readings_and_uses_labels() {
+        let root = fake_sysfs();
+        let readings = collect(root.path());
+
+        let package = find(&readings, "coretemp", "Package id 0");
+        assert_eq!(package.kind, SensorKind::Temperature);
+        assert_eq!(package.value, 45.5);
+        assert_eq!(package.high, Some(80.0));
+        assert_eq!(package.critical, Some(100.0));
+
+        // Unlabelled inputs fall back to the attribute name.
+        let unlabelled = find(&readings, "coretemp", "temp2");
+        assert_eq!(unlabelled.value, 38.0);
+        assert_eq!(unlabelled.critical, None);
+
+        let fan = find(&readings, "coretemp", "fan1");
+        assert_eq!(fan.kind, SensorKind::Fan);
+        assert_eq!(fan.value, 1200.0);
+
+        let vcore = find(&readings, "coretemp", "Vcore");
+        assert_eq!(vcore.kind, SensorKind::Voltage);
+        assert_eq!(vcore.value, 1.25);
+    }
+
+    #[test]
+    fn orders_readings_by_kind_then_index() {
+        let root = fake_sysfs();
+        let labels: Vec<String> =
+            collect(root.path()).into_iter().filter(|r| r.chip == "coretemp").map(|r| r.label).collect();
+        assert_eq!(labels, ["Package id 0", "temp2", "fan1", "Vcore"]);
+    }
+
+    #[test]
+    fn reads_thermal_zone_trip_points() {
+        let root = fake_sysfs();
+        let readings = collect(root.path());
+
+        let zone = find(&readings, "thermal_zone0", "x86_pkg_temp");
+        assert_eq!(zone.value, 52.0);
+        // The passive trip point is neither hot nor critical.
+        assert_eq!(zone.high, Some(90.0));
+        assert_eq!(zone.critical, Some(105.0));
+    }
+
+    #[test]
+    fn skips_unreadable_and_malformed_files() {
+        let root = fake_sysfs();
+        let hwmon = root.path().join("class/hwmon/hwmon0");
+        // A driver that can't read a sensor makes its input fail to read.
+        fs::create_dir(hwmon.join("temp3_input")).unwrap();
+        write_files(&hwmon, &[("temp4_input", "N/A\n"), ("temp5_input", "30000\n"), ("temp5_crit", "bogus\n")]);
+        // A zone without a temperature is left out entirely.
+        write_files(&root.path().join("class/thermal/thermal_zone1"), &[("type", "acpitz\n")]);
+
+        let readings = collect(root.path());
+        assert!(!readings.iter().any(|r| r.label == "temp3" || r.label == "temp4"));
+        assert!(!readings.iter().any(|r| r.chip == "thermal_zone1"));
+        let temp5 = find(&readings, "coretemp", "temp5");
+        assert_eq!(temp5.value, 30.0);
+        assert_eq!(temp5.critical, None);
+    }
+
+    #[test]
+    fn reads_attributes_from_the_device_directory_of_older_drivers() {
+        let root = tempfile::tempdir().unwrap();
+        write_files(
+            &root.path().join("class/hwmon/hwmon1/device"),
+            &[("name", "it8728\n"), ("fan2_input", "900\n")],
+        );
+        let readings = collect(root.path());
+        assert_eq!(find(&readings, "it8728", "fan2").value, 900.0);
+    }
+
+    #[test]
+    fn numbers_chips_numerically() {
+        let root = tempfile::tempdir().unwrap();
+        for (chip, name) in [("hwmon10", "ten"), ("hwmon2", "two")] {
+            write_files(&root.path().join("class/hwmon").join(chip), &[("name", name), ("temp1_input", "1000")]);
+        }
+        let chips: Vec<String> = collect(root.path()).into_iter().map(|r| r.chip).collect();
+        assert_eq!(chips, ["two", "ten"]);
+    }
+
+    #[test]
+    fn tells_apart_chips_with_the_same_name() {
+        let root = fake_sysfs();
+        let hwmon = root.path().join("class/hwmon");
+        write_files(&hwmon.join("hwmon1"), &[("name", "coretemp\n"), ("temp1_input", "47000\n"), ("temp1_label", "Package id 0\n")]);
+        write_files(&hwmon.join("hwmon2"), &[("name", "nvme\n"), ("temp1_input", "35000\n"), ("temp1_label", "Composite\n")]);
+
+        let readings = collect(root.path());
+        assert_eq!(find(&readings, "coretemp-hwmon0", "Package id 0").value, 45.5);
+        assert_eq!(find(&readings, "coretemp-hwmon1", "Package id 0").value, 47.0);
+        // A chip whose name is unique keeps it as is.
+        assert_eq!(find(&readings, "nvme", "Composite").value, 35.0);
+        assert!(!readings.iter().any(|r| r.chip == "coretemp"));
+    }
+
+    #[test]
+    fn fails_without_hwmon_or_thermal_classes() {
+        let root = tempfile::tempdir().unwrap();
+        let config = Config { sensors: SensorsConfig { sysfs_root: root.path().to_path_buf() }, ..Config::default() };
+        assert!(SensorsCollector::new(Arc::new(config)).is_err());
+    }
+}
@@ -3,7 +3,7 @@ use crate::config::Config;
 use crate::error::AppError;
 use crate::state::data_types::{SystemStatsData, SystemStats};
 
-use sysinfo::{System};
+use sysinfo::{System, Users};
 use std::sync::Arc;
 
 pub struct SystemStatsCollector {
@@ -13,7 +13,7 @@ pub struct SystemStatsCollector {
 impl MetricsCollector for SystemStatsCollector {
     type CollectedData = SystemStatsData;
 
-    fn new(config: Arc<Config>) -> Result<Self, AppError> where Self: Sized {
+    fn new(_config: Arc<Config>) -> Result<Self, AppError> where Self: Sized {
         Ok(SystemStatsCollector { system: System::new() })
     }
 
@@ -25,7 +25,8 @@ impl MetricsCollector for SystemStatsCollector {
         let load_avg = sysinfo::System::load_average();
         let os_version = sysinfo::System::os_version();
         let kernel_version = sysinfo::System::kernel_version();
+        let total_users = Some(Users::new_with_refreshed_list().len());
 
-        Ok(SystemStatsData::Global(SystemStats { hostname, uptime_secs, load_average: (load_avg.one,load_avg.five ,load_avg.fifteen), os_version, kernel_version, total_users: None }))
+        Ok(SystemStatsData::Global(SystemStats { hostname, uptime_secs, load_average: (load_avg.one,load_avg.five ,load_avg.fifteen), os_version, kernel_version, total_users }))
     }
 }
\ No newline at end of file
@@ -1,42 +1,38 @@
+use super::channel::UpdateSender;
+use super::scheduler::Scheduler;
 use crate::error::AppError;
 use crate::config::Config;
-use crossbeam_channel::Sender;
+use crate::shutdown::ShutdownSignal;
+use crate::state::data_types::{AppStateUpdate, Sample, Timestamp};
 use std::sync::Arc;
-use std::sync::atomic::AtomicBool;
 use std::thread::JoinHandle;
 
 
 pub trait MetricsCollector:Send + 'static {
-    type CollectedData: Send + Clone + std::fmt::Debug + 'static;
+    type CollectedData: Send + Clone + std::fmt::Debug + Into<AppStateUpdate> + 'static;
 
     fn new(config: Arc<Config>) -> Result<Self, AppError> where Self: Sized;
 
     fn collect(&mut self) -> Result<Self::CollectedData, AppError>;
-    /// Runs the collector in a dedicated thread, periodically collecting
-    /// and sending data through the provided sender.
-    /// Should respect the shutdown_signal.
+    /// Runs the collector in a dedicated thread, collecting on every tick of
+    /// `scheduler` and sending data, stamped with the tick time, through the
+    /// provided sender. Should respect the shutdown_signal.
     fn run_in_thread(
         mut self,
-        sender: Sender<Self::CollectedData>,
-        shutdown_signal: Arc<AtomicBool>,
-        config: Arc<Config>,
+        sender: UpdateSender,
+        shutdown_signal: ShutdownSignal,
+        mut scheduler: Scheduler,
     ) -> JoinHandle<()> where Self:Sized {
         std::thread::spawn(move || {
             log::debug!("Collector thread started for {}", std::any::type_name::<Self>());
 
-            while !shutdown_signal.load(std::sync::atomic::Ordering::Relaxed) {
-                match self.collect() {
-                    Ok(data) => {
-                        if sender.send(data).is_err(){
-                            log::error!("Failed to send data from {} collector: channel closed.", std::any::type_name::<Self>());
-                            break;
-                        }
-                    }
+            while let Some(tick) = scheduler.wait_for_tick(&shutdown_signal) {
+                match sender.track(|| self.collect()) {
+                    Ok(data) => sender.send(Sample::new(Timestamp::at(tick), data)),
                     Err(e) => {
                         log::error!("Error collecting data in {}: {:?}",std::any::type_name::<Self>(), e);
                     }
                 }
-                std::thread::sleep(std::time::Duration::from_millis(config.refresh_interval_ms));
             }
             log::debug!("Collector thread stopped for {}",std::any::type_name::<Self>());
         })
@@ -1,12 +1,28 @@
 use thiserror::Error;
 
 #[derive(Error, Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum AppError{
     #[error("Data Collection Error: {0}")]
     CollectionError(String),
 
-    #[error("Channel send error: {0}")]
-    ChannelSendError(String),
+    #[error("Invalid config value for `{key}`: {message}")]
+    ConfigError { key: String, message: String },
+
+    #[error("Failed to load config file {0}")]
+    ConfigFileError(String),
+
+    #[error("Failed to start exporter: {0}")]
+    ExporterError(String),
+
+    #[error("Permission denied: cannot {action} process {pid}")]
+    PermissionDenied { pid: u32, action: String },
+
+    #[error("Failed to {action} process {pid}: {message}")]
+    ProcessActionError { pid: u32, action: String, message: String },
+
+    #[error("Recording error: {0}")]
+    RecordingError(String),
 
     #[error("I/O error: {0}")]
     IoError(#[from] std::io::Error),
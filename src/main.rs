@@ -1,144 +1,150 @@
 mod error;
+mod alerting;
+mod cli;
 mod config;
 mod state {
     pub mod app_state;
     pub mod data_types;
+    pub mod history;
 }
-mod data_sources {
-    pub mod traits;
-    pub mod cpu_collector;
-    pub mod process_collector;
-    pub mod memory_collector;
-    pub mod network_collector;
-    pub mod gpu_collector;
-    pub mod system_stats_collector;
-}
+mod data_sources;
 mod app_core;
+mod output;
+mod process_actions;
+mod replay;
+mod shutdown;
+mod ui;
+mod utils;
 
+use crate::alerting::actions::AlertActions;
+use crate::alerting::AlertEngine;
 use crate::app_core::AppCore;
+use crate::cli::Cli;
 use crate::config::Config;
-use crate::data_sources::traits::MetricsCollector;
+use crate::data_sources::registry::CollectorRegistry;
 use crate::data_sources::cpu_collector::CpuCollector;
 use crate::data_sources::process_collector::ProcessCollector;
 use crate::data_sources::memory_collector::MemoryCollector;
 use crate::data_sources::network_collector::NetworkCollector;
 use crate::data_sources::gpu_collector::GpuCollector;
 use crate::data_sources::system_stats_collector::SystemStatsCollector;
-use crate::state::data_types::AppStateUpdate;
+use crate::data_sources::disk_collector::DiskCollector;
+use crate::data_sources::filesystem_collector::FilesystemCollector;
+use crate::data_sources::sensors_collector::SensorsCollector;
+use crate::data_sources::pressure_collector::PressureCollector;
+use crate::output::json::JsonSink;
+use crate::output::prometheus;
+use crate::output::record::RecordSink;
+use crate::replay::Replay;
+use crate::shutdown::{join_with_timeout, ShutdownSignal};
+use crate::state::data_types::DataSource;
+use crate::ui::Tui;
+use crate::ui::input_handlers;
 
-use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
-use crossbeam_channel::unbounded;
-use std::thread;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use clap::Parser;
+use crossbeam_channel::{never, unbounded};
 
-fn main() -> Result<(), error::AppError> {
-    let app_config = Arc::new(Config::default());
-    let shutdown_signal = Arc::new(AtomicBool::new(false));
-    let (update_sender, update_receiver) = unbounded::<AppStateUpdate>();
+// How long threads get to stop once shutdown is triggered before the process
+// exits without them.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
 
-    // CPU collector
-    {
-        let cpu_sender = update_sender.clone();
-        let cpu_shutdown = shutdown_signal.clone();
-        let cpu_config = app_config.clone();
-        let cpu_collector = CpuCollector::new(cpu_config.clone())?;
-        thread::spawn(move || {
-            let (cpu_data_sender, cpu_data_receiver) = unbounded();
-            CpuCollector::run_in_thread(cpu_collector, cpu_data_sender, cpu_shutdown.clone(), cpu_config);
-            while let Ok(cpu_data) = cpu_data_receiver.recv() {
-                if cpu_sender.send(AppStateUpdate::Cpu(cpu_data)).is_err() {
-                    break;
-                }
-            }
-        });
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("monoxide: {}", e);
+        std::process::exit(1);
     }
+}
 
-    // Process collector
-    {
-        let proc_sender = update_sender.clone();
-        let proc_shutdown = shutdown_signal.clone();
-        let proc_config = app_config.clone();
-        let proc_collector = ProcessCollector::new(proc_config.clone())?;
-        thread::spawn(move || {
-            let (proc_data_sender, proc_data_receiver) = unbounded();
-            ProcessCollector::run_in_thread(proc_collector, proc_data_sender, proc_shutdown.clone(), proc_config);
-            while let Ok(proc_data) = proc_data_receiver.recv() {
-                if proc_sender.send(AppStateUpdate::Processes(proc_data)).is_err() {
-                    break;
-                }
-            }
-        });
-    }
+fn run() -> Result<(), error::AppError> {
+    let cli = Cli::parse();
+    let app_config = Arc::new(Config::load(cli.config.as_deref())?);
+    let shutdown_signal = ShutdownSignal::new();
+    let signal_guard = shutdown::handle_signals(shutdown_signal.clone())?;
 
-    // Memory collector
-    {
-        let mem_sender = update_sender.clone();
-        let mem_shutdown = shutdown_signal.clone();
-        let mem_config = app_config.clone();
-        let mem_collector = MemoryCollector::new(mem_config.clone())?;
-        thread::spawn(move || {
-            let (mem_data_sender, mem_data_receiver) = unbounded();
-            MemoryCollector::run_in_thread(mem_collector, mem_data_sender, mem_shutdown.clone(), mem_config);
-            while let Ok(mem_data) = mem_data_receiver.recv() {
-                if mem_sender.send(AppStateUpdate::Memory(mem_data)).is_err() {
-                    break;
-                }
-            }
-        });
+    // A replay supplies every update itself, so no collectors are started.
+    let replay = cli.replay.as_deref().map(Replay::open).transpose()?;
+    let mut registry = CollectorRegistry::new(app_config.clone(), shutdown_signal.clone());
+    if replay.is_none() {
+        registry.register::<CpuCollector>(DataSource::Cpu);
+        registry.register::<ProcessCollector>(DataSource::Processes);
+        registry.register::<MemoryCollector>(DataSource::Memory);
+        registry.register::<NetworkCollector>(DataSource::Network);
+        registry.register::<GpuCollector>(DataSource::Gpu);
+        registry.register::<SystemStatsCollector>(DataSource::SystemStats);
+        registry.register::<DiskCollector>(DataSource::Disk);
+        registry.register::<FilesystemCollector>(DataSource::Filesystems);
+        registry.register::<SensorsCollector>(DataSource::Sensors);
+        registry.register::<PressureCollector>(DataSource::Pressure);
     }
 
-    // Network collector
-    {
-        let net_sender = update_sender.clone();
-        let net_shutdown = shutdown_signal.clone();
-        let net_config = app_config.clone();
-        let net_collector = NetworkCollector::new(net_config.clone())?;
-        thread::spawn(move || {
-            let (net_data_sender, net_data_receiver) = unbounded();
-            NetworkCollector::run_in_thread(net_collector, net_data_sender, net_shutdown.clone(), net_config);
-            while let Ok(net_data) = net_data_receiver.recv() {
-                if net_sender.send(AppStateUpdate::Network(net_data)).is_err() {
-                    break;
-                }
-            }
-        });
+    let (input_sender, input_receiver) = unbounded();
+    let input_receiver = if cli.json.is_some() { never() } else { input_receiver };
+    let snapshot_interval = Duration::from_millis(app_config.refresh_interval_ms);
+    let alerts = AlertEngine::new(&app_config.alerts)?;
+    let mut app_core = AppCore::new(
+        registry.updates(),
+        input_receiver,
+        shutdown_signal.clone(),
+        registry.intervals(),
+        app_config.history_size,
+        snapshot_interval,
+        alerts,
+    );
+    // Alerts replayed from a recording are history, not something to act on.
+    let alert_actions = if app_config.alerts.actions.is_empty() {
+        None
+    } else if replay.is_some() {
+        log::info!("Alert actions are disabled during replay");
+        None
+    } else {
+        let actions = AlertActions::spawn(&app_config.alerts, sysinfo::System::host_name(), shutdown_signal.clone());
+        app_core.set_alert_notifier(actions.notifier());
+        Some(actions)
+    };
+    if let Some(replay) = replay {
+        app_core.set_replay(replay);
     }
-
-    // GPU collector
-    {
-        let gpu_sender = update_sender.clone();
-        let gpu_shutdown = shutdown_signal.clone();
-        let gpu_config = app_config.clone();
-        let gpu_collector = GpuCollector::new(gpu_config.clone())?;
-        thread::spawn(move || {
-            let (gpu_data_sender, gpu_data_receiver) = unbounded();
-            GpuCollector::run_in_thread(gpu_collector, gpu_data_sender, gpu_shutdown.clone(), gpu_config);
-            while let Ok(gpu_data) = gpu_data_receiver.recv() {
-                if gpu_sender.send(AppStateUpdate::Gpu(gpu_data)).is_err() {
-                    break;
-                }
-            }
-        });
+    if let Some(path) = &cli.record {
+        app_core.add_sink(Box::new(RecordSink::create(path)?));
     }
 
-    // System Stats collector
-    {
-        let sys_sender = update_sender.clone();
-        let sys_shutdown = shutdown_signal.clone();
-        let sys_config = app_config.clone();
-        let sys_collector = SystemStatsCollector::new(sys_config.clone())?;
-        thread::spawn(move || {
-            let (sys_data_sender, sys_data_receiver) = unbounded();
-            SystemStatsCollector::run_in_thread(sys_collector, sys_data_sender, sys_shutdown.clone(), sys_config);
-            while let Ok(sys_data) = sys_data_receiver.recv() {
-                if sys_sender.send(AppStateUpdate::SystemStats(sys_data)).is_err() {
-                    break;
-                }
-            }
-        });
-    }
+    let exporter_handle = match cli.prometheus.or(app_config.prometheus.listen) {
+        Some(addr) => Some(prometheus::spawn_exporter(addr, app_core.state.clone(), shutdown_signal.clone())?),
+        None => None,
+    };
 
-    let app_core = AppCore::new(update_receiver, shutdown_signal.clone());
-    app_core.run();
+    let result = if let Some(mode) = cli.json {
+        app_core.add_sink(Box::new(JsonSink::new(std::io::stdout(), mode)));
+        app_core.run(None)
+    } else {
+        let mut tui = Tui::new(&app_config.ui)?;
+        let input_handle = input_handlers::spawn_input_thread(input_sender, shutdown_signal.clone());
+        let result = app_core.run(Some(&mut tui));
 
-    Ok(())
+        shutdown_signal.trigger();
+        drop(tui);
+        let _ = input_handle.join();
+        result
+    };
+
+    shutdown_signal.trigger();
+    drop(app_core);
+    let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+    if let Some(handle) = exporter_handle {
+        join_with_timeout(vec![("Prometheus exporter", handle)], deadline);
+    }
+    if let Some(actions) = alert_actions {
+        actions.join(deadline);
+    }
+    registry.join(deadline);
+    drop(signal_guard);
+
+    // A closed stdout (e.g. piped into `head`) is a normal way to stop
+    // streaming JSON, not an error.
+    match result {
+        Err(error::AppError::IoError(e)) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
 }
@@ -0,0 +1,79 @@
+use super::OutputSink;
+use crate::error::AppError;
+use crate::state::app_state::AppState;
+use crate::state::data_types::{AppStateUpdate, Sample, Timestamp};
+
+use serde::Serialize;
+use std::io::{BufWriter, Write};
+
+/// Version of the newline-delimited JSON format. Bump it whenever a field is
+/// renamed or removed, or its meaning changes; adding fields is compatible.
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum JsonMode {
+    /// One line per collected update.
+    Updates,
+    /// One line per refresh interval with the whole merged state.
+    Snapshot,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+enum Record<'a> {
+    Update {
+        schema_version: u32,
+        timestamp: Timestamp,
+        update: &'a AppStateUpdate,
+    },
+    Snapshot {
+        schema_version: u32,
+        timestamp: Timestamp,
+        state: &'a AppState,
+    },
+}
+
+/// Writes the update stream as newline-delimited JSON.
+pub struct JsonSink<W: Write> {
+    writer: BufWriter<W>,
+    mode: JsonMode,
+}
+
+impl<W: Write> JsonSink<W> {
+    pub fn new(writer: W, mode: JsonMode) -> Self {
+        JsonSink { writer: BufWriter::new(writer), mode }
+    }
+
+    fn write_record(&mut self, record: &Record) -> Result<(), AppError> {
+        serde_json::to_writer(&mut self.writer, record)
+            .map_err(|e| AppError::IoError(e.into()))?;
+        self.writer.write_all(b"\n")?;
+        // Flush per line so consumers see records as they happen.
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+impl<W: Write> OutputSink for JsonSink<W> {
+    fn on_update(&mut self, update: &Sample<AppStateUpdate>, _state: &AppState) -> Result<(), AppError> {
+        if self.mode != JsonMode::Updates {
+            return Ok(());
+        }
+        self.write_record(&Record::Update {
+            schema_version: JSON_SCHEMA_VERSION,
+            timestamp: update.timestamp,
+            update: &update.data,
+        })
+    }
+
+    fn on_tick(&mut self, state: &AppState) -> Result<(), AppError> {
+        if self.mode != JsonMode::Snapshot {
+            return Ok(());
+        }
+        self.write_record(&Record::Snapshot {
+            schema_version: JSON_SCHEMA_VERSION,
+            timestamp: Timestamp::now(),
+            state,
+        })
+    }
+}
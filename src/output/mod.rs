@@ -0,0 +1,23 @@
+pub mod json;
+pub mod prometheus;
+pub mod record;
+
+use crate::error::AppError;
+use crate::state::app_state::AppState;
+use crate::state::data_types::{AppStateUpdate, Sample};
+
+/// Something that consumes the update stream besides the TUI, e.g. the JSON
+/// writer. `AppCore` calls it after each update has been applied to `state`.
+pub trait OutputSink {
+    fn on_update(&mut self, update: &Sample<AppStateUpdate>, state: &AppState) -> Result<(), AppError>;
+
+    /// Called once per global refresh interval with the merged state.
+    fn on_tick(&mut self, _state: &AppState) -> Result<(), AppError> {
+        Ok(())
+    }
+
+    /// Called once when the app stops, to write out anything still buffered.
+    fn finish(&mut self, _state: &AppState) -> Result<(), AppError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,647 @@
+use crate::alerting::AlertState;
+use crate::error::AppError;
+use crate::state::app_state::{AppState, CollectorHealth};
+use crate::shutdown::ShutdownSignal;
+use crate::state::data_types::{
+    CpuData, DataSource, DiskData, DiskMetrics, FilesystemData, FilesystemMetrics, GlobalNetworkMetrics, GpuData,
+    GpuMetrics, MemoryData, MemoryDetails, NetworkData, Pressure, PressureData, PsiAverages, SensorData, SensorKind, SystemStatsData,
+};
+
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tiny_http::{Header, Response, Server};
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+// How often the listener checks the shutdown signal while idle.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// Metric name, help text and how to read the value from a per-device struct.
+type Metric<T, V> = (&'static str, &'static str, fn(&T) -> V);
+
+/// Serves the current `AppState` at `http://<addr>/metrics` in the Prometheus
+/// text format. Each scrape renders whatever the collectors last reported.
+pub fn spawn_exporter(
+    addr: SocketAddr,
+    state: Arc<Mutex<AppState>>,
+    shutdown_signal: ShutdownSignal,
+) -> Result<JoinHandle<()>, AppError> {
+    let server = Server::http(addr).map_err(|e| AppError::ExporterError(format!("{}: {}", addr, e)))?;
+    log::info!("Serving Prometheus metrics on http://{}/metrics", addr);
+
+    Ok(std::thread::spawn(move || {
+        while !shutdown_signal.is_set() {
+            let request = match server.recv_timeout(POLL_INTERVAL) {
+                Ok(Some(request)) => request,
+                Ok(None) => continue,
+                Err(e) => {
+                    log::error!("Prometheus listener failed: {}", e);
+                    break;
+                }
+            };
+
+            let result = if request.url() == "/metrics" {
+                let snapshot = Snapshot::of(&state.lock().unwrap());
+                let body = render(&snapshot);
+                let header = Header::from_bytes("Content-Type", CONTENT_TYPE).unwrap();
+                request.respond(Response::from_string(body).with_header(header))
+            } else {
+                request.respond(Response::from_string("Not found\n").with_status_code(404))
+            };
+            if let Err(e) = result {
+                log::warn!("Failed to answer metrics request: {}", e);
+            }
+        }
+    }))
+}
+
+// Collector health in `DataSource::ALL` order, so scrapes list sources stably.
+fn sorted_health(state: &AppState) -> impl Iterator<Item = (DataSource, &CollectorHealth)> {
+    DataSource::ALL.into_iter().filter_map(|source| state.collector_health.get(&source).map(|health| (source, health)))
+}
+
+/// What a scrape reads from the state, copied out under the lock so the app
+/// isn't held up while the response is formatted.
+pub struct Snapshot {
+    state: AppState,
+    // Only the number of processes is exported; the list itself is large.
+    process_count: Option<usize>,
+}
+
+impl Snapshot {
+    pub fn of(state: &AppState) -> Self {
+        let copy = AppState {
+            cpu: state.cpu.clone(),
+            memory: state.memory.clone(),
+            network: state.network.clone(),
+            gpu: state.gpu.clone(),
+            system_stats: state.system_stats.clone(),
+            disk: state.disk.clone(),
+            filesystems: state.filesystems.clone(),
+            sensors: state.sensors.clone(),
+            pressure: state.pressure.clone(),
+            unavailable: state.unavailable.clone(),
+            dropped_updates: state.dropped_updates.clone(),
+            collector_health: state.collector_health.clone(),
+            self_usage: state.self_usage,
+            last_updated: state.last_updated.clone(),
+            alerts: state.alerts.clone(),
+            ..AppState::new(1)
+        };
+        Snapshot { state: copy, process_count: state.processes.as_ref().map(Vec::len) }
+    }
+}
+
+/// Renders `snapshot` in the Prometheus text exposition format.
+pub fn render(snapshot: &Snapshot) -> String {
+    let state = &snapshot.state;
+    let mut out = MetricsWriter::default();
+
+    out.family("monoxide_source_available", "gauge", "Whether the collector is running (1) or unavailable (0).");
+    for source in DataSource::ALL {
+        let available = !state.unavailable.contains_key(&source);
+        out.sample("monoxide_source_available", &[("source", source.name())], if available { 1.0 } else { 0.0 });
+    }
+    out.family("monoxide_source_last_update_seconds", "gauge", "Unix time of the last sample from the collector.");
+    for source in DataSource::ALL {
+        if let Some(timestamp) = state.last_updated.get(&source) {
+            let seconds = timestamp.unix_millis() as f64 / 1000.0;
+            out.sample("monoxide_source_last_update_seconds", &[("source", source.name())], seconds);
+        }
+    }
+    out.family(
+        "monoxide_source_dropped_updates_total",
+        "counter",
+        "Samples from the collector dropped because a newer one arrived before the app read it.",
+    );
+    for source in DataSource::ALL {
+        let dropped = state.dropped_updates.get(&source).copied().unwrap_or(0);
+        out.sample("monoxide_source_dropped_updates_total", &[("source", source.name())], dropped as f64);
+    }
+    out.family(
+        "monoxide_collector_consecutive_failures",
+        "gauge",
+        "Collections in a row that failed; 0 after a success.",
+    );
+    for (source, health) in sorted_health(state) {
+        out.sample("monoxide_collector_consecutive_failures", &[("source", source.name())], health.consecutive_failures.into());
+    }
+    out.family("monoxide_collector_duration_seconds", "gauge", "How long the collector's latest collection took.");
+    for (source, health) in sorted_health(state) {
+        out.sample("monoxide_collector_duration_seconds", &[("source", source.name())], health.last_duration_ms / 1000.0);
+    }
+    if let Some(usage) = &state.self_usage {
+        out.gauge("monoxide_self_cpu_percent", "CPU used by monoxide itself, in percent of one core.", usage.cpu_percent.into());
+        out.gauge("monoxide_self_resident_memory_bytes", "Resident memory of monoxide itself.", usage.rss_bytes as f64);
+        out.gauge("monoxide_self_threads", "Threads running in monoxide.", usage.threads.into());
+    }
+
+    if let Some(CpuData::GlobalCpuMetrics(cpu)) = &state.cpu {
+        out.family("monoxide_cpu_usage_percent", "gauge", "Total CPU usage across all cores.");
+        out.sample("monoxide_cpu_usage_percent", &[], cpu.total_usage_percent.into());
+        out.family("monoxide_cpu_core_usage_percent", "gauge", "Usage of a single CPU core.");
+        for (core, usage) in cpu.core_usages_percent.iter().enumerate() {
+            out.sample("monoxide_cpu_core_usage_percent", &[("core", &core.to_string())], (*usage).into());
+        }
+        if cpu.core_frequencies_mhz.iter().any(|mhz| *mhz > 0) {
+            out.family("monoxide_cpu_core_frequency_hertz", "gauge", "Current clock of a single CPU core.");
+            for (core, mhz) in cpu.core_frequencies_mhz.iter().enumerate() {
+                out.sample("monoxide_cpu_core_frequency_hertz", &[("core", &core.to_string())], *mhz as f64 * 1e6);
+            }
+        }
+        if let Some(times) = &cpu.times {
+            out.family("monoxide_cpu_time_percent", "gauge", "Share of CPU time spent in each mode over the last interval.");
+            for (mode, percent) in times.total.modes() {
+                out.sample("monoxide_cpu_time_percent", &[("mode", mode)], percent);
+            }
+            out.family("monoxide_cpu_core_time_percent", "gauge", "Share of a core's time spent in each mode over the last interval.");
+            for (core, breakdown) in times.cores.iter().enumerate() {
+                for (mode, percent) in breakdown.modes() {
+                    out.sample("monoxide_cpu_core_time_percent", &[("core", &core.to_string()), ("mode", mode)], percent);
+                }
+            }
+            out.gauge("monoxide_context_switches_per_second", "Context switches per second.", times.context_switches_per_sec);
+            out.gauge("monoxide_interrupts_per_second", "Interrupts serviced per second.", times.interrupts_per_sec);
+        }
+    }
+
+    if let Some(MemoryData::Global(memory)) = &state.memory {
+        fn kb(value: u64) -> f64 {
+            (value * 1024) as f64
+        }
+        out.gauge("monoxide_memory_total_bytes", "Total physical memory.", kb(memory.total_memory_kb));
+        out.gauge("monoxide_memory_used_bytes", "Physical memory in use.", kb(memory.used_memory_kb));
+        out.gauge("monoxide_memory_available_bytes", "Memory available for new allocations.", kb(memory.available_memory_kb));
+        out.gauge("monoxide_swap_total_bytes", "Total swap space.", kb(memory.total_swap_kb));
+        out.gauge("monoxide_swap_used_bytes", "Swap space in use.", kb(memory.used_swap_kb));
+        if let Some(details) = &memory.details {
+            let gauges: [Metric<MemoryDetails, f64>; 14] = [
+                ("monoxide_memory_free_bytes", "Memory not used for anything.", |d| kb(d.free_kb)),
+                ("monoxide_memory_buffers_bytes", "Block device buffers.", |d| kb(d.buffers_kb)),
+                ("monoxide_memory_cached_bytes", "Page cache, including shared memory.", |d| kb(d.cached_kb)),
+                ("monoxide_memory_shared_bytes", "tmpfs and shared memory.", |d| kb(d.shared_kb)),
+                ("monoxide_memory_slab_reclaimable_bytes", "Kernel slab memory that can be reclaimed.", |d| kb(d.slab_reclaimable_kb)),
+                ("monoxide_memory_slab_unreclaimable_bytes", "Kernel slab memory that cannot be reclaimed.", |d| kb(d.slab_unreclaimable_kb)),
+                ("monoxide_memory_dirty_bytes", "Memory waiting to be written back to disk.", |d| kb(d.dirty_kb)),
+                ("monoxide_memory_writeback_bytes", "Memory being written back to disk.", |d| kb(d.writeback_kb)),
+                ("monoxide_memory_huge_pages", "Huge pages in the pool.", |d| d.huge_pages_total as f64),
+                ("monoxide_memory_huge_pages_free", "Huge pages not yet allocated.", |d| d.huge_pages_free as f64),
+                ("monoxide_memory_committed_bytes", "Memory committed to processes.", |d| kb(d.committed_as_kb)),
+                ("monoxide_memory_commit_limit_bytes", "Commit limit under strict overcommit.", |d| kb(d.commit_limit_kb)),
+                ("monoxide_swap_in_bytes_per_second", "Swap-in rate.", |d| d.swap_in_bytes_per_sec),
+                ("monoxide_swap_out_bytes_per_second", "Swap-out rate.", |d| d.swap_out_bytes_per_sec),
+            ];
+            for (name, help, value) in gauges {
+                out.gauge(name, help, value(details));
+            }
+        }
+    }
+
+    if let Some(count) = snapshot.process_count {
+        out.gauge("monoxide_processes", "Number of processes reported.", count as f64);
+    }
+
+    if let Some(NetworkData::Global(interfaces)) = &state.network {
+        let counters: [Metric<GlobalNetworkMetrics, Option<u64>>; 8] = [
+            ("monoxide_network_received_bytes_total", "Bytes received.", |i| Some(i.total_received_bytes)),
+            ("monoxide_network_transmitted_bytes_total", "Bytes transmitted.", |i| Some(i.total_transmitted_bytes)),
+            ("monoxide_network_received_packets_total", "Packets received.", |i| Some(i.total_received_packets)),
+            ("monoxide_network_transmitted_packets_total", "Packets transmitted.", |i| Some(i.total_transmitted_packets)),
+            ("monoxide_network_receive_errors_total", "Receive errors.", |i| Some(i.total_receive_errors)),
+            ("monoxide_network_transmit_errors_total", "Transmit errors.", |i| Some(i.total_transmit_errors)),
+            ("monoxide_network_receive_drops_total", "Received packets dropped.", |i| i.total_receive_drops),
+            ("monoxide_network_transmit_drops_total", "Transmitted packets dropped.", |i| i.total_transmit_drops),
+        ];
+        for (name, help, value) in counters {
+            let mut samples = interfaces.iter().filter_map(|i| value(i).map(|v| (i, v))).peekable();
+            if samples.peek().is_none() {
+                continue;
+            }
+            out.family(name, "counter", help);
+            for (interface, v) in samples {
+                out.sample(name, &[("interface", &interface.interface_name)], v as f64);
+            }
+        }
+    }
+
+    if let Some(GpuData::Nvidia(gpus)) = &state.gpu {
+        let gauges: [Metric<GpuMetrics, f64>; 5] = [
+            ("monoxide_gpu_memory_total_bytes", "Total GPU memory.", |g| (g.memory_total_mb * 1024 * 1024) as f64),
+            ("monoxide_gpu_memory_used_bytes", "GPU memory in use.", |g| (g.memory_used_mb * 1024 * 1024) as f64),
+            ("monoxide_gpu_utilization_percent", "GPU utilization.", |g| g.utilization_percent as f64),
+            ("monoxide_gpu_temperature_celsius", "GPU temperature.", |g| g.temperature_celsius as f64),
+            ("monoxide_gpu_fan_speed_percent", "GPU fan speed.", |g| g.fan_speed_percent as f64),
+        ];
+        for (name, help, value) in gauges {
+            if gpus.is_empty() {
+                break;
+            }
+            out.family(name, "gauge", help);
+            for (index, gpu) in gpus.iter().enumerate() {
+                out.sample(name, &[("gpu", &index.to_string()), ("name", &gpu.name)], value(gpu));
+            }
+        }
+    }
+
+    if let Some(DiskData::Global(disks)) = &state.disk && !disks.is_empty() {
+        let metrics: [Metric<DiskMetrics, f64>; 6] = [
+            ("monoxide_disk_read_bytes_total", "Bytes read from the device.", |d| d.total_read_bytes as f64),
+            ("monoxide_disk_written_bytes_total", "Bytes written to the device.", |d| d.total_written_bytes as f64),
+            ("monoxide_disk_read_latency_milliseconds", "Mean read latency over the last interval.", |d| d.read_latency_ms),
+            ("monoxide_disk_write_latency_milliseconds", "Mean write latency over the last interval.", |d| d.write_latency_ms),
+            ("monoxide_disk_queue_depth", "Mean requests in flight over the last interval.", |d| d.queue_depth),
+            ("monoxide_disk_utilization_percent", "Share of the last interval the device was busy.", |d| d.utilization_percent),
+        ];
+        for (name, help, value) in metrics {
+            let kind = if name.ends_with("_total") { "counter" } else { "gauge" };
+            out.family(name, kind, help);
+            for disk in disks {
+                out.sample(name, &[("device", &disk.device_name)], value(disk));
+            }
+        }
+    }
+
+    if let Some(FilesystemData::Global(filesystems)) = &state.filesystems && !filesystems.is_empty() {
+        let gauges: [Metric<FilesystemMetrics, u64>; 5] = [
+            ("monoxide_filesystem_size_bytes", "Filesystem size.", |f| f.total_bytes),
+            ("monoxide_filesystem_used_bytes", "Space in use.", |f| f.used_bytes),
+            ("monoxide_filesystem_available_bytes", "Space available to unprivileged users.", |f| f.available_bytes),
+            ("monoxide_filesystem_inodes", "Total inodes.", |f| f.total_inodes),
+            ("monoxide_filesystem_inodes_free", "Free inodes.", |f| f.free_inodes),
+        ];
+        for (name, help, value) in gauges {
+            out.family(name, "gauge", help);
+            for fs in filesystems {
+                let labels = [("mount_point", fs.mount_point.as_str()), ("device", &fs.device), ("fs_type", &fs.fs_type)];
+                out.sample(name, &labels, value(fs) as f64);
+            }
+        }
+    }
+
+    if let Some(SensorData::Global(readings)) = &state.sensors {
+        let families = [
+            (SensorKind::Temperature, "monoxide_sensor_temperature_celsius", "Hardware temperature sensor reading."),
+            (SensorKind::Fan, "monoxide_sensor_fan_rpm", "Fan speed."),
+            (SensorKind::Voltage, "monoxide_sensor_voltage_volts", "Voltage sensor reading."),
+        ];
+        for (kind, name, help) in families {
+            for reading in readings.iter().filter(|r| r.kind == kind) {
+                out.family(name, "gauge", help);
+                out.sample(name, &[("chip", &reading.chip), ("label", &reading.label)], reading.value);
+            }
+        }
+        let name = "monoxide_sensor_temperature_critical_celsius";
+        for reading in readings.iter().filter(|r| r.kind == SensorKind::Temperature) {
+            if let Some(critical) = reading.critical {
+                out.family(name, "gauge", "Temperature at which the hardware is considered critical.");
+                out.sample(name, &[("chip", &reading.chip), ("label", &reading.label)], critical);
+            }
+        }
+    }
+
+    if let Some(PressureData::Global(pressure)) = &state.pressure {
+        // The system-wide figures are labelled with the root cgroup.
+        let scopes: Vec<(&str, &Pressure)> = std::iter::once(("/", &pressure.system))
+            .chain(pressure.cgroups.iter().map(|c| (c.cgroup.as_str(), &c.pressure)))
+            .collect();
+        let name = "monoxide_pressure_stall_percent";
+        out.family(name, "gauge", "Share of time tasks were stalled on a resource, averaged over a window.");
+        for (cgroup, resource, kind, averages) in psi_lines(&scopes) {
+            for (window, value) in [("10s", averages.avg10), ("60s", averages.avg60), ("300s", averages.avg300)] {
+                let labels = [("cgroup", cgroup), ("resource", resource), ("kind", kind), ("window", window)];
+                out.sample(name, &labels, value);
+            }
+        }
+        let name = "monoxide_pressure_stall_seconds_total";
+        out.family(name, "counter", "Total time tasks were stalled on a resource.");
+        for (cgroup, resource, kind, averages) in psi_lines(&scopes) {
+            let labels = [("cgroup", cgroup), ("resource", resource), ("kind", kind)];
+            out.sample(name, &labels, averages.total_us as f64 / 1e6);
+        }
+    }
+
+    if let Some(SystemStatsData::Global(stats)) = &state.system_stats {
+        let (one, five, fifteen) = stats.load_average;
+        out.family("monoxide_load_average", "gauge", "System load average.");
+        out.sample("monoxide_load_average", &[("period", "1m")], one);
+        out.sample("monoxide_load_average", &[("period", "5m")], five);
+        out.sample("monoxide_load_average", &[("period", "15m")], fifteen);
+        out.gauge("monoxide_uptime_seconds", "Time since boot.", stats.uptime_secs as f64);
+    }
+
+    if !state.alerts.is_empty() {
+        let name = "monoxide_alert_state";
+        out.family(name, "gauge", "State of an alert rule: 0 ok, 1 pending, 2 firing.");
+        for alert in &state.alerts {
+            let value = match alert.state {
+                AlertState::Ok => 0.0,
+                AlertState::Pending => 1.0,
+                AlertState::Firing => 2.0,
+            };
+            out.sample(name, &[("name", &alert.name), ("severity", alert.severity.name())], value);
+        }
+    }
+
+    out.text
+}
+
+// Every PSI line as (cgroup, resource, "some" or "full", averages).
+fn psi_lines<'a>(scopes: &[(&'a str, &'a Pressure)]) -> Vec<(&'a str, &'static str, &'static str, PsiAverages)> {
+    let mut lines = Vec::new();
+    for (cgroup, pressure) in scopes {
+        for (resource, psi) in pressure.resources() {
+            let Some(psi) = psi else { continue };
+            lines.push((*cgroup, resource, "some", psi.some));
+            if let Some(full) = psi.full {
+                lines.push((*cgroup, resource, "full", full));
+            }
+        }
+    }
+    lines
+}
+
+// Accumulates exposition text, writing each family's HELP/TYPE lines once.
+// A family's samples must all follow those lines, before the next family.
+#[derive(Default)]
+struct MetricsWriter {
+    text: String,
+    families: HashSet<String>,
+}
+
+impl MetricsWriter {
+    fn family(&mut self, name: &str, kind: &str, help: &str) {
+        if !self.families.insert(name.to_string()) {
+            return;
+        }
+        let _ = writeln!(self.text, "# HELP {} {}", name, help);
+        let _ = writeln!(self.text, "# TYPE {} {}", name, kind);
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.text.push_str(name);
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(key, value)| format!("{}=\"{}\"", key, escape_label(value)))
+                .collect();
+            let _ = write!(self.text, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.text, " {}", format_value(value));
+    }
+
+    fn gauge(&mut self, name: &str, help: &str, value: f64) {
+        self.family(name, "gauge", help);
+        self.sample(name, &[], value);
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerting::rule::Unit;
+    use crate::alerting::{AlertStatus, Severity};
+    use crate::state::data_types::{
+        CgroupPressure, GlobalCpuMetrics, GlobalMemoryMetrics, PressureMetrics, PsiResource, SensorReading, SystemStats,
+    };
+
+    const AWKWARD: &str = "back\\slash \"quoted\"\nnewline";
+    const ESCAPED: &str = "back\\\\slash \\\"quoted\\\"\\nnewline";
+
+    fn interface(name: &str) -> GlobalNetworkMetrics {
+        GlobalNetworkMetrics {
+            interface_name: name.to_string(),
+            total_received_bytes: 1000,
+            total_transmitted_bytes: 2000,
+            total_received_packets: 10,
+            total_transmitted_packets: 20,
+            total_receive_errors: 0,
+            total_transmit_errors: 0,
+            total_receive_drops: Some(1),
+            total_transmit_drops: None,
+            received_bytes_per_sec: 0.0,
+            transmitted_bytes_per_sec: 0.0,
+            received_packets_per_sec: 0.0,
+            transmitted_packets_per_sec: 0.0,
+            receive_errors_per_sec: 0.0,
+            transmit_errors_per_sec: 0.0,
+            receive_drops_per_sec: None,
+            transmit_drops_per_sec: None,
+        }
+    }
+
+    fn filesystem(mount_point: &str) -> FilesystemMetrics {
+        FilesystemMetrics {
+            mount_point: mount_point.to_string(),
+            device: "/dev/sda1".to_string(),
+            fs_type: "ext4".to_string(),
+            total_bytes: 100,
+            used_bytes: 40,
+            available_bytes: 50,
+            total_inodes: 10,
+            used_inodes: 4,
+            free_inodes: 6,
+        }
+    }
+
+    fn disk(name: &str) -> DiskMetrics {
+        DiskMetrics {
+            device_name: name.to_string(),
+            total_read_bytes: 4096,
+            total_written_bytes: 8192,
+            read_bytes_per_sec: 0.0,
+            written_bytes_per_sec: 0.0,
+            reads_per_sec: 0.0,
+            writes_per_sec: 0.0,
+            read_latency_ms: 0.5,
+            write_latency_ms: 1.5,
+            queue_depth: 0.1,
+            utilization_percent: 12.5,
+        }
+    }
+
+    fn sensor(chip: &str, label: &str, kind: SensorKind, critical: Option<f64>) -> SensorReading {
+        SensorReading { chip: chip.to_string(), label: label.to_string(), kind, value: 40.0, high: None, critical }
+    }
+
+    fn psi(avg10: f64) -> Option<PsiResource> {
+        let averages = PsiAverages { avg10, avg60: avg10 / 2.0, avg300: avg10 / 4.0, total_us: 1_500_000 };
+        Some(PsiResource { some: averages, full: Some(averages) })
+    }
+
+    // A state with something from every source, named awkwardly where names
+    // come from the system.
+    fn full_state() -> AppState {
+        let health = CollectorHealth { consecutive_failures: 2, last_duration_ms: 250.0, ..CollectorHealth::default() };
+        AppState {
+            cpu: Some(CpuData::GlobalCpuMetrics(GlobalCpuMetrics {
+                total_usage_percent: 50.0,
+                core_usages_percent: vec![40.0, 60.0],
+                core_frequencies_mhz: vec![2000, 2100],
+                times: None,
+            })),
+            processes: Some(Vec::new()),
+            memory: Some(MemoryData::Global(GlobalMemoryMetrics {
+                total_memory_kb: 1024,
+                available_memory_kb: 512,
+                used_memory_kb: 256,
+                total_swap_kb: 0,
+                used_swap_kb: 0,
+                details: None,
+            })),
+            network: Some(NetworkData::Global(vec![interface("eth0"), interface(AWKWARD)])),
+            system_stats: Some(SystemStatsData::Global(SystemStats {
+                hostname: None,
+                uptime_secs: 3600,
+                load_average: (0.5, 0.25, 0.125),
+                os_version: None,
+                kernel_version: None,
+                total_users: None,
+            })),
+            disk: Some(DiskData::Global(vec![disk("sda"), disk("cciss/c0d0")])),
+            filesystems: Some(FilesystemData::Global(vec![filesystem("/"), filesystem(AWKWARD)])),
+            sensors: Some(SensorData::Global(vec![
+                sensor("coretemp", "Core 0", SensorKind::Temperature, Some(100.0)),
+                sensor("nct6775", "fan1", SensorKind::Fan, None),
+                sensor("coretemp", "Core 1", SensorKind::Temperature, None),
+                sensor("nct6775", "Vcore", SensorKind::Voltage, None),
+            ])),
+            pressure: Some(PressureData::Global(PressureMetrics {
+                system: Pressure { cpu: psi(1.0), memory: psi(2.0), io: None },
+                cgroups: vec![CgroupPressure {
+                    cgroup: "system.slice".to_string(),
+                    pressure: Pressure { cpu: psi(0.5), memory: None, io: None },
+                }],
+            })),
+            collector_health: [(DataSource::Cpu, health)].into(),
+            alerts: vec![AlertStatus {
+                name: AWKWARD.to_string(),
+                rule: "cpu.total > 90".to_string(),
+                severity: Severity::Critical,
+                state: AlertState::Firing,
+                since: None,
+                value: Some(95.0),
+                unit: Unit::Percent,
+            }],
+            ..AppState::new(1)
+        }
+    }
+
+    fn render_state(state: &AppState) -> String {
+        render(&Snapshot::of(state))
+    }
+
+    // The family a sample line belongs to: its name up to the labels or value.
+    fn sample_name(line: &str) -> &str {
+        line.split(['{', ' ']).next().unwrap()
+    }
+
+    #[test]
+    fn writes_each_family_once_before_its_samples() {
+        let text = render_state(&full_state());
+        let mut seen: Vec<String> = Vec::new();
+        let mut current = String::new();
+        let mut samples = 0;
+        for line in text.lines() {
+            if let Some(rest) = line.strip_prefix("# HELP ") {
+                let name = rest.split(' ').next().unwrap();
+                assert!(!seen.iter().any(|family| family == name), "second HELP for {}", name);
+                seen.push(name.to_string());
+                current = name.to_string();
+            } else if let Some(rest) = line.strip_prefix("# TYPE ") {
+                assert_eq!(rest.split(' ').next(), Some(current.as_str()), "TYPE without HELP: {}", line);
+            } else {
+                assert_eq!(sample_name(line), current, "sample outside its family: {}", line);
+                samples += 1;
+            }
+        }
+        let types = text.lines().filter(|line| line.starts_with("# TYPE ")).count();
+        assert_eq!(types, seen.len());
+        assert!(samples > seen.len());
+        // Every source contributed something.
+        for name in [
+            "monoxide_cpu_usage_percent",
+            "monoxide_memory_used_bytes",
+            "monoxide_processes",
+            "monoxide_network_received_bytes_total",
+            "monoxide_disk_utilization_percent",
+            "monoxide_filesystem_size_bytes",
+            "monoxide_sensor_temperature_celsius",
+            "monoxide_sensor_temperature_critical_celsius",
+            "monoxide_sensor_fan_rpm",
+            "monoxide_pressure_stall_seconds_total",
+            "monoxide_load_average",
+            "monoxide_alert_state",
+            "monoxide_collector_consecutive_failures",
+        ] {
+            assert!(seen.iter().any(|family| family == name), "no {} in\n{}", name, text);
+        }
+    }
+
+    #[test]
+    fn types_counters_and_gauges() {
+        let text = render_state(&full_state());
+        for line in text.lines().filter_map(|line| line.strip_prefix("# TYPE ")) {
+            let (name, kind) = line.split_once(' ').unwrap();
+            // Prometheus naming: counters, and only counters, end in `_total`.
+            let expected = if name.ends_with("_total") { "counter" } else { "gauge" };
+            assert_eq!(kind, expected, "{}", name);
+        }
+        assert!(text.contains("# TYPE monoxide_source_dropped_updates_total counter\n"));
+        assert!(text.contains("# TYPE monoxide_network_received_bytes_total counter\n"));
+        assert!(text.contains("# TYPE monoxide_disk_read_bytes_total counter\n"));
+        assert!(text.contains("# TYPE monoxide_disk_read_latency_milliseconds gauge\n"));
+        assert!(text.contains("# TYPE monoxide_cpu_usage_percent gauge\n"));
+    }
+
+    #[test]
+    fn escapes_label_values() {
+        let text = render_state(&full_state());
+        let interface = format!("monoxide_network_received_bytes_total{{interface=\"{}\"}} 1000\n", ESCAPED);
+        assert!(text.contains(&interface), "{}", text);
+        let mount = format!("monoxide_filesystem_size_bytes{{mount_point=\"{}\",device=\"/dev/sda1\",fs_type=\"ext4\"}} 100\n", ESCAPED);
+        assert!(text.contains(&mount), "{}", text);
+        let alert = format!("monoxide_alert_state{{name=\"{}\",severity=\"critical\"}} 2\n", ESCAPED);
+        assert!(text.contains(&alert), "{}", text);
+        assert!(text.contains("monoxide_disk_read_bytes_total{device=\"cciss/c0d0\"} 4096\n"));
+        // An escaped newline never splits a sample across lines.
+        assert!(text.lines().all(|line| line.starts_with("# ") || line.starts_with("monoxide_")));
+    }
+
+    #[test]
+    fn formats_values() {
+        let text = render_state(&full_state());
+        assert!(text.contains("monoxide_load_average{period=\"15m\"} 0.125\n"));
+        assert!(text.contains("monoxide_cpu_core_frequency_hertz{core=\"1\"} 2100000000\n"));
+        assert!(text.contains("monoxide_collector_duration_seconds{source=\"cpu\"} 0.25\n"));
+        assert!(text.contains("monoxide_pressure_stall_seconds_total{cgroup=\"system.slice\",resource=\"cpu\",kind=\"full\"} 1.5\n"));
+        assert_eq!(format_value(f64::NAN), "NaN");
+        assert_eq!(format_value(f64::INFINITY), "+Inf");
+        assert_eq!(format_value(f64::NEG_INFINITY), "-Inf");
+    }
+
+    #[test]
+    fn leaves_out_sources_without_data() {
+        let text = render_state(&AppState::new(1));
+        assert!(text.contains("monoxide_source_available{source=\"cpu\"} 1\n"));
+        assert!(!text.contains("monoxide_cpu_usage_percent"));
+        assert!(!text.contains("monoxide_processes"));
+        assert!(!text.contains("monoxide_alert_state"));
+    }
+}
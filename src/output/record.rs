@@ -0,0 +1,130 @@
+use super::OutputSink;
+use crate::error::AppError;
+use crate::state::app_state::AppState;
+use crate::state::data_types::{AppStateUpdate, Sample};
+
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+
+/// Start of every recording. The last byte is the format version; bump it
+/// whenever a field of `AppStateUpdate` is renamed, removed or retyped.
+pub const RECORDING_MAGIC: &[u8; 8] = b"MONOXR\x00\x01";
+
+/// Largest record written or read back, in bytes. Far above any real update,
+/// but keeps a corrupt length prefix from allocating gigabytes on replay.
+pub const MAX_RECORD_LENGTH: u32 = 64 * 1024 * 1024;
+
+/// One recorded update. On disk each is a little-endian `u32` byte length
+/// followed by the record as MessagePack. Structs are written as maps, as
+/// the tagged `AppStateUpdate` cannot be read back from positional fields.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordedUpdate<U> {
+    // Wall-clock collection time, in milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+    pub update: U,
+}
+
+/// Appends every update to a recording file for later `--replay`.
+pub struct RecordSink {
+    writer: BufWriter<File>,
+}
+
+impl RecordSink {
+    /// Opens `path` for appending, starting a new recording if it is empty.
+    /// A non-empty file must already be a recording in the current format;
+    /// a partial last record, left by a recorder killed mid-write, is cut off
+    /// so the new session stays readable after it.
+    pub fn create(path: &Path) -> Result<Self, AppError> {
+        let error = |message: String| AppError::RecordingError(format!("{}: {}", path.display(), message));
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)
+            .map_err(|e| error(e.to_string()))?;
+        let file_length = file.metadata().map_err(|e| error(e.to_string()))?.len();
+        if file_length == 0 {
+            file.write_all(RECORDING_MAGIC).map_err(|e| error(e.to_string()))?;
+        } else {
+            let mut reader = BufReader::new(&file);
+            check_magic(&mut reader).map_err(error)?;
+            let complete = complete_length(&mut reader, file_length).map_err(|e| error(e.to_string()))?;
+            if complete < file_length {
+                log::warn!(
+                    "{}: dropping {} bytes of a truncated record at byte {}",
+                    path.display(),
+                    file_length - complete,
+                    complete
+                );
+                file.set_len(complete).map_err(|e| error(e.to_string()))?;
+            }
+        }
+        log::info!("Recording updates to {}", path.display());
+        Ok(RecordSink { writer: BufWriter::new(file) })
+    }
+}
+
+impl OutputSink for RecordSink {
+    fn on_update(&mut self, update: &Sample<AppStateUpdate>, _state: &AppState) -> Result<(), AppError> {
+        let record = RecordedUpdate { timestamp_ms: update.timestamp.unix_millis(), update: &update.data };
+        let bytes = rmp_serde::to_vec_named(&record).map_err(|e| AppError::RecordingError(e.to_string()))?;
+        let length = u32::try_from(bytes.len())
+            .ok()
+            .filter(|length| *length <= MAX_RECORD_LENGTH)
+            .ok_or_else(|| AppError::RecordingError(format!("record of {} bytes is too large", bytes.len())))?;
+        self.writer.write_all(&length.to_le_bytes())?;
+        self.writer.write_all(&bytes)?;
+        Ok(())
+    }
+
+    // Flushing once per refresh interval bounds what a crash can lose
+    // without a syscall for every update.
+    fn on_tick(&mut self, _state: &AppState) -> Result<(), AppError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn finish(&mut self, _state: &AppState) -> Result<(), AppError> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_data()?;
+        Ok(())
+    }
+}
+
+// Length of the file up to the end of its last complete record, reading
+// from just after the header. Records are only checked to fit in the file.
+fn complete_length(reader: &mut BufReader<&File>, file_length: u64) -> std::io::Result<u64> {
+    let mut offset = RECORDING_MAGIC.len() as u64;
+    loop {
+        let mut length = [0u8; 4];
+        match reader.read_exact(&mut length) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(offset),
+            Err(e) => return Err(e),
+        }
+        let length = u32::from_le_bytes(length);
+        let end = offset + 4 + length as u64;
+        if length > MAX_RECORD_LENGTH || end > file_length {
+            return Ok(offset);
+        }
+        reader.seek_relative(length as i64)?;
+        offset = end;
+    }
+}
+
+/// Reads the header from the start of `reader` and checks it is a recording
+/// this build understands.
+pub fn check_magic(reader: &mut impl Read) -> Result<(), String> {
+    let mut magic = [0u8; RECORDING_MAGIC.len()];
+    reader.read_exact(&mut magic).map_err(|e| format!("not a recording ({})", e))?;
+    match magic {
+        magic if &magic == RECORDING_MAGIC => Ok(()),
+        [b'M', b'O', b'N', b'O', b'X', b'R', _, version] => Err(format!(
+            "recording format version {} is not supported (expected {})",
+            version, RECORDING_MAGIC[7]
+        )),
+        _ => Err("not a recording".to_string()),
+    }
+}
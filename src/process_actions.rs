@@ -0,0 +1,381 @@
+use crate::error::AppError;
+
+use std::io;
+
+// CPU indices a `cpu_set_t` can hold; higher ones are rejected when parsed.
+#[cfg(target_os = "linux")]
+const CPU_SET_SIZE: usize = libc::CPU_SETSIZE as usize;
+#[cfg(not(target_os = "linux"))]
+const CPU_SET_SIZE: usize = 1024;
+
+/// A signal to send, by number: any from 1 to the highest real-time one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signal(libc::c_int);
+
+// Standard signal names, without the `SIG` prefix.
+const SIGNAL_NAMES: [(&str, libc::c_int); 29] = [
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("ILL", libc::SIGILL),
+    ("TRAP", libc::SIGTRAP),
+    ("ABRT", libc::SIGABRT),
+    ("BUS", libc::SIGBUS),
+    ("FPE", libc::SIGFPE),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("SEGV", libc::SIGSEGV),
+    ("USR2", libc::SIGUSR2),
+    ("PIPE", libc::SIGPIPE),
+    ("ALRM", libc::SIGALRM),
+    ("TERM", libc::SIGTERM),
+    ("CHLD", libc::SIGCHLD),
+    ("CONT", libc::SIGCONT),
+    ("STOP", libc::SIGSTOP),
+    ("TSTP", libc::SIGTSTP),
+    ("TTIN", libc::SIGTTIN),
+    ("TTOU", libc::SIGTTOU),
+    ("URG", libc::SIGURG),
+    ("XCPU", libc::SIGXCPU),
+    ("XFSZ", libc::SIGXFSZ),
+    ("VTALRM", libc::SIGVTALRM),
+    ("PROF", libc::SIGPROF),
+    ("WINCH", libc::SIGWINCH),
+    ("IO", libc::SIGIO),
+    ("SYS", libc::SIGSYS),
+];
+
+#[cfg(target_os = "linux")]
+const PLATFORM_SIGNAL_NAMES: [(&str, libc::c_int); 2] = [("STKFLT", libc::SIGSTKFLT), ("PWR", libc::SIGPWR)];
+#[cfg(not(target_os = "linux"))]
+const PLATFORM_SIGNAL_NAMES: [(&str, libc::c_int); 0] = [];
+
+// Real-time signals, numbered after the standard ones. The C library keeps
+// the first few for itself, so their range is only known at run time.
+#[cfg(target_os = "linux")]
+fn realtime_signals() -> std::ops::RangeInclusive<libc::c_int> {
+    libc::SIGRTMIN()..=libc::SIGRTMAX()
+}
+#[cfg(not(target_os = "linux"))]
+#[allow(clippy::reversed_empty_ranges)]
+fn realtime_signals() -> std::ops::RangeInclusive<libc::c_int> {
+    1..=0
+}
+
+impl Signal {
+    /// Offered in the process view's signal menu, in order. Any other signal
+    /// can be entered by name or number.
+    pub const MENU: [Signal; 9] = [
+        Signal(libc::SIGTERM),
+        Signal(libc::SIGKILL),
+        Signal(libc::SIGINT),
+        Signal(libc::SIGHUP),
+        Signal(libc::SIGQUIT),
+        Signal(libc::SIGSTOP),
+        Signal(libc::SIGCONT),
+        Signal(libc::SIGUSR1),
+        Signal(libc::SIGUSR2),
+    ];
+
+    /// The highest signal number that can be sent.
+    pub fn highest() -> libc::c_int {
+        let standard = SIGNAL_NAMES.iter().chain(&PLATFORM_SIGNAL_NAMES).map(|(_, number)| *number).max().unwrap_or(31);
+        (*realtime_signals().end()).max(standard)
+    }
+
+    /// Parses a signal number such as `15`, or a name with or without the
+    /// `SIG` prefix in any case, e.g. `term`, `SIGUSR1` or `RTMIN+2`. Returns
+    /// a message for the prompt on failure.
+    pub fn parse(text: &str) -> Result<Signal, String> {
+        let text = text.trim();
+        if let Ok(number) = text.parse::<libc::c_int>() {
+            if !(1..=Signal::highest()).contains(&number) {
+                return Err(format!("signal {} is out of range; enter 1 to {}", number, Signal::highest()));
+            }
+            return Ok(Signal(number));
+        }
+
+        let upper = text.to_ascii_uppercase();
+        let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+        let unknown = || format!("unknown signal `{}`", text);
+        if let Some((_, number)) = SIGNAL_NAMES.iter().chain(&PLATFORM_SIGNAL_NAMES).find(|(known, _)| *known == name) {
+            return Ok(Signal(*number));
+        }
+        let realtime = realtime_signals();
+        let number = match name {
+            "RTMIN" => *realtime.start(),
+            "RTMAX" => *realtime.end(),
+            _ => {
+                let offset = |rest: &str| rest.parse::<libc::c_int>().map_err(|_| unknown());
+                if let Some(rest) = name.strip_prefix("RTMIN+") {
+                    realtime.start().saturating_add(offset(rest)?)
+                } else if let Some(rest) = name.strip_prefix("RTMAX-") {
+                    realtime.end().saturating_sub(offset(rest)?)
+                } else {
+                    return Err(unknown());
+                }
+            }
+        };
+        if !realtime.contains(&number) {
+            return Err(format!("`{}` is not a real-time signal on this system", text));
+        }
+        Ok(Signal(number))
+    }
+
+    /// The signal's name, e.g. `SIGTERM` or `SIGRTMIN+2`.
+    pub fn name(self) -> String {
+        if let Some((name, _)) = SIGNAL_NAMES.iter().chain(&PLATFORM_SIGNAL_NAMES).find(|(_, number)| *number == self.0) {
+            return format!("SIG{}", name);
+        }
+        let realtime = realtime_signals();
+        match self.0 - realtime.start() {
+            _ if !realtime.contains(&self.0) => format!("signal {}", self.0),
+            0 => "SIGRTMIN".to_string(),
+            offset => format!("SIGRTMIN+{}", offset),
+        }
+    }
+
+    fn number(self) -> libc::c_int {
+        self.0
+    }
+}
+
+pub const MIN_NICE: i32 = -20;
+pub const MAX_NICE: i32 = 19;
+
+/// Something that can be done to a running process from the process view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProcessAction {
+    Signal(Signal),
+    Renice(i32),
+    // CPU indices the process may run on.
+    SetAffinity(Vec<usize>),
+}
+
+impl ProcessAction {
+    /// Imperative description for prompts and messages, e.g. `send SIGTERM to`.
+    pub fn describe(&self) -> String {
+        match self {
+            ProcessAction::Signal(signal) => format!("send {} to", signal.name()),
+            ProcessAction::Renice(nice) => format!("set nice {} on", nice),
+            ProcessAction::SetAffinity(cpus) => format!("set CPU affinity {} on", format_cpu_list(cpus)),
+        }
+    }
+
+    /// Applies the action to `pid`. Lacking the privilege to do so is
+    /// reported as `AppError::PermissionDenied`.
+    pub fn apply(&self, pid: u32) -> Result<(), AppError> {
+        // 0 and anything that wraps negative would address a process group.
+        if pid == 0 || pid > libc::pid_t::MAX as u32 {
+            return Err(AppError::ProcessActionError { pid, action: self.describe(), message: "invalid PID".to_string() });
+        }
+        let result = match self {
+            ProcessAction::Signal(signal) => send_signal(pid, *signal),
+            ProcessAction::Renice(nice) => renice(pid, *nice),
+            ProcessAction::SetAffinity(cpus) => set_affinity(pid, cpus),
+        };
+        result.map_err(|e| match e.raw_os_error() {
+            Some(libc::EPERM) | Some(libc::EACCES) => AppError::PermissionDenied { pid, action: self.describe() },
+            _ => AppError::ProcessActionError { pid, action: self.describe(), message: e.to_string() },
+        })
+    }
+}
+
+fn send_signal(pid: u32, signal: Signal) -> io::Result<()> {
+    // SAFETY: kill has no memory-safety preconditions.
+    if unsafe { libc::kill(pid as libc::pid_t, signal.number()) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+fn renice(pid: u32, nice: i32) -> io::Result<()> {
+    // SAFETY: setpriority has no memory-safety preconditions.
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, pid as libc::id_t, nice) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_affinity(pid: u32, cpus: &[usize]) -> io::Result<()> {
+    if let Some(cpu) = cpus.iter().find(|cpu| **cpu >= CPU_SET_SIZE) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("CPU {} is out of range", cpu)));
+    }
+    // SAFETY: cpu_set_t is plain data; every index was checked above to fit
+    // in it, and sched_setaffinity reads exactly `size_of::<cpu_set_t>()` bytes.
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for cpu in cpus {
+            libc::CPU_SET(*cpu, &mut set);
+        }
+        if libc::sched_setaffinity(pid as libc::pid_t, std::mem::size_of::<libc::cpu_set_t>(), &set) == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_affinity(_pid: u32, _cpus: &[usize]) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "CPU affinity is only supported on Linux"))
+}
+
+/// Parses a CPU list such as `0-3,6`. Returns sorted, de-duplicated indices,
+/// or a message for the prompt.
+pub fn parse_cpu_list(spec: &str) -> Result<Vec<usize>, String> {
+    let syntax = || "enter CPU numbers or ranges, e.g. 0-3,6".to_string();
+    let parse = |cpu: &str| {
+        let cpu = cpu.trim().parse::<usize>().map_err(|_| syntax())?;
+        // Checked before expanding ranges, so `0-4000000000` can't allocate.
+        if cpu >= CPU_SET_SIZE {
+            return Err(format!("CPU {} is out of range; the highest is {}", cpu, CPU_SET_SIZE - 1));
+        }
+        Ok(cpu)
+    };
+    let mut cpus = Vec::new();
+    for part in spec.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (parse(first)?, parse(last)?);
+                if first > last {
+                    return Err(format!("range {} runs backwards", part));
+                }
+                cpus.extend(first..=last);
+            }
+            None => cpus.push(parse(part)?),
+        }
+    }
+    cpus.sort_unstable();
+    cpus.dedup();
+    if cpus.is_empty() {
+        return Err(syntax());
+    }
+    Ok(cpus)
+}
+
+/// The inverse of `parse_cpu_list`, collapsing runs into ranges.
+pub fn format_cpu_list(cpus: &[usize]) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    let mut index = 0;
+    while index < cpus.len() {
+        let start = cpus[index];
+        let mut end = start;
+        while index + 1 < cpus.len() && cpus[index + 1] == end + 1 {
+            index += 1;
+            end = cpus[index];
+        }
+        parts.push(if start == end { start.to_string() } else { format!("{}-{}", start, end) });
+        index += 1;
+    }
+    parts.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_signal_names_and_numbers() {
+        assert_eq!(Signal::parse("15"), Ok(Signal(libc::SIGTERM)));
+        assert_eq!(Signal::parse(" 9 "), Ok(Signal(libc::SIGKILL)));
+        assert_eq!(Signal::parse("TERM"), Ok(Signal(libc::SIGTERM)));
+        assert_eq!(Signal::parse("SIGHUP"), Ok(Signal(libc::SIGHUP)));
+        assert_eq!(Signal::parse("sigusr1"), Ok(Signal(libc::SIGUSR1)));
+        assert_eq!(Signal::parse("winch"), Ok(Signal(libc::SIGWINCH)));
+        assert_eq!(Signal::parse("PWR"), Ok(Signal(libc::SIGPWR)));
+    }
+
+    #[test]
+    fn parses_realtime_signals() {
+        let (min, max) = (libc::SIGRTMIN(), libc::SIGRTMAX());
+        assert_eq!(Signal::highest(), max);
+        assert_eq!(Signal::parse("RTMIN"), Ok(Signal(min)));
+        assert_eq!(Signal::parse("sigrtmin+2"), Ok(Signal(min + 2)));
+        assert_eq!(Signal::parse("RTMAX-1"), Ok(Signal(max - 1)));
+        assert_eq!(Signal::parse("SIGRTMAX"), Ok(Signal(max)));
+        assert_eq!(Signal::parse(&max.to_string()), Ok(Signal(max)));
+        assert_eq!(Signal::parse("RTMIN+1000"), Err("`RTMIN+1000` is not a real-time signal on this system".to_string()));
+        assert_eq!(Signal::parse("RTMAX-1000"), Err("`RTMAX-1000` is not a real-time signal on this system".to_string()));
+    }
+
+    #[test]
+    fn rejects_unknown_signals() {
+        let highest = Signal::highest();
+        assert_eq!(Signal::parse("0"), Err(format!("signal 0 is out of range; enter 1 to {}", highest)));
+        assert_eq!(Signal::parse("-9"), Err(format!("signal -9 is out of range; enter 1 to {}", highest)));
+        let beyond = highest + 1;
+        assert_eq!(Signal::parse(&beyond.to_string()), Err(format!("signal {} is out of range; enter 1 to {}", beyond, highest)));
+        assert_eq!(Signal::parse("BOGUS"), Err("unknown signal `BOGUS`".to_string()));
+        assert_eq!(Signal::parse("RTMIN+x"), Err("unknown signal `RTMIN+x`".to_string()));
+        assert_eq!(Signal::parse(""), Err("unknown signal ``".to_string()));
+    }
+
+    #[test]
+    fn names_signals() {
+        assert_eq!(Signal(libc::SIGTERM).name(), "SIGTERM");
+        assert_eq!(Signal(libc::SIGSTKFLT).name(), "SIGSTKFLT");
+        assert_eq!(Signal(libc::SIGRTMIN()).name(), "SIGRTMIN");
+        assert_eq!(Signal(libc::SIGRTMIN() + 3).name(), "SIGRTMIN+3");
+        // Names read back as the same signal.
+        for number in 1..=Signal::highest() {
+            let name = Signal(number).name();
+            if !name.starts_with("signal") {
+                assert_eq!(Signal::parse(&name), Ok(Signal(number)), "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn parses_single_cpus_and_ranges() {
+        assert_eq!(parse_cpu_list("0-3,6"), Ok(vec![0, 1, 2, 3, 6]));
+        assert_eq!(parse_cpu_list(" 2 , 0 - 1 "), Ok(vec![0, 1, 2]));
+        assert_eq!(parse_cpu_list("5"), Ok(vec![5]));
+    }
+
+    #[test]
+    fn sorts_and_deduplicates() {
+        assert_eq!(parse_cpu_list("3,1-2,2,1"), Ok(vec![1, 2, 3]));
+        assert_eq!(parse_cpu_list("4-4,4"), Ok(vec![4]));
+    }
+
+    #[test]
+    fn rejects_malformed_lists() {
+        assert!(parse_cpu_list("").is_err());
+        assert!(parse_cpu_list(" , ").is_err());
+        assert!(parse_cpu_list("a").is_err());
+        assert!(parse_cpu_list("1-").is_err());
+        assert!(parse_cpu_list("-1").is_err());
+    }
+
+    #[test]
+    fn rejects_reversed_ranges() {
+        assert_eq!(parse_cpu_list("3-1"), Err("range 3-1 runs backwards".to_string()));
+    }
+
+    #[test]
+    fn rejects_cpus_beyond_the_cpu_set() {
+        assert_eq!(parse_cpu_list(&(CPU_SET_SIZE - 1).to_string()), Ok(vec![CPU_SET_SIZE - 1]));
+        assert!(parse_cpu_list(&CPU_SET_SIZE.to_string()).unwrap_err().contains("out of range"));
+        assert!(parse_cpu_list("0-4000000000").unwrap_err().contains("out of range"));
+        assert!(parse_cpu_list("99999999999999999999999").is_err());
+    }
+
+    #[test]
+    fn formats_runs_as_ranges() {
+        assert_eq!(format_cpu_list(&[0, 1, 2, 3, 6]), "0-3,6");
+        assert_eq!(format_cpu_list(&[1, 3, 5]), "1,3,5");
+        assert_eq!(format_cpu_list(&[7]), "7");
+        assert_eq!(format_cpu_list(&[]), "");
+    }
+
+    #[test]
+    fn formatting_round_trips_through_parsing() {
+        for spec in ["0-3,6", "1,3,5", "0-15", "2,4-7,9"] {
+            assert_eq!(format_cpu_list(&parse_cpu_list(spec).unwrap()), spec);
+        }
+    }
+}
@@ -0,0 +1,373 @@
+use crate::error::AppError;
+use crate::output::record::{check_magic, RecordedUpdate, MAX_RECORD_LENGTH, RECORDING_MAGIC};
+use crate::state::app_state::ReplayStatus;
+use crate::state::data_types::{AppStateUpdate, Sample, Timestamp};
+
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+/// Playback speeds the speed control cycles through.
+pub const SPEEDS: [u32; 3] = [1, 10, 100];
+
+// How far before a seek target updates are re-applied to rebuild the state.
+// Covers the slowest collector interval and the history graphs' window.
+const REBUILD_WINDOW_MS: u64 = 5 * 60 * 1000;
+
+// Where one record lives in the file.
+#[derive(Debug, Clone, Copy)]
+struct IndexEntry {
+    timestamp_ms: u64,
+    offset: u64,
+    length: u32,
+}
+
+/// Plays a recording back into the app in place of live collectors. Only an
+/// index is kept in memory; records are read from disk as they come due.
+///
+/// Recorded times map onto a virtual clock: the first record plays at
+/// `anchor`, and every later one as far after it as it was recorded, so
+/// history graphs keep their true spacing at any playback speed.
+pub struct Replay {
+    reader: BufReader<File>,
+    index: Vec<IndexEntry>,
+    // Sources that failed to start never send anything else, so these are
+    // re-applied after every seek however far back they were recorded.
+    unavailable: Vec<(u64, AppStateUpdate)>,
+    // Index of the next record to play.
+    next: usize,
+    // Recorded time reached at real time `position_at`.
+    position_ms: u64,
+    position_at: Instant,
+    speed: u32,
+    paused: bool,
+    anchor: Instant,
+}
+
+impl Replay {
+    pub fn open(path: &Path) -> Result<Self, AppError> {
+        let error = |message: String| AppError::RecordingError(format!("{}: {}", path.display(), message));
+        let file = File::open(path).map_err(|e| error(e.to_string()))?;
+        let file_length = file.metadata().map_err(|e| error(e.to_string()))?.len();
+        let mut reader = BufReader::new(file);
+        check_magic(&mut reader).map_err(error)?;
+
+        let mut index = Vec::new();
+        let mut unavailable = Vec::new();
+        let mut offset = RECORDING_MAGIC.len() as u64;
+        loop {
+            let mut length = [0u8; 4];
+            match reader.read_exact(&mut length) {
+                Ok(()) => {}
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(error(e.to_string())),
+            }
+            let length = u32::from_le_bytes(length);
+            // A recorder killed mid-write leaves a partial last record.
+            if offset + 4 + length as u64 > file_length {
+                log::warn!("{}: stopping at truncated record at byte {}", path.display(), offset);
+                break;
+            }
+            let record = match read_record(&mut reader, length) {
+                Ok(record) => record,
+                Err(e) => {
+                    log::warn!("{}: stopping at damaged record at byte {}: {}", path.display(), offset, e);
+                    break;
+                }
+            };
+            if let AppStateUpdate::SourceUnavailable { .. } = record.update {
+                unavailable.push((record.timestamp_ms, record.update));
+            }
+            index.push(IndexEntry { timestamp_ms: record.timestamp_ms, offset: offset + 4, length });
+            offset += 4 + length as u64;
+        }
+        // Sessions appended across a clock change can be out of order.
+        index.sort_by_key(|entry| entry.timestamp_ms);
+
+        let Some(first) = index.first() else {
+            return Err(error("recording is empty".to_string()));
+        };
+        log::info!("Replaying {} updates from {}", index.len(), path.display());
+        let now = Instant::now();
+        Ok(Replay {
+            reader,
+            position_ms: first.timestamp_ms,
+            index,
+            unavailable,
+            next: 0,
+            position_at: now,
+            speed: SPEEDS[0],
+            paused: false,
+            anchor: now,
+        })
+    }
+
+    fn start_ms(&self) -> u64 {
+        self.index.first().map_or(0, |entry| entry.timestamp_ms)
+    }
+
+    fn end_ms(&self) -> u64 {
+        self.index.last().map_or(0, |entry| entry.timestamp_ms)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.index.len()
+    }
+
+    // Recorded time the playback has reached by now.
+    fn position(&self) -> u64 {
+        if self.paused || self.is_finished() {
+            return self.position_ms;
+        }
+        let advanced = self.position_at.elapsed().as_millis() as u64 * self.speed as u64;
+        (self.position_ms + advanced).min(self.end_ms())
+    }
+
+    // Restarts the clock from the current position, for speed and pause changes.
+    fn rebase(&mut self) {
+        self.position_ms = self.position();
+        self.position_at = Instant::now();
+    }
+
+    /// When the next record is due, or `None` while paused or finished.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        if self.paused {
+            return None;
+        }
+        let entry = self.index.get(self.next)?;
+        let wait_ms = entry.timestamp_ms.saturating_sub(self.position_ms) / self.speed as u64;
+        Some(self.position_at + Duration::from_millis(wait_ms))
+    }
+
+    /// Every record that has come due, in order.
+    pub fn due(&mut self) -> Result<Vec<Sample<AppStateUpdate>>, AppError> {
+        let position = self.position();
+        let mut samples = Vec::new();
+        while let Some(entry) = self.index.get(self.next).copied() {
+            if entry.timestamp_ms > position {
+                break;
+            }
+            samples.push(self.read(entry)?);
+            self.next += 1;
+        }
+        if self.is_finished() {
+            self.position_ms = position;
+            self.position_at = Instant::now();
+        }
+        Ok(samples)
+    }
+
+    /// Moves playback by `delta_ms` of recorded time. Returns the updates that
+    /// rebuild the state at the new position, to be applied to a fresh state.
+    pub fn seek(&mut self, delta_ms: i64) -> Result<Vec<Sample<AppStateUpdate>>, AppError> {
+        let target = self.position().saturating_add_signed(delta_ms).clamp(self.start_ms(), self.end_ms());
+        let rebuild_from = target.saturating_sub(REBUILD_WINDOW_MS);
+
+        let mut samples: Vec<Sample<AppStateUpdate>> = self
+            .unavailable
+            .iter()
+            .filter(|(timestamp_ms, _)| *timestamp_ms < rebuild_from)
+            .map(|(timestamp_ms, update)| self.sample(*timestamp_ms, update.clone()))
+            .collect();
+        self.next = self.index.partition_point(|entry| entry.timestamp_ms < rebuild_from);
+        while let Some(entry) = self.index.get(self.next).copied() {
+            if entry.timestamp_ms > target {
+                break;
+            }
+            samples.push(self.read(entry)?);
+            self.next += 1;
+        }
+        self.position_ms = target;
+        self.position_at = Instant::now();
+        Ok(samples)
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.rebase();
+        self.paused = !self.paused;
+    }
+
+    pub fn cycle_speed(&mut self) {
+        self.rebase();
+        let index = SPEEDS.iter().position(|speed| *speed == self.speed).unwrap_or(0);
+        self.speed = SPEEDS[(index + 1) % SPEEDS.len()];
+    }
+
+    pub fn status(&self) -> ReplayStatus {
+        let position_ms = self.position();
+        ReplayStatus {
+            start_ms: self.start_ms(),
+            end_ms: self.end_ms(),
+            position_ms,
+            now: self.instant_at(position_ms),
+            speed: self.speed,
+            paused: self.paused,
+            finished: self.is_finished(),
+        }
+    }
+
+    fn instant_at(&self, timestamp_ms: u64) -> Instant {
+        self.anchor + Duration::from_millis(timestamp_ms.saturating_sub(self.start_ms()))
+    }
+
+    fn sample(&self, timestamp_ms: u64, update: AppStateUpdate) -> Sample<AppStateUpdate> {
+        let timestamp = Timestamp {
+            monotonic: self.instant_at(timestamp_ms),
+            wall: UNIX_EPOCH + Duration::from_millis(timestamp_ms),
+        };
+        Sample::new(timestamp, update)
+    }
+
+    fn read(&mut self, entry: IndexEntry) -> Result<Sample<AppStateUpdate>, AppError> {
+        self.reader.seek(SeekFrom::Start(entry.offset))?;
+        let record = read_record(&mut self.reader, entry.length)?;
+        Ok(self.sample(record.timestamp_ms, record.update))
+    }
+}
+
+fn read_record(reader: &mut impl Read, length: u32) -> Result<RecordedUpdate<AppStateUpdate>, AppError> {
+    if length > MAX_RECORD_LENGTH {
+        return Err(AppError::RecordingError(format!("record length {} exceeds the maximum of {}", length, MAX_RECORD_LENGTH)));
+    }
+    let mut bytes = vec![0u8; length as usize];
+    reader.read_exact(&mut bytes)?;
+    rmp_serde::from_slice(&bytes).map_err(|e| AppError::RecordingError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::OutputSink;
+    use crate::output::record::RecordSink;
+    use crate::state::app_state::AppState;
+    use crate::state::data_types::DataSource;
+    use std::io::Write;
+
+    const START_MS: u64 = 1_700_000_000_000;
+
+    fn unavailable(source: DataSource, reason: &str) -> AppStateUpdate {
+        AppStateUpdate::SourceUnavailable { source, reason: reason.to_string() }
+    }
+
+    // Records `updates` one second apart and returns the file's directory.
+    fn record(updates: Vec<AppStateUpdate>) -> (tempfile::TempDir, std::path::PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.rec");
+        let mut sink = RecordSink::create(&path).unwrap();
+        let state = AppState::new(10);
+        for (index, update) in updates.into_iter().enumerate() {
+            let timestamp = Timestamp {
+                monotonic: Instant::now(),
+                wall: UNIX_EPOCH + Duration::from_millis(START_MS + index as u64 * 1000),
+            };
+            sink.on_update(&Sample::new(timestamp, update), &state).unwrap();
+        }
+        sink.finish(&state).unwrap();
+        (dir, path)
+    }
+
+    fn append(path: &Path, bytes: &[u8]) {
+        std::fs::OpenOptions::new().append(true).open(path).unwrap().write_all(bytes).unwrap();
+    }
+
+    // Every recorded update, by seeking to the end of the recording.
+    fn play_all(replay: &mut Replay) -> Vec<(u64, AppStateUpdate)> {
+        let samples = replay.seek(i64::MAX / 2).unwrap();
+        samples.into_iter().map(|sample| (sample.timestamp.unix_millis(), sample.data)).collect()
+    }
+
+    fn describe(played: &[(u64, AppStateUpdate)]) -> Vec<(u64, DataSource)> {
+        played.iter().map(|(timestamp_ms, update)| (*timestamp_ms, update.source())).collect()
+    }
+
+    #[test]
+    fn reads_back_what_was_recorded() {
+        let (_dir, path) = record(vec![
+            AppStateUpdate::Processes(Vec::new()),
+            unavailable(DataSource::Gpu, "no driver"),
+            AppStateUpdate::Processes(Vec::new()),
+        ]);
+        let mut replay = Replay::open(&path).unwrap();
+        assert_eq!(replay.index.len(), 3);
+
+        let played = play_all(&mut replay);
+        assert_eq!(
+            describe(&played),
+            [(START_MS, DataSource::Processes), (START_MS + 1000, DataSource::Gpu), (START_MS + 2000, DataSource::Processes)]
+        );
+        let AppStateUpdate::SourceUnavailable { reason, .. } = &played[1].1 else { panic!("expected SourceUnavailable") };
+        assert_eq!(reason, "no driver");
+    }
+
+    #[test]
+    fn stops_at_a_truncated_trailing_record() {
+        let (_dir, path) = record(vec![AppStateUpdate::Processes(Vec::new()), unavailable(DataSource::Gpu, "no driver")]);
+        // A length prefix promising more than the file holds.
+        append(&path, &100u32.to_le_bytes());
+        append(&path, &[0x81; 10]);
+
+        let mut replay = Replay::open(&path).unwrap();
+        assert_eq!(describe(&play_all(&mut replay)), [(START_MS, DataSource::Processes), (START_MS + 1000, DataSource::Gpu)]);
+    }
+
+    #[test]
+    fn appends_after_a_truncated_record() {
+        let (_dir, path) = record(vec![AppStateUpdate::Processes(Vec::new())]);
+        append(&path, &100u32.to_le_bytes());
+        append(&path, &[0x81; 10]);
+
+        // A second session, as after restarting a recorder that crashed.
+        let mut sink = RecordSink::create(&path).unwrap();
+        let state = AppState::new(10);
+        let timestamp = Timestamp { monotonic: Instant::now(), wall: UNIX_EPOCH + Duration::from_millis(START_MS + 5000) };
+        sink.on_update(&Sample::new(timestamp, unavailable(DataSource::Gpu, "no driver")), &state).unwrap();
+        sink.finish(&state).unwrap();
+
+        let mut replay = Replay::open(&path).unwrap();
+        assert_eq!(describe(&play_all(&mut replay)), [(START_MS, DataSource::Processes), (START_MS + 5000, DataSource::Gpu)]);
+    }
+
+    #[test]
+    fn appends_after_a_cut_off_length_prefix() {
+        let (_dir, path) = record(vec![AppStateUpdate::Processes(Vec::new())]);
+        let complete = std::fs::metadata(&path).unwrap().len();
+        append(&path, &[0x10, 0x00]);
+        drop(RecordSink::create(&path).unwrap());
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), complete);
+    }
+
+    #[test]
+    fn stops_at_a_cut_off_length_prefix() {
+        let (_dir, path) = record(vec![AppStateUpdate::Processes(Vec::new())]);
+        append(&path, &[0x10, 0x00]);
+
+        let mut replay = Replay::open(&path).unwrap();
+        assert_eq!(describe(&play_all(&mut replay)), [(START_MS, DataSource::Processes)]);
+    }
+
+    #[test]
+    fn does_not_trust_a_corrupt_length() {
+        let (_dir, path) = record(vec![AppStateUpdate::Processes(Vec::new())]);
+        append(&path, &u32::MAX.to_le_bytes());
+        append(&path, &[0; 64]);
+
+        let mut replay = Replay::open(&path).unwrap();
+        assert_eq!(replay.index.len(), 1);
+        assert_eq!(describe(&play_all(&mut replay)), [(START_MS, DataSource::Processes)]);
+        assert!(read_record(&mut std::io::empty(), MAX_RECORD_LENGTH + 1).is_err());
+    }
+
+    #[test]
+    fn rejects_empty_and_foreign_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let empty = dir.path().join("empty.rec");
+        RecordSink::create(&empty).unwrap();
+        let Err(error) = Replay::open(&empty) else { panic!("opened an empty recording") };
+        assert!(error.to_string().contains("recording is empty"));
+
+        let foreign = dir.path().join("foreign.rec");
+        std::fs::write(&foreign, b"not a recording").unwrap();
+        assert!(Replay::open(&foreign).is_err());
+    }
+}
@@ -0,0 +1,111 @@
+use crate::error::AppError;
+
+use crossbeam_channel::{bounded, Receiver, RecvTimeoutError, Sender};
+use signal_hook::consts::{SIGINT, SIGTERM};
+use signal_hook::iterator::{Handle, Signals};
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+// How often `join_with_timeout` checks whether the threads are done.
+const JOIN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Tells every thread to stop. Loops can poll `is_set`; threads blocked in a
+/// `select!` or a sleep wait on `receiver`, which disconnects the moment the
+/// signal is triggered, so they wake at once instead of at their next tick.
+#[derive(Debug, Clone)]
+pub struct ShutdownSignal {
+    flag: Arc<AtomicBool>,
+    // Dropped on trigger; never sent on.
+    sender: Arc<Mutex<Option<Sender<()>>>>,
+    receiver: Receiver<()>,
+}
+
+impl ShutdownSignal {
+    pub fn new() -> Self {
+        let (sender, receiver) = bounded(0);
+        ShutdownSignal { flag: Arc::new(AtomicBool::new(false)), sender: Arc::new(Mutex::new(Some(sender))), receiver }
+    }
+
+    pub fn trigger(&self) {
+        self.flag.store(true, Ordering::Relaxed);
+        self.sender.lock().unwrap().take();
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.flag.load(Ordering::Relaxed)
+    }
+
+    /// Becomes ready, as disconnected, once the signal is triggered.
+    pub fn receiver(&self) -> &Receiver<()> {
+        &self.receiver
+    }
+
+    /// Sleeps for `duration` or until the signal is triggered. Returns whether
+    /// it was triggered.
+    pub fn sleep(&self, duration: Duration) -> bool {
+        match self.receiver.recv_timeout(duration) {
+            Err(RecvTimeoutError::Timeout) => self.is_set(),
+            _ => true,
+        }
+    }
+}
+
+impl Default for ShutdownSignal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Triggers `shutdown_signal` on SIGINT or SIGTERM. Dropping the returned
+/// guard stops listening.
+pub fn handle_signals(shutdown_signal: ShutdownSignal) -> Result<SignalGuard, AppError> {
+    let mut signals = Signals::new([SIGINT, SIGTERM])?;
+    let handle = signals.handle();
+    let thread = std::thread::spawn(move || {
+        // Ends when the guard closes the handle.
+        for signal in signals.forever() {
+            log::info!("Received signal {}, shutting down.", signal);
+            shutdown_signal.trigger();
+        }
+    });
+    Ok(SignalGuard { handle, thread: Some(thread) })
+}
+
+pub struct SignalGuard {
+    handle: Handle,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for SignalGuard {
+    fn drop(&mut self) {
+        self.handle.close();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Joins each thread that finishes before `deadline`. Threads still running
+/// then (e.g. stuck in a slow read) are logged and left behind, as they end
+/// with the process anyway.
+pub fn join_with_timeout<N: Display>(handles: Vec<(N, JoinHandle<()>)>, deadline: Instant) {
+    let mut pending = handles;
+    while !pending.is_empty() && Instant::now() < deadline {
+        let (finished, running): (Vec<_>, Vec<_>) = pending.into_iter().partition(|(_, handle)| handle.is_finished());
+        for (name, handle) in finished {
+            if handle.join().is_err() {
+                log::error!("{} thread panicked.", name);
+            }
+        }
+        pending = running;
+        if !pending.is_empty() {
+            std::thread::sleep(JOIN_POLL_INTERVAL);
+        }
+    }
+    for (name, _) in pending {
+        log::warn!("{} thread did not stop in time.", name);
+    }
+}
@@ -1,8 +1,14 @@
-use std::option;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::time::Instant;
 
+use crate::alerting::{AlertStatus, AlertTransition};
 use crate::state::data_types::*;
+use crate::state::history::History;
 
-#[derive(Debug, Default)]
+pub const DEFAULT_HISTORY_CAPACITY: usize = 300;
+
+#[derive(Debug, Serialize)]
 pub struct AppState{
     pub cpu: Option<CpuData>,
     pub processes: Option<Vec<ProcessInfo>>,
@@ -10,4 +16,306 @@ pub struct AppState{
     pub network: Option<NetworkData>,
     pub gpu: Option<GpuData>,
     pub system_stats: Option<SystemStatsData>,
-}
\ No newline at end of file
+    pub disk: Option<DiskData>,
+    pub filesystems: Option<FilesystemData>,
+    pub sensors: Option<SensorData>,
+    pub pressure: Option<PressureData>,
+    // Sources whose collector failed to start, with the reason.
+    pub unavailable: HashMap<DataSource, String>,
+    // Current refresh interval of each running collector.
+    pub refresh_intervals_ms: HashMap<DataSource, u64>,
+    // Samples each collector dropped because the app fell behind.
+    pub dropped_updates: HashMap<DataSource, u64>,
+    // How each running collector's recent collections went.
+    pub collector_health: HashMap<DataSource, CollectorHealth>,
+    // monoxide's own resource use, sampled once per refresh interval.
+    pub self_usage: Option<SelfUsage>,
+    // Collection time of the latest sample from each source.
+    pub last_updated: HashMap<DataSource, Timestamp>,
+    // One entry per configured alert rule, in config order.
+    pub alerts: Vec<AlertStatus>,
+    // Recent firing and resolved transitions, oldest first.
+    pub alert_log: VecDeque<AlertTransition>,
+    // Instants do not serialize; exports work from the latest values.
+    #[serde(skip)]
+    pub history: MetricHistory,
+    // Playback position when running from `--replay`.
+    #[serde(skip)]
+    pub replay: Option<ReplayStatus>,
+}
+
+/// Outcome of a collector's recent collections, for telling one that is
+/// stuck, failing or slow apart from one that is merely idle.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CollectorHealth {
+    pub last_success: Option<Timestamp>,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<Timestamp>,
+    pub consecutive_failures: u32,
+    // How long the latest collection took, successful or not.
+    pub last_duration_ms: f64,
+    // Set while a collection is running; a collector that stays here is stuck.
+    pub collecting_since: Option<Timestamp>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct SelfUsage {
+    // Percent of one core, like the process table.
+    pub cpu_percent: f32,
+    pub rss_bytes: u64,
+    pub threads: u32,
+}
+
+/// Where a replay is, for the footer and for graphing against recorded time.
+#[derive(Debug, Clone, Copy)]
+pub struct ReplayStatus {
+    // Recorded wall-clock times, in milliseconds since the Unix epoch.
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub position_ms: u64,
+    // The playback position on the clock replayed samples are stamped with.
+    pub now: Instant,
+    pub speed: u32,
+    pub paused: bool,
+    pub finished: bool,
+}
+
+/// Ring buffers of recent values for the metrics worth graphing.
+#[derive(Debug)]
+pub struct MetricHistory {
+    capacity: usize,
+    pub cpu_total: History<f32>,
+    pub cpu_cores: Vec<History<f32>>,
+    pub memory_used_kb: History<u64>,
+    pub swap_used_kb: History<u64>,
+    // Bytes per second, keyed by interface name.
+    pub network_rx: HashMap<String, History<f64>>,
+    pub network_tx: HashMap<String, History<f64>>,
+    // Indexed like the GPU list in `GpuData`.
+    pub gpu_utilization: Vec<History<u32>>,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        AppState::new(DEFAULT_HISTORY_CAPACITY)
+    }
+}
+
+impl AppState {
+    pub fn new(history_capacity: usize) -> Self {
+        AppState {
+            cpu: None,
+            processes: None,
+            memory: None,
+            network: None,
+            gpu: None,
+            system_stats: None,
+            disk: None,
+            filesystems: None,
+            sensors: None,
+            pressure: None,
+            unavailable: HashMap::new(),
+            refresh_intervals_ms: HashMap::new(),
+            dropped_updates: HashMap::new(),
+            collector_health: HashMap::new(),
+            self_usage: None,
+            last_updated: HashMap::new(),
+            alerts: Vec::new(),
+            alert_log: VecDeque::new(),
+            history: MetricHistory::new(history_capacity),
+            replay: None,
+        }
+    }
+
+    /// The current time on the clock samples are stamped with: the playback
+    /// position during a replay, otherwise the real time.
+    pub fn now(&self) -> Instant {
+        self.replay.map_or_else(Instant::now, |replay| replay.now)
+    }
+
+    pub fn unavailable_reason(&self, source: DataSource) -> Option<&str> {
+        self.unavailable.get(&source).map(String::as_str)
+    }
+
+    pub fn apply(&mut self, sample: Sample<AppStateUpdate>) {
+        let timestamp = sample.timestamp;
+        let at = timestamp.monotonic;
+        let source = match sample.data {
+            AppStateUpdate::Cpu(data) => {
+                self.history.record_cpu(at, &data);
+                self.cpu = Some(data);
+                DataSource::Cpu
+            }
+            AppStateUpdate::Processes(data) => {
+                self.processes = Some(data);
+                DataSource::Processes
+            }
+            AppStateUpdate::Memory(data) => {
+                self.history.record_memory(at, &data);
+                self.memory = Some(data);
+                DataSource::Memory
+            }
+            AppStateUpdate::Network(data) => {
+                self.history.record_network(at, &data);
+                self.network = Some(data);
+                DataSource::Network
+            }
+            AppStateUpdate::Gpu(data) => {
+                self.history.record_gpu(at, &data);
+                self.gpu = Some(data);
+                DataSource::Gpu
+            }
+            AppStateUpdate::SystemStats(data) => {
+                self.system_stats = Some(data);
+                DataSource::SystemStats
+            }
+            AppStateUpdate::Disk(data) => {
+                self.disk = Some(data);
+                DataSource::Disk
+            }
+            AppStateUpdate::Filesystems(data) => {
+                self.filesystems = Some(data);
+                DataSource::Filesystems
+            }
+            AppStateUpdate::Sensors(data) => {
+                self.sensors = Some(data);
+                DataSource::Sensors
+            }
+            AppStateUpdate::Pressure(data) => {
+                self.pressure = Some(data);
+                DataSource::Pressure
+            }
+            AppStateUpdate::SourceUnavailable { source, reason } => {
+                self.unavailable.insert(source, reason);
+                return;
+            }
+        };
+        self.last_updated.insert(source, timestamp);
+    }
+}
+
+impl MetricHistory {
+    pub fn new(capacity: usize) -> Self {
+        MetricHistory {
+            capacity,
+            cpu_total: History::new(capacity),
+            cpu_cores: Vec::new(),
+            memory_used_kb: History::new(capacity),
+            swap_used_kb: History::new(capacity),
+            network_rx: HashMap::new(),
+            network_tx: HashMap::new(),
+            gpu_utilization: Vec::new(),
+        }
+    }
+
+    fn record_cpu(&mut self, at: Instant, data: &CpuData) {
+        let CpuData::GlobalCpuMetrics(metrics) = data;
+        self.cpu_total.push(at, metrics.total_usage_percent);
+        self.cpu_cores.resize_with(metrics.core_usages_percent.len(), || History::new(self.capacity));
+        for (history, usage) in self.cpu_cores.iter_mut().zip(&metrics.core_usages_percent) {
+            history.push(at, *usage);
+        }
+    }
+
+    fn record_memory(&mut self, at: Instant, data: &MemoryData) {
+        let MemoryData::Global(metrics) = data;
+        self.memory_used_kb.push(at, metrics.used_memory_kb);
+        self.swap_used_kb.push(at, metrics.used_swap_kb);
+    }
+
+    fn record_network(&mut self, at: Instant, data: &NetworkData) {
+        let NetworkData::Global(interfaces) = data;
+        // Interfaces come and go with VPNs and containers; keeping the history
+        // of every one ever seen would grow without bound.
+        let present = |name: &String| interfaces.iter().any(|iface| iface.interface_name == *name);
+        self.network_rx.retain(|name, _| present(name));
+        self.network_tx.retain(|name, _| present(name));
+        for iface in interfaces {
+            self.network_rx
+                .entry(iface.interface_name.clone())
+                .or_insert_with(|| History::new(self.capacity))
+                .push(at, iface.received_bytes_per_sec);
+            self.network_tx
+                .entry(iface.interface_name.clone())
+                .or_insert_with(|| History::new(self.capacity))
+                .push(at, iface.transmitted_bytes_per_sec);
+        }
+    }
+
+    fn record_gpu(&mut self, at: Instant, data: &GpuData) {
+        let GpuData::Nvidia(gpus) = data;
+        self.gpu_utilization.resize_with(gpus.len(), || History::new(self.capacity));
+        for (history, gpu) in self.gpu_utilization.iter_mut().zip(gpus) {
+            history.push(at, gpu.utilization_percent);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn interface(name: &str, rx: f64) -> GlobalNetworkMetrics {
+        GlobalNetworkMetrics {
+            interface_name: name.to_string(),
+            total_received_bytes: 0,
+            total_transmitted_bytes: 0,
+            total_received_packets: 0,
+            total_transmitted_packets: 0,
+            total_receive_errors: 0,
+            total_transmit_errors: 0,
+            total_receive_drops: None,
+            total_transmit_drops: None,
+            received_bytes_per_sec: rx,
+            transmitted_bytes_per_sec: rx / 2.0,
+            received_packets_per_sec: 0.0,
+            transmitted_packets_per_sec: 0.0,
+            receive_errors_per_sec: 0.0,
+            transmit_errors_per_sec: 0.0,
+            receive_drops_per_sec: None,
+            transmit_drops_per_sec: None,
+        }
+    }
+
+    fn apply_network(state: &mut AppState, at: Instant, interfaces: Vec<GlobalNetworkMetrics>) {
+        let update = AppStateUpdate::Network(NetworkData::Global(interfaces));
+        state.apply(Sample::new(Timestamp::at(at), update));
+    }
+
+    fn names(histories: &HashMap<String, History<f64>>) -> Vec<&str> {
+        let mut names: Vec<&str> = histories.keys().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn records_network_history_per_interface() {
+        let mut state = AppState::new(10);
+        let start = Instant::now();
+        apply_network(&mut state, start, vec![interface("eth0", 100.0), interface("lo", 10.0)]);
+        apply_network(&mut state, start + Duration::from_secs(1), vec![interface("eth0", 300.0), interface("lo", 10.0)]);
+
+        let history = &state.history;
+        assert_eq!(names(&history.network_rx), ["eth0", "lo"]);
+        assert_eq!(history.network_rx["eth0"].average(Duration::from_secs(60)), Some(200.0));
+        assert_eq!(history.network_tx["eth0"].latest().map(|(_, tx)| *tx), Some(150.0));
+    }
+
+    #[test]
+    fn forgets_interfaces_that_disappear() {
+        let mut state = AppState::new(10);
+        let start = Instant::now();
+        apply_network(&mut state, start, vec![interface("eth0", 1.0), interface("veth1a2b", 1.0), interface("wg0", 1.0)]);
+        apply_network(&mut state, start + Duration::from_secs(1), vec![interface("eth0", 1.0), interface("veth3c4d", 1.0)]);
+
+        assert_eq!(names(&state.history.network_rx), ["eth0", "veth3c4d"]);
+        assert_eq!(names(&state.history.network_tx), ["eth0", "veth3c4d"]);
+        // What is still there keeps its history.
+        assert_eq!(state.history.network_rx["eth0"].last_secs(60).count(), 2);
+        assert_eq!(state.history.network_rx["veth3c4d"].last_secs(60).count(), 1);
+
+        apply_network(&mut state, start + Duration::from_secs(2), Vec::new());
+        assert!(state.history.network_rx.is_empty() && state.history.network_tx.is_empty());
+    }
+}
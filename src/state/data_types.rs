@@ -1,25 +1,119 @@
-use serde::{Deserialize, Serialize}; // If you plan to serialize later
+use serde::{Deserialize, Serialize};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// When a sample was collected. `monotonic` is used for intervals and
+/// history windows, `wall` for display and export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timestamp {
+    pub monotonic: Instant,
+    pub wall: SystemTime,
+}
+
+impl Timestamp {
+    pub fn now() -> Self {
+        Timestamp { monotonic: Instant::now(), wall: SystemTime::now() }
+    }
+
+    pub fn unix_millis(&self) -> u64 {
+        self.wall.duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+    }
+
+    // Pairs a past (or present) monotonic instant with the matching wall-clock time.
+    pub fn at(monotonic: Instant) -> Self {
+        let now = Timestamp::now();
+        let age = now.monotonic.saturating_duration_since(monotonic);
+        Timestamp { monotonic, wall: now.wall.checked_sub(age).unwrap_or(now.wall) }
+    }
+}
+
+// Only the wall-clock time is meaningful outside this process.
+impl Serialize for Timestamp {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.unix_millis())
+    }
+}
+
+/// A value tagged with its collection time.
+#[derive(Debug, Clone, Serialize)]
+pub struct Sample<T> {
+    pub timestamp: Timestamp,
+    pub data: T,
+}
+
+impl<T> Sample<T> {
+    pub fn new(timestamp: Timestamp, data: T) -> Self {
+        Sample { timestamp, data }
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Sample<U> {
+        Sample { timestamp: self.timestamp, data: f(self.data) }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GlobalCpuMetrics {
     pub total_usage_percent: f32, // Overall CPU usage
     pub core_usages_percent: Vec<f32>, // Usage per logical core
+    pub core_frequencies_mhz: Vec<u64>, // Current clock per logical core; 0 when unknown
+    // Only available where /proc/stat is.
+    pub times: Option<CpuTimes>,
 }
 
+/// Where CPU time went over the last interval, from /proc/stat.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PerProcessCpuMetrics {
-    pub pid: u32,
-    pub cpu_usage_percent:f32,
+pub struct CpuTimes {
+    pub total: CpuTimeBreakdown,
+    // Indexed like `core_usages_percent`.
+    pub cores: Vec<CpuTimeBreakdown>,
+    pub context_switches_per_sec: f64,
+    pub interrupts_per_sec: f64,
+}
+
+/// Shares of elapsed CPU time, in percent. `user` through `steal` add up to
+/// 100; `guest` and `guest_nice` are time spent running VMs and are already
+/// counted in `user` and `nice`.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct CpuTimeBreakdown {
+    pub user: f64,
+    pub nice: f64,
+    pub system: f64,
+    pub idle: f64,
+    pub iowait: f64,
+    pub irq: f64,
+    pub softirq: f64,
+    pub steal: f64,
+    pub guest: f64,
+    pub guest_nice: f64,
 }
 
-#[derive(Debug, Clone)]
+impl CpuTimeBreakdown {
+    /// Each share with its /proc/stat name, in /proc/stat order.
+    pub fn modes(&self) -> [(&'static str, f64); 10] {
+        [
+            ("user", self.user),
+            ("nice", self.nice),
+            ("system", self.system),
+            ("idle", self.idle),
+            ("iowait", self.iowait),
+            ("irq", self.irq),
+            ("softirq", self.softirq),
+            ("steal", self.steal),
+            ("guest", self.guest),
+            ("guest_nice", self.guest_nice),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum CpuData {
     GlobalCpuMetrics(GlobalCpuMetrics)
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ProcessStatus {
-    Run, 
+    Run,
     Sleep,
     Idle,
     Zombie,
@@ -40,44 +134,123 @@ impl From<sysinfo::ProcessStatus> for ProcessStatus {
     }
 }
 
-#[derive(Debug, Clone)]
+impl std::fmt::Display for ProcessStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProcessStatus::Run => write!(f, "Running"),
+            ProcessStatus::Sleep => write!(f, "Sleeping"),
+            ProcessStatus::Idle => write!(f, "Idle"),
+            ProcessStatus::Zombie => write!(f, "Zombie"),
+            ProcessStatus::Unknown(status) => write!(f, "{}", status),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProcessInfo {
     pub pid: u32,
+    pub ppid: Option<u32>,
     pub name: String,
+    // Paths and arguments are decoded lossily; they need not be UTF-8.
+    pub cmd: Vec<String>,
+    pub exe: Option<String>,
+    pub cwd: Option<String>,
     pub cpu_usage_percent: f32,
+    // Resident set size.
     pub memory_usage_kb: u64,
+    pub virtual_memory_kb: u64,
     pub status: ProcessStatus,
+    // A thread listed alongside its process; its CPU and memory are already
+    // counted in the process's own figures.
+    pub is_thread: bool,
+    // `None` for threads themselves.
+    pub thread_count: Option<usize>,
+    // Names of the owning user and group; `None` when the id has no entry.
+    pub user: Option<String>,
+    pub group: Option<String>,
+    // Seconds since the Unix epoch.
+    pub start_time_secs: u64,
+    pub run_time_secs: u64,
+    pub disk_read_bytes_total: u64,
+    pub disk_written_bytes_total: u64,
+    // Rates over the time since the previous sample; zero on the first one.
+    pub disk_read_bytes_per_sec: f64,
+    pub disk_written_bytes_per_sec: f64,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GlobalMemoryMetrics {
     pub total_memory_kb: u64,
     pub available_memory_kb: u64,
     pub used_memory_kb: u64,
     pub total_swap_kb: u64,
     pub used_swap_kb: u64,
+    // Only available where /proc/meminfo is.
+    pub details: Option<MemoryDetails>,
 }
 
-#[derive(Debug, Clone)]
+/// Where memory went, from /proc/meminfo and /proc/vmstat.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryDetails {
+    pub free_kb: u64,
+    pub buffers_kb: u64,
+    // Page cache, including `shared_kb`.
+    pub cached_kb: u64,
+    // tmpfs and shared memory segments.
+    pub shared_kb: u64,
+    pub slab_reclaimable_kb: u64,
+    pub slab_unreclaimable_kb: u64,
+    pub dirty_kb: u64,
+    pub writeback_kb: u64,
+    pub huge_pages_total: u64,
+    pub huge_pages_free: u64,
+    pub huge_page_size_kb: u64,
+    // Memory the kernel has promised to processes, against the limit it
+    // enforces under strict overcommit.
+    pub committed_as_kb: u64,
+    pub commit_limit_kb: u64,
+    // Rates over the time since the previous sample; zero on the first one.
+    pub swap_in_bytes_per_sec: f64,
+    pub swap_out_bytes_per_sec: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum MemoryData {
     Global(GlobalMemoryMetrics),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GlobalNetworkMetrics {
     pub interface_name: String,
-    pub received_bytes: u64,
-    pub transmitted_bytes: u64,
-    pub received_packets: u64,
-    pub transmitted_packets: u64,
+    // Cumulative counters as reported by the OS.
+    pub total_received_bytes: u64,
+    pub total_transmitted_bytes: u64,
+    pub total_received_packets: u64,
+    pub total_transmitted_packets: u64,
+    pub total_receive_errors: u64,
+    pub total_transmit_errors: u64,
+    // Drop counters are only available on Linux.
+    pub total_receive_drops: Option<u64>,
+    pub total_transmit_drops: Option<u64>,
+    // Rates over the time since the previous sample; zero on the first one.
+    pub received_bytes_per_sec: f64,
+    pub transmitted_bytes_per_sec: f64,
+    pub received_packets_per_sec: f64,
+    pub transmitted_packets_per_sec: f64,
+    pub receive_errors_per_sec: f64,
+    pub transmit_errors_per_sec: f64,
+    pub receive_drops_per_sec: Option<f64>,
+    pub transmit_drops_per_sec: Option<f64>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum NetworkData {
     Global(Vec<GlobalNetworkMetrics>), // List for all interfaces
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GpuMetrics {
     pub name:String,
     pub memory_total_mb: u64,
@@ -87,13 +260,14 @@ pub struct GpuMetrics {
     pub fan_speed_percent: u32,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum GpuData{
     Nvidia(Vec<GpuMetrics>)
 }
 
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemStats {
     pub hostname: Option<String>,
     pub uptime_secs: u64,
@@ -103,12 +277,214 @@ pub struct SystemStats {
     pub total_users: Option<usize>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SystemStatsData{
     Global(SystemStats),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskMetrics {
+    pub device_name: String,
+    pub total_read_bytes: u64,
+    pub total_written_bytes: u64,
+    // Rates over the time since the previous sample; zero on the first one.
+    pub read_bytes_per_sec: f64,
+    pub written_bytes_per_sec: f64,
+    pub reads_per_sec: f64,
+    pub writes_per_sec: f64,
+    // Mean time per request completed in the interval, queueing included;
+    // zero when nothing completed.
+    pub read_latency_ms: f64,
+    pub write_latency_ms: f64,
+    // Average number of requests in flight over the interval.
+    pub queue_depth: f64,
+    // Share of the interval the device was busy.
+    pub utilization_percent: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiskData {
+    Global(Vec<DiskMetrics>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilesystemMetrics {
+    pub mount_point: String,
+    // Source as listed in the mount table, e.g. `/dev/sda1`.
+    pub device: String,
+    pub fs_type: String,
+    pub total_bytes: u64,
+    pub used_bytes: u64,
+    // Space available to unprivileged users; excludes blocks reserved for root.
+    pub available_bytes: u64,
+    // Zero on filesystems without a fixed inode table, such as btrfs.
+    pub total_inodes: u64,
+    pub used_inodes: u64,
+    pub free_inodes: u64,
+}
+
+impl FilesystemMetrics {
+    /// Share of the space usable by unprivileged users that is taken, as `df`
+    /// reports it.
+    pub fn usage_percent(&self) -> f64 {
+        crate::utils::ratio(self.used_bytes, self.used_bytes + self.available_bytes) * 100.0
+    }
+
+    pub fn inode_usage_percent(&self) -> f64 {
+        crate::utils::ratio(self.used_inodes, self.total_inodes) * 100.0
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilesystemData {
+    Global(Vec<FilesystemMetrics>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensorKind {
+    // Degrees Celsius.
+    Temperature,
+    // Revolutions per minute.
+    Fan,
+    // Volts.
+    Voltage,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SensorReading {
+    // hwmon chip name, e.g. `coretemp` or `nvme`, or the thermal zone's directory.
+    pub chip: String,
+    // The driver's label, e.g. `Core 0`, or the attribute name when it has none.
+    pub label: String,
+    pub kind: SensorKind,
+    pub value: f64,
+    // Thresholds reported by the driver, in the same unit as `value`.
+    pub high: Option<f64>,
+    pub critical: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensorData {
+    Global(Vec<SensorReading>),
+}
+
+/// One line of a PSI file: the share of time some (or all) non-idle tasks
+/// were stalled on a resource.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PsiAverages {
+    // Percentages over the last 10 s, 60 s and 300 s.
+    pub avg10: f64,
+    pub avg60: f64,
+    pub avg300: f64,
+    // Cumulative stall time in microseconds.
+    pub total_us: u64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PsiResource {
+    pub some: PsiAverages,
+    // Older kernels have no `full` line for CPU.
+    pub full: Option<PsiAverages>,
+}
+
+/// Pressure on each resource; `None` where the file could not be read.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pressure {
+    pub cpu: Option<PsiResource>,
+    pub memory: Option<PsiResource>,
+    pub io: Option<PsiResource>,
+}
+
+impl Pressure {
+    /// Each resource with its PSI file name.
+    pub fn resources(&self) -> [(&'static str, Option<&PsiResource>); 3] {
+        [("cpu", self.cpu.as_ref()), ("memory", self.memory.as_ref()), ("io", self.io.as_ref())]
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CgroupPressure {
+    // Path relative to the cgroup2 mount, e.g. `system.slice`.
+    pub cgroup: String,
+    pub pressure: Pressure,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PressureMetrics {
+    pub system: Pressure,
+    pub cgroups: Vec<CgroupPressure>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PressureData {
+    Global(PressureMetrics),
+}
+
+/// Identifies which collector a piece of state came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataSource {
+    Cpu,
+    Processes,
+    Memory,
+    Network,
+    Gpu,
+    SystemStats,
+    Disk,
+    Filesystems,
+    Sensors,
+    Pressure,
+}
+
+impl DataSource {
+    pub const ALL: [DataSource; 10] = [
+        DataSource::Cpu,
+        DataSource::Processes,
+        DataSource::Memory,
+        DataSource::Network,
+        DataSource::Gpu,
+        DataSource::SystemStats,
+        DataSource::Disk,
+        DataSource::Filesystems,
+        DataSource::Sensors,
+        DataSource::Pressure,
+    ];
+
+    pub fn from_name(name: &str) -> Option<DataSource> {
+        DataSource::ALL.into_iter().find(|source| source.name() == name)
+    }
+
+    /// Name used for this source in the config file and in log messages.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DataSource::Cpu => "cpu",
+            DataSource::Processes => "processes",
+            DataSource::Memory => "memory",
+            DataSource::Network => "network",
+            DataSource::Gpu => "gpu",
+            DataSource::SystemStats => "system_stats",
+            DataSource::Disk => "disk",
+            DataSource::Filesystems => "filesystems",
+            DataSource::Sensors => "sensors",
+            DataSource::Pressure => "pressure",
+        }
+    }
+}
+
+impl std::fmt::Display for DataSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
 pub enum AppStateUpdate {
     Cpu(CpuData),
     Processes(Vec<ProcessInfo>),
@@ -116,4 +492,88 @@ pub enum AppStateUpdate {
     Network(NetworkData),
     Gpu(GpuData),
     SystemStats(SystemStatsData),
-}
\ No newline at end of file
+    Disk(DiskData),
+    Filesystems(FilesystemData),
+    Sensors(SensorData),
+    Pressure(PressureData),
+    // The collector for this source could not be initialised.
+    SourceUnavailable { source: DataSource, reason: String },
+}
+
+impl AppStateUpdate {
+    pub fn source(&self) -> DataSource {
+        match self {
+            AppStateUpdate::Cpu(_) => DataSource::Cpu,
+            AppStateUpdate::Processes(_) => DataSource::Processes,
+            AppStateUpdate::Memory(_) => DataSource::Memory,
+            AppStateUpdate::Network(_) => DataSource::Network,
+            AppStateUpdate::Gpu(_) => DataSource::Gpu,
+            AppStateUpdate::SystemStats(_) => DataSource::SystemStats,
+            AppStateUpdate::Disk(_) => DataSource::Disk,
+            AppStateUpdate::Filesystems(_) => DataSource::Filesystems,
+            AppStateUpdate::Sensors(_) => DataSource::Sensors,
+            AppStateUpdate::Pressure(_) => DataSource::Pressure,
+            AppStateUpdate::SourceUnavailable { source, .. } => *source,
+        }
+    }
+}
+
+impl From<CpuData> for AppStateUpdate {
+    fn from(data: CpuData) -> Self {
+        AppStateUpdate::Cpu(data)
+    }
+}
+
+impl From<Vec<ProcessInfo>> for AppStateUpdate {
+    fn from(data: Vec<ProcessInfo>) -> Self {
+        AppStateUpdate::Processes(data)
+    }
+}
+
+impl From<MemoryData> for AppStateUpdate {
+    fn from(data: MemoryData) -> Self {
+        AppStateUpdate::Memory(data)
+    }
+}
+
+impl From<NetworkData> for AppStateUpdate {
+    fn from(data: NetworkData) -> Self {
+        AppStateUpdate::Network(data)
+    }
+}
+
+impl From<GpuData> for AppStateUpdate {
+    fn from(data: GpuData) -> Self {
+        AppStateUpdate::Gpu(data)
+    }
+}
+
+impl From<SystemStatsData> for AppStateUpdate {
+    fn from(data: SystemStatsData) -> Self {
+        AppStateUpdate::SystemStats(data)
+    }
+}
+
+impl From<DiskData> for AppStateUpdate {
+    fn from(data: DiskData) -> Self {
+        AppStateUpdate::Disk(data)
+    }
+}
+
+impl From<FilesystemData> for AppStateUpdate {
+    fn from(data: FilesystemData) -> Self {
+        AppStateUpdate::Filesystems(data)
+    }
+}
+
+impl From<SensorData> for AppStateUpdate {
+    fn from(data: SensorData) -> Self {
+        AppStateUpdate::Sensors(data)
+    }
+}
+
+impl From<PressureData> for AppStateUpdate {
+    fn from(data: PressureData) -> Self {
+        AppStateUpdate::Pressure(data)
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Fixed-capacity ring buffer of timestamped values, oldest first. Pushing
+/// onto a full buffer evicts the oldest entry.
+#[derive(Debug, Clone)]
+pub struct History<T> {
+    samples: VecDeque<(Instant, T)>,
+    capacity: usize,
+}
+
+impl<T> History<T> {
+    pub fn new(capacity: usize) -> Self {
+        History { samples: VecDeque::with_capacity(capacity), capacity: capacity.max(1) }
+    }
+
+    pub fn push(&mut self, at: Instant, value: T) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((at, value));
+    }
+
+    pub fn latest(&self) -> Option<&(Instant, T)> {
+        self.samples.back()
+    }
+
+    /// Samples taken within `window` of the newest sample, oldest first.
+    pub fn last(&self, window: Duration) -> impl Iterator<Item = &(Instant, T)> {
+        let newest = self.latest().map(|(at, _)| *at);
+        self.samples
+            .iter()
+            .filter(move |(at, _)| newest.is_some_and(|newest| newest.saturating_duration_since(*at) <= window))
+    }
+
+    /// Samples taken within the last `secs` seconds, oldest first.
+    pub fn last_secs(&self, secs: u64) -> impl Iterator<Item = &(Instant, T)> {
+        self.last(Duration::from_secs(secs))
+    }
+}
+
+impl<T: Copy + Into<f64>> History<T> {
+    /// Mean of the samples within `window` of the newest sample.
+    pub fn average(&self, window: Duration) -> Option<f64> {
+        let (sum, count) = self
+            .last(window)
+            .fold((0.0, 0usize), |(sum, count), (_, value)| (sum + (*value).into(), count + 1));
+        (count > 0).then(|| sum / count as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `values` pushed one second apart from `start`.
+    fn history(capacity: usize, start: Instant, values: &[u32]) -> History<u32> {
+        let mut history = History::new(capacity);
+        for (index, value) in values.iter().enumerate() {
+            history.push(start + Duration::from_secs(index as u64), *value);
+        }
+        history
+    }
+
+    fn values(history: &History<u32>, secs: u64) -> Vec<u32> {
+        history.last_secs(secs).map(|(_, value)| *value).collect()
+    }
+
+    #[test]
+    fn keeps_samples_oldest_first() {
+        let history = history(5, Instant::now(), &[1, 2, 3]);
+        assert_eq!(values(&history, 60), [1, 2, 3]);
+        assert_eq!(history.latest().map(|(_, value)| *value), Some(3));
+    }
+
+    #[test]
+    fn evicts_the_oldest_when_full() {
+        let start = Instant::now();
+        let history = history(3, start, &[1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(values(&history, 60), [5, 6, 7]);
+        assert_eq!(history.samples.len(), 3);
+        assert_eq!(history.samples.front().map(|(at, _)| *at), Some(start + Duration::from_secs(4)));
+    }
+
+    #[test]
+    fn holds_at_least_one_sample() {
+        let history = history(0, Instant::now(), &[1, 2]);
+        assert_eq!(values(&history, 60), [2]);
+    }
+
+    #[test]
+    fn windows_are_measured_from_the_newest_sample() {
+        let history = history(10, Instant::now(), &[1, 2, 3, 4, 5]);
+        assert_eq!(values(&history, 0), [5]);
+        // Both ends are inclusive.
+        assert_eq!(values(&history, 2), [3, 4, 5]);
+        assert_eq!(values(&history, 60), [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn averages_the_window() {
+        let history = history(10, Instant::now(), &[10, 20, 30, 40]);
+        assert_eq!(history.average(Duration::from_secs(1)), Some(35.0));
+        assert_eq!(history.average(Duration::from_secs(60)), Some(25.0));
+        assert_eq!(History::<u32>::new(10).average(Duration::from_secs(60)), None);
+        assert_eq!(History::<u32>::new(10).last_secs(60).count(), 0);
+    }
+}
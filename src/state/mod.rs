@@ -1,2 +1,3 @@
 pub mod app_state;
-pub mod data_types;
\ No newline at end of file
+pub mod data_types;
+pub mod history;
\ No newline at end of file
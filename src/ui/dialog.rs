@@ -0,0 +1,103 @@
+use crate::process_actions::{self, ProcessAction, Signal, MAX_NICE, MIN_NICE};
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+/// A modal popup for acting on processes. `targets` is fixed when the dialog
+/// opens, so refreshes underneath it cannot change what gets acted on.
+#[derive(Debug)]
+pub struct Dialog {
+    // PID and name of each process the action will apply to.
+    pub targets: Vec<(u32, String)>,
+    pub step: DialogStep,
+}
+
+#[derive(Debug)]
+pub enum DialogStep {
+    SignalMenu { selected: usize },
+    Prompt { kind: PromptKind, text: String, error: Option<String> },
+    Confirm { action: ProcessAction },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptKind {
+    // A signal not in the menu.
+    Signal,
+    Nice,
+    Affinity,
+}
+
+impl PromptKind {
+    pub fn label(self) -> String {
+        match self {
+            PromptKind::Signal => format!("Signal name or number (1 to {})", Signal::highest()),
+            PromptKind::Nice => format!("Nice value ({} to {})", MIN_NICE, MAX_NICE),
+            PromptKind::Affinity => "CPUs, e.g. 0-3,6".to_string(),
+        }
+    }
+
+    fn parse(self, text: &str) -> Result<ProcessAction, String> {
+        match self {
+            PromptKind::Signal => Signal::parse(text).map(ProcessAction::Signal),
+            PromptKind::Nice => match text.trim().parse::<i32>() {
+                Ok(nice) if (MIN_NICE..=MAX_NICE).contains(&nice) => Ok(ProcessAction::Renice(nice)),
+                _ => Err(format!("enter a number from {} to {}", MIN_NICE, MAX_NICE)),
+            },
+            PromptKind::Affinity => process_actions::parse_cpu_list(text).map(ProcessAction::SetAffinity),
+        }
+    }
+}
+
+pub enum DialogOutcome {
+    Open,
+    Cancelled,
+    Confirmed(ProcessAction),
+}
+
+impl Dialog {
+    pub fn signal_menu(targets: Vec<(u32, String)>) -> Self {
+        Dialog { targets, step: DialogStep::SignalMenu { selected: 0 } }
+    }
+
+    pub fn prompt(targets: Vec<(u32, String)>, kind: PromptKind) -> Self {
+        Dialog { targets, step: DialogStep::Prompt { kind, text: String::new(), error: None } }
+    }
+
+    pub fn handle_key(&mut self, key: &KeyEvent) -> DialogOutcome {
+        if key.code == KeyCode::Esc {
+            return DialogOutcome::Cancelled;
+        }
+        match &mut self.step {
+            DialogStep::SignalMenu { selected } => match key.code {
+                KeyCode::Up => *selected = selected.saturating_sub(1),
+                // The entry after the listed signals asks for any other.
+                KeyCode::Down => *selected = (*selected + 1).min(Signal::MENU.len()),
+                KeyCode::Enter => {
+                    self.step = match Signal::MENU.get(*selected) {
+                        Some(signal) => DialogStep::Confirm { action: ProcessAction::Signal(*signal) },
+                        None => DialogStep::Prompt { kind: PromptKind::Signal, text: String::new(), error: None },
+                    };
+                }
+                _ => {}
+            },
+            DialogStep::Prompt { kind, text, error } => match key.code {
+                KeyCode::Char(c) => text.push(c),
+                KeyCode::Backspace => {
+                    text.pop();
+                }
+                KeyCode::Enter => match kind.parse(text) {
+                    Ok(action) => self.step = DialogStep::Confirm { action },
+                    Err(message) => *error = Some(message),
+                },
+                _ => {}
+            },
+            DialogStep::Confirm { action } => match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                    return DialogOutcome::Confirmed(action.clone());
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') => return DialogOutcome::Cancelled,
+                _ => {}
+            },
+        }
+        DialogOutcome::Open
+    }
+}
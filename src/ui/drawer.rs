@@ -0,0 +1,1245 @@
+use super::UiState;
+use super::dialog::{Dialog, DialogStep, PromptKind};
+use super::input_handlers::InputAction;
+use super::View;
+use super::filesystem_table::{FilesystemColumn, FilesystemTableState};
+use super::layout::{AlertsLayout, DashboardLayout, HistoryLayout, InternalsLayout, SystemLayout};
+use super::process_table::{ProcessTableState, SortColumn};
+use super::theme::Theme;
+use crate::alerting::{AlertState, Severity, TransitionKind};
+use crate::process_actions::Signal;
+use crate::state::app_state::{AppState, CollectorHealth, ReplayStatus};
+use crate::state::data_types::*;
+use crate::state::history::History;
+use crate::utils::{format_bytes, format_rate, format_unix_time, format_uptime, ratio};
+
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::symbols::Marker;
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Axis, Block, Borders, Cell, Chart, Clear, Dataset, Gauge, GraphType, LineGauge, Paragraph, Row, Table, Wrap};
+use ratatui::Frame;
+use std::time::{Duration, Instant};
+
+// How far back the history graphs and trend columns look.
+const HISTORY_WINDOW_SECS: u64 = 60;
+// Temperatures within this many degrees of their critical threshold are flagged.
+const CRITICAL_TEMPERATURE_MARGIN: f64 = 10.0;
+// A collector silent for this many of its intervals is reported as stale, or
+// as stuck when it is still inside the same collection.
+const STALE_AFTER_INTERVALS: u32 = 3;
+// How long a process action's outcome stays in the footer.
+const STATUS_DURATION: Duration = Duration::from_secs(5);
+
+pub fn draw(frame: &mut Frame, state: &AppState, ui: &mut UiState) {
+    match ui.view {
+        View::Dashboard => draw_dashboard(frame, state, ui),
+        View::History => draw_history(frame, state, ui),
+        View::System => draw_system(frame, state, ui),
+        View::Alerts => draw_alerts(frame, state, ui),
+        View::Internals => draw_internals(frame, state, ui),
+    }
+    if let Some(dialog) = &ui.dialog {
+        draw_dialog(frame, dialog, &ui.theme);
+    }
+}
+
+fn draw_dashboard(frame: &mut Frame, state: &AppState, ui: &mut UiState) {
+    let layout = DashboardLayout::new(frame.area());
+    let theme = &ui.theme;
+
+    draw_header(frame, layout.header, state, theme);
+    draw_cpu(frame, layout.cpu, state, theme);
+    draw_memory(frame, layout.memory, state, theme);
+    draw_network(frame, layout.network, state, theme);
+    draw_disk(frame, layout.disk, state, theme);
+    draw_gpu(frame, layout.gpu, state, theme);
+    draw_processes(frame, layout.processes, state, theme, &mut ui.processes);
+    draw_footer(frame, layout.footer, state, ui);
+}
+
+fn panel(title: &str) -> Block<'_> {
+    Block::default()
+        .borders(Borders::ALL)
+        .title(Span::styled(title, Style::default().add_modifier(Modifier::BOLD)))
+}
+
+// A panel for one data source, with its current refresh interval in the title.
+fn source_panel<'a>(title: &'a str, state: &AppState, source: DataSource) -> Block<'a> {
+    let block = panel(title);
+    match state.refresh_intervals_ms.get(&source) {
+        Some(interval_ms) => block.title(format!(" {}ms ", interval_ms)),
+        None => block,
+    }
+}
+
+// Shown in place of a panel that has no data yet, or whose source is absent.
+fn draw_placeholder(frame: &mut Frame, area: Rect, block: Block, state: &AppState, theme: &Theme, source: DataSource) {
+    let text = match state.unavailable_reason(source) {
+        Some(reason) => Paragraph::new(format!("Unavailable: {}", reason))
+            .style(Style::default().fg(theme.muted))
+            .wrap(Wrap { trim: true }),
+        None => Paragraph::new("Waiting for data...").style(Style::default().fg(theme.muted)),
+    };
+    frame.render_widget(text.block(block), area);
+}
+
+fn draw_header(frame: &mut Frame, area: Rect, state: &AppState, theme: &Theme) {
+    let block = panel("monoxide");
+    let Some(SystemStatsData::Global(stats)) = &state.system_stats else {
+        return draw_placeholder(frame, area, block, state, theme, DataSource::SystemStats);
+    };
+
+    let unknown = || "unknown".to_string();
+    let (one, five, fifteen) = stats.load_average;
+    let mut line = Line::from(vec![
+        Span::styled(stats.hostname.clone().unwrap_or_else(unknown), Style::default().fg(theme.accent)),
+    ]);
+    // Firing alerts go first so a narrow terminal doesn't cut them off.
+    let firing: Vec<Severity> =
+        state.alerts.iter().filter(|alert| alert.state == AlertState::Firing).map(|alert| alert.severity).collect();
+    if !firing.is_empty() {
+        let color = if firing.contains(&Severity::Critical) { theme.critical } else { theme.warn };
+        line.push_span(Span::styled(
+            format!("  ALERTS: {} firing", firing.len()),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ));
+    }
+    line.extend([
+        Span::raw(format!("  OS: {}", stats.os_version.clone().unwrap_or_else(unknown))),
+        Span::raw(format!("  Kernel: {}", stats.kernel_version.clone().unwrap_or_else(unknown))),
+        Span::raw(format!("  Up: {}", format_uptime(stats.uptime_secs))),
+        Span::raw(format!("  Load: {:.2} {:.2} {:.2}", one, five, fifteen)),
+    ]);
+    if let Some(users) = stats.total_users {
+        line.push_span(Span::raw(format!("  Users: {}", users)));
+    }
+    // Stall percentages tell contention apart better than the load average.
+    if let Some(PressureData::Global(pressure)) = &state.pressure {
+        line.push_span(Span::raw("  PSI:"));
+        for (resource, psi) in pressure.system.resources() {
+            if let Some(psi) = psi {
+                line.push_span(Span::raw(format!(" {} ", resource)));
+                line.push_span(Span::styled(
+                    format!("{:.1}%", psi.some.avg10),
+                    Style::default().fg(theme.pressure_color(psi.some.avg10)),
+                ));
+            }
+        }
+    }
+    frame.render_widget(Paragraph::new(line).block(block), area);
+}
+
+fn draw_cpu(frame: &mut Frame, area: Rect, state: &AppState, theme: &Theme) {
+    let block = source_panel("CPU", state, DataSource::Cpu);
+    let Some(CpuData::GlobalCpuMetrics(metrics)) = &state.cpu else {
+        return draw_placeholder(frame, area, block, state, theme, DataSource::Cpu);
+    };
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    if inner.height == 0 {
+        return;
+    }
+
+    let breakdown_height = if metrics.times.is_some() { 1 } else { 0 };
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Length(breakdown_height), Constraint::Min(0)])
+        .split(inner);
+
+    let total = metrics.total_usage_percent as f64;
+    let total_gauge = Gauge::default()
+        .gauge_style(Style::default().fg(theme.usage_color(total)))
+        .ratio((total / 100.0).clamp(0.0, 1.0))
+        .label(format!("Total {:.1}%", total));
+    frame.render_widget(total_gauge, rows[0]);
+
+    if let Some(times) = &metrics.times {
+        let breakdown = &times.total;
+        // iowait points at slow storage, steal at a busy hypervisor.
+        let share = |label: &str, percent: f64, flag: bool| {
+            let style = if flag && percent >= 10.0 { Style::default().fg(theme.warn) } else { Style::default() };
+            Span::styled(format!("{} {:.0}%  ", label, percent), style)
+        };
+        let mut spans = vec![
+            share("usr", breakdown.user, false),
+            share("nice", breakdown.nice, false),
+            share("sys", breakdown.system, false),
+            share("io", breakdown.iowait, true),
+            share("irq", breakdown.irq, false),
+            share("sirq", breakdown.softirq, false),
+            share("steal", breakdown.steal, true),
+        ];
+        // Only hosts running VMs have any guest time.
+        let guest = breakdown.guest + breakdown.guest_nice;
+        if guest > 0.0 {
+            spans.push(share("guest", guest, false));
+        }
+        spans.push(Span::styled(
+            format!("ctx {:.0}/s  intr {:.0}/s", times.context_switches_per_sec, times.interrupts_per_sec),
+            Style::default().fg(theme.muted),
+        ));
+        frame.render_widget(Paragraph::new(Line::from(spans)), rows[1]);
+    }
+
+    // Lay the cores out in as many columns as needed to fit the panel height.
+    let cores = &metrics.core_usages_percent;
+    let per_column = (rows[2].height as usize).max(1);
+    let columns = cores.len().div_ceil(per_column).max(1);
+    let column_areas = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![Constraint::Ratio(1, columns as u32); columns])
+        .split(rows[2]);
+
+    for (index, usage) in cores.iter().enumerate() {
+        let column = index / per_column;
+        let row = (index % per_column) as u16;
+        let Some(column_area) = column_areas.get(column) else { break };
+        let area = Rect { y: column_area.y + row, height: 1, ..*column_area };
+        let usage = *usage as f64;
+        let frequency = match metrics.core_frequencies_mhz.get(index) {
+            Some(mhz) if *mhz > 0 => format!(" {:.2}GHz", *mhz as f64 / 1000.0),
+            _ => String::new(),
+        };
+        let gauge = LineGauge::default()
+            .filled_style(Style::default().fg(theme.usage_color(usage)))
+            .ratio((usage / 100.0).clamp(0.0, 1.0))
+            .label(format!("{:>3} {:>5.1}%{}", index, usage, frequency));
+        frame.render_widget(gauge, area);
+    }
+}
+
+fn draw_memory(frame: &mut Frame, area: Rect, state: &AppState, theme: &Theme) {
+    let block = source_panel("Memory", state, DataSource::Memory);
+    let Some(MemoryData::Global(metrics)) = &state.memory else {
+        return draw_placeholder(frame, area, block, state, theme, DataSource::Memory);
+    };
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Length(2), Constraint::Length(1), Constraint::Min(0)])
+        .split(inner);
+
+    let bytes = |kb: u64| format_bytes(kb * 1024);
+    match &metrics.details {
+        Some(details) => draw_memory_bar(frame, rows[0], rows[1], metrics.total_memory_kb, details, theme),
+        None => {
+            let mem_ratio = ratio(metrics.used_memory_kb, metrics.total_memory_kb);
+            let mem_gauge = Gauge::default()
+                .gauge_style(Style::default().fg(theme.usage_color(mem_ratio * 100.0)))
+                .ratio(mem_ratio)
+                .label(format!("RAM {} / {}", bytes(metrics.used_memory_kb), bytes(metrics.total_memory_kb)));
+            frame.render_widget(mem_gauge, rows[0]);
+        }
+    }
+
+    let swap_ratio = ratio(metrics.used_swap_kb, metrics.total_swap_kb);
+    let swap_gauge = Gauge::default()
+        .gauge_style(Style::default().fg(theme.usage_color(swap_ratio * 100.0)))
+        .ratio(swap_ratio)
+        .label(format!("Swap {} / {}", bytes(metrics.used_swap_kb), bytes(metrics.total_swap_kb)));
+    frame.render_widget(swap_gauge, rows[2]);
+
+    let mut lines = vec![Line::from(format!(
+        "Available: {}  In use: {} / {}",
+        bytes(metrics.available_memory_kb),
+        bytes(metrics.used_memory_kb),
+        bytes(metrics.total_memory_kb)
+    ))];
+    if let Some(details) = &metrics.details {
+        lines.push(Line::from(format!("Dirty: {}  Writeback: {}", bytes(details.dirty_kb), bytes(details.writeback_kb))));
+        lines.push(Line::from(format!(
+            "Slab: {} reclaimable, {} unreclaimable",
+            bytes(details.slab_reclaimable_kb),
+            bytes(details.slab_unreclaimable_kb)
+        )));
+        // Going past the limit only fails allocations under strict overcommit.
+        let committed_style = if details.committed_as_kb > details.commit_limit_kb {
+            Style::default().fg(theme.warn)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::styled(
+            format!("Committed: {} of {} limit", bytes(details.committed_as_kb), bytes(details.commit_limit_kb)),
+            committed_style,
+        ));
+        if details.huge_pages_total > 0 {
+            lines.push(Line::from(format!(
+                "Huge pages: {} / {} used ({} each)",
+                details.huge_pages_total - details.huge_pages_free.min(details.huge_pages_total),
+                details.huge_pages_total,
+                bytes(details.huge_page_size_kb)
+            )));
+        }
+        let swapping = details.swap_in_bytes_per_sec + details.swap_out_bytes_per_sec > 0.0;
+        lines.push(Line::styled(
+            format!(
+                "Swap in: {}  out: {}",
+                format_rate(details.swap_in_bytes_per_sec),
+                format_rate(details.swap_out_bytes_per_sec)
+            ),
+            if swapping { Style::default().fg(theme.warn) } else { Style::default() },
+        ));
+    }
+    frame.render_widget(Paragraph::new(lines), rows[3]);
+}
+
+// RAM as a bar stacked from application memory, buffers, shared memory and
+// reclaimable cache, with the rest free, plus a legend underneath that wraps
+// onto a second line in narrow panels.
+fn draw_memory_bar(frame: &mut Frame, bar_area: Rect, legend_area: Rect, total_kb: u64, details: &MemoryDetails, theme: &Theme) {
+    let cache_kb = (details.cached_kb + details.slab_reclaimable_kb).saturating_sub(details.shared_kb);
+    let used_kb = total_kb
+        .saturating_sub(details.free_kb)
+        .saturating_sub(details.buffers_kb)
+        .saturating_sub(details.cached_kb)
+        .saturating_sub(details.slab_reclaimable_kb);
+    let segments = [
+        ("used", used_kb, theme.ok),
+        ("buffers", details.buffers_kb, theme.accent),
+        ("shared", details.shared_kb, theme.highlight),
+        ("cache", cache_kb, theme.warn),
+    ];
+
+    // Round the running total rather than each segment, so rounding errors
+    // don't add up along the bar.
+    let width = bar_area.width as f64;
+    let mut bar = Vec::new();
+    let (mut filled_kb, mut filled_cells) = (0, 0);
+    for (_, kb, color) in segments {
+        filled_kb += kb;
+        let end = (ratio(filled_kb, total_kb) * width).round() as usize;
+        bar.push(Span::styled("█".repeat(end.saturating_sub(filled_cells)), Style::default().fg(color)));
+        filled_cells = filled_cells.max(end);
+    }
+    bar.push(Span::styled("░".repeat((bar_area.width as usize).saturating_sub(filled_cells)), Style::default().fg(theme.muted)));
+    frame.render_widget(Paragraph::new(Line::from(bar)), bar_area);
+
+    // Non-breaking spaces keep each entry together when the legend wraps.
+    let entry = |label: &str, kb: u64| format!("{}\u{a0}{}  ", label, format_bytes(kb * 1024).replace(' ', "\u{a0}"));
+    let mut legend = Vec::new();
+    for (label, kb, color) in segments {
+        legend.push(Span::styled("■\u{a0}", Style::default().fg(color)));
+        legend.push(Span::raw(entry(label, kb)));
+    }
+    legend.push(Span::styled("░\u{a0}", Style::default().fg(theme.muted)));
+    legend.push(Span::raw(entry("free", details.free_kb)));
+    frame.render_widget(Paragraph::new(Line::from(legend)).wrap(Wrap { trim: true }), legend_area);
+}
+
+fn draw_network(frame: &mut Frame, area: Rect, state: &AppState, theme: &Theme) {
+    let block = source_panel("Network", state, DataSource::Network);
+    let Some(NetworkData::Global(interfaces)) = &state.network else {
+        return draw_placeholder(frame, area, block, state, theme, DataSource::Network);
+    };
+
+    let mut interfaces: Vec<&GlobalNetworkMetrics> = interfaces.iter().collect();
+    interfaces.sort_by(|a, b| a.interface_name.cmp(&b.interface_name));
+
+    // Each interface takes two lines: bytes on top, packets underneath.
+    let muted = Style::default().fg(theme.muted);
+    let two_lines = |top: String, bottom: String| {
+        Cell::from(Text::from(vec![Line::raw(top), Line::styled(bottom, muted)]))
+    };
+    let header = Row::new(["Interface", "RX/s", "TX/s", "RX total", "TX total", "Errors", "Drops"])
+        .style(Style::default().add_modifier(Modifier::BOLD));
+    let rows = interfaces.iter().map(|iface| {
+        let errors = iface.total_receive_errors + iface.total_transmit_errors;
+        let error_style = if iface.receive_errors_per_sec + iface.transmit_errors_per_sec > 0.0 {
+            Style::default().fg(theme.critical)
+        } else {
+            Style::default()
+        };
+        let (drops, drop_style) = match (iface.total_receive_drops, iface.total_transmit_drops) {
+            (Some(rx), Some(tx)) => {
+                let dropping = iface.receive_drops_per_sec.unwrap_or(0.0) + iface.transmit_drops_per_sec.unwrap_or(0.0) > 0.0;
+                let style = if dropping { Style::default().fg(theme.warn) } else { Style::default() };
+                ((rx + tx).to_string(), style)
+            }
+            _ => ("-".to_string(), Style::default()),
+        };
+        Row::new([
+            Cell::from(iface.interface_name.clone()),
+            two_lines(format_rate(iface.received_bytes_per_sec), format!("{:.0} pkt/s", iface.received_packets_per_sec)),
+            two_lines(format_rate(iface.transmitted_bytes_per_sec), format!("{:.0} pkt/s", iface.transmitted_packets_per_sec)),
+            two_lines(format_bytes(iface.total_received_bytes), format!("{} pkts", iface.total_received_packets)),
+            two_lines(format_bytes(iface.total_transmitted_bytes), format!("{} pkts", iface.total_transmitted_packets)),
+            Cell::from(errors.to_string()).style(error_style),
+            Cell::from(drops).style(drop_style),
+        ])
+        .height(2)
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Min(8),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(6),
+            Constraint::Length(6),
+        ],
+    )
+    .header(header)
+    .block(block);
+    frame.render_widget(table, area);
+}
+
+fn draw_disk(frame: &mut Frame, area: Rect, state: &AppState, theme: &Theme) {
+    let block = source_panel("Disks", state, DataSource::Disk);
+    let Some(DiskData::Global(disks)) = &state.disk else {
+        return draw_placeholder(frame, area, block, state, theme, DataSource::Disk);
+    };
+
+    let header = Row::new(["Device", "Read/s", "Write/s", "IOPS r/w", "Lat r/w", "Queue", "Util"])
+        .style(Style::default().add_modifier(Modifier::BOLD));
+    let rows = disks.iter().map(|disk| {
+        Row::new([
+            Cell::from(disk.device_name.clone()),
+            Cell::from(format_rate(disk.read_bytes_per_sec)),
+            Cell::from(format_rate(disk.written_bytes_per_sec)),
+            Cell::from(format!("{:.0}/{:.0}", disk.reads_per_sec, disk.writes_per_sec)),
+            Cell::from(format!("{:.1}/{:.1}ms", disk.read_latency_ms, disk.write_latency_ms)),
+            Cell::from(format!("{:.2}", disk.queue_depth)),
+            Cell::from(format!("{:.0}%", disk.utilization_percent))
+                .style(Style::default().fg(theme.usage_color(disk.utilization_percent))),
+        ])
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Min(6),
+            Constraint::Length(11),
+            Constraint::Length(11),
+            Constraint::Length(9),
+            Constraint::Length(12),
+            Constraint::Length(5),
+            Constraint::Length(4),
+        ],
+    )
+    .header(header)
+    .block(block);
+    frame.render_widget(table, area);
+}
+
+fn draw_filesystems(frame: &mut Frame, area: Rect, state: &AppState, theme: &Theme, table: &FilesystemTableState) {
+    let block = source_panel("Filesystems", state, DataSource::Filesystems);
+    let Some(FilesystemData::Global(filesystems)) = &state.filesystems else {
+        return draw_placeholder(frame, area, block, state, theme, DataSource::Filesystems);
+    };
+
+    let header: Vec<Cell> = FilesystemColumn::ALL
+        .iter()
+        .map(|column| {
+            if *column != table.sort_column {
+                return Cell::from(column.title());
+            }
+            let arrow = if table.ascending { "▲" } else { "▼" };
+            Cell::from(format!("{}{}", column.title(), arrow)).style(Style::default().fg(theme.accent))
+        })
+        .collect();
+    let header = Row::new(header).style(Style::default().add_modifier(Modifier::BOLD));
+    let rows = table.sorted(filesystems).into_iter().map(|fs| {
+        let usage = fs.usage_percent();
+        // Filesystems without an inode table have nothing to show.
+        let inodes = if fs.total_inodes > 0 {
+            let inode_usage = fs.inode_usage_percent();
+            Cell::from(format!("{:.0}%", inode_usage)).style(Style::default().fg(theme.usage_color(inode_usage)))
+        } else {
+            Cell::from("-")
+        };
+        Row::new([
+            Cell::from(fs.mount_point.clone()),
+            Cell::from(fs.device.clone()),
+            Cell::from(fs.fs_type.clone()),
+            Cell::from(format_bytes(fs.total_bytes)),
+            Cell::from(format_bytes(fs.used_bytes)),
+            Cell::from(format_bytes(fs.available_bytes)),
+            Cell::from(format!("{:.0}%", usage)).style(Style::default().fg(theme.usage_color(usage))),
+            inodes,
+        ])
+    });
+    let table_widget = Table::new(
+        rows,
+        [
+            Constraint::Min(16),
+            Constraint::Min(12),
+            Constraint::Length(8),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(5),
+            Constraint::Length(8),
+        ],
+    )
+    .header(header)
+    .block(block.title(format!(" {} mounted ", filesystems.len())));
+    frame.render_widget(table_widget, area);
+}
+
+fn draw_sensors(frame: &mut Frame, area: Rect, state: &AppState, theme: &Theme) {
+    let block = source_panel("Sensors", state, DataSource::Sensors);
+    let Some(SensorData::Global(readings)) = &state.sensors else {
+        return draw_placeholder(frame, area, block, state, theme, DataSource::Sensors);
+    };
+    if readings.is_empty() {
+        let text = Paragraph::new("No sensors detected").style(Style::default().fg(theme.muted));
+        return frame.render_widget(text.block(block), area);
+    }
+
+    let format_value = |kind: SensorKind, value: f64| match kind {
+        SensorKind::Temperature => format!("{:.1}°C", value),
+        SensorKind::Fan => format!("{:.0} RPM", value),
+        SensorKind::Voltage => format!("{:.3} V", value),
+    };
+    let header = Row::new(["Chip", "Sensor", "Value", "High", "Critical"])
+        .style(Style::default().add_modifier(Modifier::BOLD));
+    let rows = readings.iter().map(|reading| {
+        let style = match (reading.kind, reading.critical) {
+            (SensorKind::Temperature, Some(critical)) if reading.value >= critical => {
+                Style::default().fg(theme.critical).add_modifier(Modifier::BOLD)
+            }
+            (SensorKind::Temperature, Some(critical)) if reading.value >= critical - CRITICAL_TEMPERATURE_MARGIN => {
+                Style::default().fg(theme.warn)
+            }
+            _ => Style::default(),
+        };
+        let threshold = |value: Option<f64>| value.map(|v| format_value(reading.kind, v)).unwrap_or_default();
+        Row::new([
+            Cell::from(reading.chip.clone()),
+            Cell::from(reading.label.clone()),
+            Cell::from(format_value(reading.kind, reading.value)),
+            Cell::from(threshold(reading.high)),
+            Cell::from(threshold(reading.critical)),
+        ])
+        .style(style)
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Min(12),
+            Constraint::Min(16),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(10),
+        ],
+    )
+    .header(header)
+    .block(block);
+    frame.render_widget(table, area);
+}
+
+fn draw_pressure(frame: &mut Frame, area: Rect, state: &AppState, theme: &Theme) {
+    let block = source_panel("Pressure", state, DataSource::Pressure);
+    let Some(PressureData::Global(pressure)) = &state.pressure else {
+        return draw_placeholder(frame, area, block, state, theme, DataSource::Pressure);
+    };
+
+    let averages = |psi: Option<&PsiAverages>| -> Vec<Cell> {
+        let Some(psi) = psi else {
+            return vec![Cell::from(""), Cell::from(""), Cell::from("")];
+        };
+        [psi.avg10, psi.avg60, psi.avg300]
+            .into_iter()
+            .map(|value| Cell::from(format!("{:.2}", value)).style(Style::default().fg(theme.pressure_color(value))))
+            .collect()
+    };
+    let header = Row::new(["Scope", "Resource", "Some 10s", "60s", "300s", "Full 10s", "60s", "300s"])
+        .style(Style::default().add_modifier(Modifier::BOLD));
+    let scopes = std::iter::once(("system", &pressure.system))
+        .chain(pressure.cgroups.iter().map(|c| (c.cgroup.as_str(), &c.pressure)));
+    let mut rows = Vec::new();
+    for (scope, pressure) in scopes {
+        // Name each scope once, on its first row.
+        let mut label = scope;
+        for (resource, psi) in pressure.resources() {
+            let Some(psi) = psi else { continue };
+            let mut cells = vec![Cell::from(std::mem::take(&mut label)), Cell::from(resource)];
+            cells.extend(averages(Some(&psi.some)));
+            cells.extend(averages(psi.full.as_ref()));
+            rows.push(Row::new(cells));
+        }
+    }
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Min(10),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Length(6),
+            Constraint::Length(6),
+            Constraint::Length(8),
+            Constraint::Length(6),
+            Constraint::Length(6),
+        ],
+    )
+    .header(header)
+    .block(block.title(" stalled % "));
+    frame.render_widget(table, area);
+}
+
+fn draw_gpu(frame: &mut Frame, area: Rect, state: &AppState, theme: &Theme) {
+    let block = source_panel("GPU", state, DataSource::Gpu);
+    let Some(GpuData::Nvidia(gpus)) = &state.gpu else {
+        return draw_placeholder(frame, area, block, state, theme, DataSource::Gpu);
+    };
+    if gpus.is_empty() {
+        let text = Paragraph::new("No GPUs detected").style(Style::default().fg(theme.muted));
+        return frame.render_widget(text.block(block), area);
+    }
+
+    let window = Duration::from_secs(HISTORY_WINDOW_SECS);
+    let header = Row::new(["GPU", "Util", "Avg 1m", "Memory", "Temp", "Fan"])
+        .style(Style::default().add_modifier(Modifier::BOLD));
+    let rows = gpus.iter().enumerate().map(|(index, gpu)| {
+        let average = state.history.gpu_utilization.get(index).and_then(|history| history.average(window));
+        Row::new([
+            Cell::from(gpu.name.clone()),
+            Cell::from(format!("{}%", gpu.utilization_percent))
+                .style(Style::default().fg(theme.usage_color(gpu.utilization_percent as f64))),
+            Cell::from(average.map(|avg| format!("{:.0}%", avg)).unwrap_or_default()),
+            Cell::from(format!("{} / {} MiB", gpu.memory_used_mb, gpu.memory_total_mb)),
+            Cell::from(format!("{}°C", gpu.temperature_celsius)),
+            Cell::from(format!("{}%", gpu.fan_speed_percent)),
+        ])
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Min(12),
+            Constraint::Length(5),
+            Constraint::Length(6),
+            Constraint::Length(20),
+            Constraint::Length(6),
+            Constraint::Length(5),
+        ],
+    )
+    .header(header)
+    .block(block);
+    frame.render_widget(table, area);
+}
+
+fn draw_processes(frame: &mut Frame, area: Rect, state: &AppState, theme: &Theme, table: &mut ProcessTableState) {
+    let block = source_panel("Processes", state, DataSource::Processes);
+    let Some(processes) = &state.processes else {
+        return draw_placeholder(frame, area, block, state, theme, DataSource::Processes);
+    };
+
+    let area = if table.show_details {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(area);
+        let selected = table.selected_pid().and_then(|pid| processes.iter().find(|p| p.pid == pid));
+        draw_process_details(frame, columns[1], selected, theme);
+        columns[0]
+    } else {
+        area
+    };
+
+    let visible = table.visible(processes);
+    table.page_size = area.height.saturating_sub(3).max(1) as usize;
+    table.sync_selection(&visible);
+
+    let mut header: Vec<Cell> = SortColumn::ALL
+        .iter()
+        .map(|column| {
+            if *column != table.sort_column {
+                return Cell::from(column.title());
+            }
+            let arrow = if table.ascending { "▲" } else { "▼" };
+            Cell::from(format!("{}{}", column.title(), arrow)).style(Style::default().fg(theme.accent))
+        })
+        .collect();
+    let mut widths = vec![
+        Constraint::Length(8),
+        Constraint::Min(16),
+        Constraint::Length(12),
+        Constraint::Length(6),
+        Constraint::Length(11),
+        Constraint::Length(10),
+    ];
+    if table.tree_mode {
+        header.extend([Cell::from("Tree CPU%"), Cell::from("Tree Mem")]);
+        widths.extend([Constraint::Length(9), Constraint::Length(11)]);
+    }
+    let header = Row::new(header).style(Style::default().add_modifier(Modifier::BOLD));
+
+    let rows = visible.iter().map(|row| {
+        let process = row.process;
+        let status_style = match process.status {
+            ProcessStatus::Zombie => Style::default().fg(theme.critical),
+            _ => Style::default(),
+        };
+        let marker = match (row.has_children, row.collapsed) {
+            (true, true) => "[+] ",
+            _ => "",
+        };
+        let mut cells = vec![
+            Cell::from(process.pid.to_string()),
+            Cell::from(format!("{}{}{}", row.tree_prefix, marker, process.name)),
+            Cell::from(process.user.clone().unwrap_or_else(|| "?".to_string())),
+            Cell::from(format!("{:.1}", process.cpu_usage_percent)),
+            Cell::from(format_bytes(process.memory_usage_kb * 1024)),
+            Cell::from(process.status.to_string()).style(status_style),
+        ];
+        if table.tree_mode {
+            cells.push(Cell::from(format!("{:.1}", row.subtree_cpu_percent)));
+            cells.push(Cell::from(format_bytes(row.subtree_memory_kb * 1024)));
+        }
+        if table.is_tagged(process.pid) {
+            Row::new(cells).style(Style::default().fg(theme.warn).add_modifier(Modifier::BOLD))
+        } else {
+            Row::new(cells)
+        }
+    });
+
+    let mut block = block.title(format!(" {}/{} shown ", visible.len(), processes.len()));
+    if table.has_filters() {
+        let mut filters = Vec::new();
+        if !table.search.is_empty() {
+            filters.push(format!("/{}/", table.search));
+        }
+        if let Some(status) = &table.status_filter {
+            filters.push(format!("status={}", status));
+        }
+        if let Some(user) = &table.user_filter {
+            filters.push(format!("user={}", user));
+        }
+        block = block.title(Span::styled(format!(" {} ", filters.join(" ")), Style::default().fg(theme.highlight)));
+    }
+
+    let table_widget = Table::new(rows, widths)
+        .header(header)
+        .row_highlight_style(Style::default().bg(theme.highlight).fg(Color::Black))
+        .block(block);
+    frame.render_stateful_widget(table_widget, area, &mut table.table_state);
+}
+
+fn draw_process_details(frame: &mut Frame, area: Rect, process: Option<&ProcessInfo>, theme: &Theme) {
+    let block = panel("Details");
+    let Some(process) = process else {
+        let text = Paragraph::new("No process selected").style(Style::default().fg(theme.muted));
+        return frame.render_widget(text.block(block), area);
+    };
+
+    let label = Style::default().fg(theme.accent);
+    let field = |name: &'static str, value: String| Line::from(vec![Span::styled(format!("{:<11}", name), label), Span::raw(value)]);
+    let unknown = || "-".to_string();
+    let command = if process.cmd.is_empty() { format!("[{}]", process.name) } else { process.cmd.join(" ") };
+
+    let lines = vec![
+        field("Name", process.name.clone()),
+        field("Command", command),
+        field("Exe", process.exe.clone().unwrap_or_else(unknown)),
+        field("Cwd", process.cwd.clone().unwrap_or_else(unknown)),
+        field("PID", match process.ppid {
+            Some(ppid) => format!("{} (parent {})", process.pid, ppid),
+            None => process.pid.to_string(),
+        }),
+        field("User", format!(
+            "{} / {}",
+            process.user.clone().unwrap_or_else(unknown),
+            process.group.clone().unwrap_or_else(unknown)
+        )),
+        field("Status", process.status.to_string()),
+        field("Threads", process.thread_count.map(|n| n.to_string()).unwrap_or_else(unknown)),
+        field("Started", format_unix_time(process.start_time_secs)),
+        field("Running", format_uptime(process.run_time_secs)),
+        field("CPU", format!("{:.1}%", process.cpu_usage_percent)),
+        field("Resident", format_bytes(process.memory_usage_kb * 1024)),
+        field("Virtual", format_bytes(process.virtual_memory_kb * 1024)),
+        field("Disk read", format!(
+            "{} ({})",
+            format_rate(process.disk_read_bytes_per_sec),
+            format_bytes(process.disk_read_bytes_total)
+        )),
+        field("Disk write", format!(
+            "{} ({})",
+            format_rate(process.disk_written_bytes_per_sec),
+            format_bytes(process.disk_written_bytes_total)
+        )),
+    ];
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }).block(block), area);
+}
+
+fn draw_footer(frame: &mut Frame, area: Rect, state: &AppState, ui: &UiState) {
+    let key_style = Style::default().fg(ui.theme.highlight).add_modifier(Modifier::BOLD);
+    if let Some(status) = ui.status.as_ref().filter(|status| status.at.elapsed() < STATUS_DURATION) {
+        let color = if status.is_error { ui.theme.critical } else { ui.theme.ok };
+        let text = Paragraph::new(status.text.as_str()).style(Style::default().fg(color));
+        return frame.render_widget(text, area);
+    }
+    if ui.processes.editing_search {
+        let line = Line::from(vec![
+            Span::styled("Search: ", key_style),
+            Span::raw(format!("{}_", ui.processes.search)),
+            Span::styled("  enter keep  esc clear", Style::default().fg(ui.theme.muted)),
+        ]);
+        return frame.render_widget(Paragraph::new(line), area);
+    }
+    let history_label = if ui.view == View::History { "dashboard" } else { "graphs" };
+    let system_label = if ui.view == View::System { "dashboard" } else { "system" };
+    let alerts_label = if ui.view == View::Alerts { "dashboard" } else { "alerts" };
+    let internals_label = if ui.view == View::Internals { "dashboard" } else { "internals" };
+    let mut entries = vec![
+        (InputAction::Quit, "quit"),
+        (InputAction::ToggleHistory, history_label),
+        (InputAction::ToggleSystem, system_label),
+        (InputAction::ToggleAlerts, alerts_label),
+        (InputAction::ToggleInternals, internals_label),
+    ];
+    let mut spans = Vec::new();
+    if let Some(replay) = &state.replay {
+        spans.extend(replay_status(replay, &ui.theme));
+        let pause_label = if replay.paused { "play" } else { "pause" };
+        entries.extend([
+            (InputAction::TogglePause, pause_label),
+            (InputAction::CycleSpeed, "speed"),
+            (InputAction::SeekBackward, "-1m"),
+            (InputAction::SeekForward, "+1m"),
+            (InputAction::SeekBackwardFar, "-10m"),
+            (InputAction::SeekForwardFar, "+10m"),
+        ]);
+    } else {
+        entries.extend([(InputAction::FasterRefresh, "faster"), (InputAction::SlowerRefresh, "slower")]);
+    }
+    match ui.view {
+        // Process actions are refused during a replay, so their keys are not advertised.
+        View::Dashboard if state.replay.is_some() => entries.extend([
+            (InputAction::Search, "search"),
+            (InputAction::SortNext, "sort"),
+            (InputAction::ReverseSort, "reverse"),
+            (InputAction::ToggleTree, "tree"),
+            (InputAction::ToggleDetails, "details"),
+        ]),
+        View::Dashboard => entries.extend([
+            (InputAction::Search, "search"),
+            (InputAction::SortNext, "sort"),
+            (InputAction::ReverseSort, "reverse"),
+            (InputAction::FilterStatus, "status"),
+            (InputAction::FilterUser, "user"),
+            (InputAction::ToggleTree, "tree"),
+            (InputAction::ToggleDetails, "details"),
+            (InputAction::Tag, "tag"),
+            (InputAction::SendSignal, "signal"),
+            (InputAction::Renice, "nice"),
+            (InputAction::SetAffinity, "affinity"),
+        ]),
+        View::System => entries.extend([(InputAction::SortNext, "sort"), (InputAction::ReverseSort, "reverse")]),
+        View::History | View::Alerts | View::Internals => {}
+    }
+    for (action, label) in entries {
+        if let Some(key) = ui.keymap.key_for(action) {
+            spans.push(Span::styled(key.to_string(), key_style));
+            spans.push(Span::raw(format!(" {}  ", label)));
+        }
+    }
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+// e.g. `⏸ 10x 2024-03-01 14:05:09 UTC 00:12:40/01:00:00`
+fn replay_status(replay: &ReplayStatus, theme: &Theme) -> Vec<Span<'static>> {
+    let (icon, color) = match (replay.finished, replay.paused) {
+        (true, _) => ("■", theme.muted),
+        (false, true) => ("⏸", theme.warn),
+        (false, false) => ("▶", theme.ok),
+    };
+    let elapsed_secs = (replay.position_ms - replay.start_ms) / 1000;
+    let total_secs = (replay.end_ms - replay.start_ms) / 1000;
+    vec![
+        Span::styled(format!("{} {}x ", icon, replay.speed), Style::default().fg(color).add_modifier(Modifier::BOLD)),
+        Span::raw(format!(
+            "{} {}/{}  ",
+            format_unix_time(replay.position_ms / 1000),
+            format_uptime(elapsed_secs),
+            format_uptime(total_secs)
+        )),
+    ]
+}
+
+fn draw_dialog(frame: &mut Frame, dialog: &Dialog, theme: &Theme) {
+    let muted = Style::default().fg(theme.muted);
+    let target = match dialog.targets.as_slice() {
+        [(pid, name)] => format!("process {} ({})", pid, name),
+        targets => {
+            let names: Vec<String> = targets.iter().take(3).map(|(pid, name)| format!("{} {}", pid, name)).collect();
+            let more = if targets.len() > 3 { ", ..." } else { "" };
+            format!("{} processes ({}{})", targets.len(), names.join(", "), more)
+        }
+    };
+
+    let (title, mut lines) = match &dialog.step {
+        DialogStep::SignalMenu { selected } => {
+            let lines = Signal::MENU
+                .iter()
+                .map(|signal| signal.name())
+                .chain(["Other...".to_string()])
+                .enumerate()
+                .map(|(index, name)| {
+                    let style = if index == *selected {
+                        Style::default().bg(theme.highlight).fg(Color::Black)
+                    } else {
+                        Style::default()
+                    };
+                    Line::styled(format!(" {} ", name), style)
+                })
+                .collect();
+            (" Send signal ", lines)
+        }
+        DialogStep::Prompt { kind, text, error } => {
+            let title = match kind {
+                PromptKind::Signal => " Send signal ",
+                PromptKind::Nice => " Renice ",
+                PromptKind::Affinity => " CPU affinity ",
+            };
+            let mut lines = vec![Line::from(format!("{}: {}_", kind.label(), text))];
+            if let Some(error) = error {
+                lines.push(Line::styled(error.clone(), Style::default().fg(theme.critical)));
+            }
+            (title, lines)
+        }
+        DialogStep::Confirm { action } => {
+            let question = format!("Really {} {}?", action.describe(), target);
+            (" Confirm ", vec![Line::from(question), Line::default(), Line::styled("y confirm  n cancel", muted)])
+        }
+    };
+    if !matches!(dialog.step, DialogStep::Confirm { .. }) {
+        lines.insert(0, Line::styled(format!("For {}", target), muted));
+        lines.insert(1, Line::default());
+    }
+
+    let frame_area = frame.area();
+    let width = frame_area.width.min(60);
+    let height = (lines.len() as u16 + 2).min(frame_area.height);
+    let area = Rect {
+        x: frame_area.x + (frame_area.width - width) / 2,
+        y: frame_area.y + (frame_area.height - height) / 2,
+        width,
+        height,
+    };
+    let block = panel(title).border_style(Style::default().fg(theme.accent));
+    frame.render_widget(Clear, area);
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }).block(block), area);
+}
+
+fn draw_system(frame: &mut Frame, state: &AppState, ui: &UiState) {
+    let layout = SystemLayout::new(frame.area());
+    draw_filesystems(frame, layout.filesystems, state, &ui.theme, &ui.filesystems);
+    draw_sensors(frame, layout.sensors, state, &ui.theme);
+    draw_pressure(frame, layout.pressure, state, &ui.theme);
+    draw_footer(frame, layout.footer, state, ui);
+}
+
+fn draw_alerts(frame: &mut Frame, state: &AppState, ui: &UiState) {
+    let layout = AlertsLayout::new(frame.area());
+    let theme = &ui.theme;
+    let severity_color = |severity: Severity| match severity {
+        Severity::Warning => theme.warn,
+        Severity::Critical => theme.critical,
+    };
+    let now = state.now();
+    let age = |since: Option<Timestamp>| {
+        since.map_or_else(String::new, |since| format_uptime(now.saturating_duration_since(since.monotonic).as_secs()))
+    };
+
+    let block = panel("Alerts");
+    if state.alerts.is_empty() {
+        let text = Paragraph::new("No alert rules configured; add [[alerts.rules]] to the config file.")
+            .style(Style::default().fg(theme.muted));
+        frame.render_widget(text.block(block), layout.rules);
+    } else {
+        let header = Row::new(["Name", "Rule", "Severity", "State", "For", "Value"])
+            .style(Style::default().add_modifier(Modifier::BOLD));
+        let rows = state.alerts.iter().map(|alert| {
+            let (label, style) = match alert.state {
+                AlertState::Ok => ("ok", Style::default().fg(theme.ok)),
+                AlertState::Pending => ("pending", Style::default().fg(theme.warn)),
+                AlertState::Firing => {
+                    ("FIRING", Style::default().fg(severity_color(alert.severity)).add_modifier(Modifier::BOLD))
+                }
+            };
+            Row::new(vec![
+                Cell::from(alert.name.clone()),
+                Cell::from(alert.rule.clone()),
+                Cell::from(alert.severity.name()).style(Style::default().fg(severity_color(alert.severity))),
+                Cell::from(label).style(style),
+                Cell::from(if alert.state == AlertState::Ok { String::new() } else { age(alert.since) }),
+                Cell::from(alert.value.map_or_else(|| "no data".to_string(), |value| alert.unit.format(value))),
+            ])
+        });
+        let widths = [
+            Constraint::Length(20),
+            Constraint::Min(30),
+            Constraint::Length(9),
+            Constraint::Length(8),
+            Constraint::Length(9),
+            Constraint::Length(14),
+        ];
+        frame.render_widget(Table::new(rows, widths).header(header).block(block), layout.rules);
+    }
+
+    // Newest first, as many as fit.
+    let log_block = panel("Alert log");
+    let lines: Vec<Line> = state
+        .alert_log
+        .iter()
+        .rev()
+        .map(|transition| {
+            let (label, color) = match transition.kind {
+                TransitionKind::Firing => ("FIRING  ", severity_color(transition.severity)),
+                TransitionKind::Resolved => ("resolved", theme.ok),
+            };
+            let value = transition.value.map_or_else(|| "no data".to_string(), |value| transition.unit.format(value));
+            Line::from(vec![
+                Span::styled(format_unix_time(transition.at.unix_millis() / 1000), Style::default().fg(theme.muted)),
+                Span::raw("  "),
+                Span::styled(label, Style::default().fg(color).add_modifier(Modifier::BOLD)),
+                Span::raw(format!("  {}  {}  ({})", transition.name, transition.rule, value)),
+            ])
+        })
+        .collect();
+    let log = if lines.is_empty() {
+        Paragraph::new("No alerts have fired yet.").style(Style::default().fg(theme.muted))
+    } else {
+        Paragraph::new(lines)
+    };
+    frame.render_widget(log.block(log_block), layout.log);
+    draw_footer(frame, layout.footer, state, ui);
+}
+
+fn draw_internals(frame: &mut Frame, state: &AppState, ui: &UiState) {
+    let layout = InternalsLayout::new(frame.area());
+    let theme = &ui.theme;
+    let now = Instant::now();
+    let ago = |at: Option<Timestamp>| {
+        let Some(at) = at else { return "never".to_string() };
+        match now.saturating_duration_since(at.monotonic).as_secs() {
+            secs if secs < 60 => format!("{}s ago", secs),
+            secs => format!("{} ago", format_uptime(secs)),
+        }
+    };
+
+    let block = panel("Collectors");
+    if state.replay.is_some() {
+        let text = Paragraph::new("Collectors don't run during a replay.").style(Style::default().fg(theme.muted));
+        frame.render_widget(text.block(block), layout.collectors);
+    } else {
+        let header = Row::new(["Collector", "Status", "Interval", "Last success", "Took", "Failures", "Dropped", "Last error"])
+            .style(Style::default().add_modifier(Modifier::BOLD));
+        let rows = DataSource::ALL.into_iter().map(|source| {
+            let dropped = state.dropped_updates.get(&source).copied().unwrap_or(0).to_string();
+            if let Some(reason) = state.unavailable_reason(source) {
+                let muted = Style::default().fg(theme.muted);
+                return Row::new(vec![
+                    Cell::from(source.name()),
+                    Cell::from("unavailable"),
+                    Cell::from(""),
+                    Cell::from(""),
+                    Cell::from(""),
+                    Cell::from(""),
+                    Cell::from(dropped),
+                    Cell::from(reason.to_string()),
+                ])
+                .style(muted);
+            }
+            let interval_ms = state.refresh_intervals_ms.get(&source).copied();
+            let health = state.collector_health.get(&source).cloned().unwrap_or_default();
+            let (label, color) = collector_status(&health, interval_ms, now, theme);
+            let failures_style = if health.consecutive_failures > 0 { Style::default().fg(theme.critical) } else { Style::default() };
+            let error = match (&health.last_error, health.last_error_at) {
+                (Some(error), at) => format!("{} ({})", error, ago(at)),
+                (None, _) => String::new(),
+            };
+            Row::new(vec![
+                Cell::from(source.name()),
+                Cell::from(label).style(Style::default().fg(color)),
+                Cell::from(interval_ms.map_or_else(String::new, |ms| format!("{}ms", ms))),
+                Cell::from(ago(health.last_success)),
+                Cell::from(format!("{:.1}ms", health.last_duration_ms)),
+                Cell::from(health.consecutive_failures.to_string()).style(failures_style),
+                Cell::from(dropped),
+                Cell::from(error).style(Style::default().fg(theme.critical)),
+            ])
+        });
+        let widths = [
+            Constraint::Length(13),
+            Constraint::Length(11),
+            Constraint::Length(9),
+            Constraint::Length(14),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(8),
+            Constraint::Min(20),
+        ];
+        frame.render_widget(Table::new(rows, widths).header(header).block(block), layout.collectors);
+    }
+
+    let text = match state.self_usage {
+        Some(usage) => Paragraph::new(format!(
+            "CPU: {:.1}%  RSS: {}  Threads: {}",
+            usage.cpu_percent,
+            format_bytes(usage.rss_bytes),
+            usage.threads
+        )),
+        None => Paragraph::new("Measuring...").style(Style::default().fg(theme.muted)),
+    };
+    frame.render_widget(text.block(panel("monoxide overhead")), layout.overhead);
+    draw_footer(frame, layout.footer, state, ui);
+}
+
+// A collector's state in a word, judged against its refresh interval.
+fn collector_status(health: &CollectorHealth, interval_ms: Option<u64>, now: Instant, theme: &Theme) -> (&'static str, Color) {
+    let grace = Duration::from_millis(interval_ms.unwrap_or(1000)) * STALE_AFTER_INTERVALS;
+    let overdue = |at: Option<Timestamp>| at.is_some_and(|at| now.saturating_duration_since(at.monotonic) > grace);
+    if overdue(health.collecting_since) {
+        ("stuck", theme.critical)
+    } else if health.consecutive_failures > 0 {
+        ("failing", theme.critical)
+    } else if health.last_success.is_none() {
+        ("starting", theme.muted)
+    } else if overdue(health.last_success) {
+        ("stale", theme.warn)
+    } else {
+        ("ok", theme.ok)
+    }
+}
+
+fn draw_history(frame: &mut Frame, state: &AppState, ui: &UiState) {
+    let layout = HistoryLayout::new(frame.area());
+    let theme = &ui.theme;
+    let now = state.now();
+    let percent = |v: f64| format!("{:.0}%", v);
+
+    // CPU: total on top of the individual cores. Only the total gets a
+    // legend entry; the cores would drown it.
+    let mut cpu_series = vec![Series::new("total", theme.accent, points(&state.history.cpu_total, now, |v| *v as f64))];
+    for (index, core) in state.history.cpu_cores.iter().enumerate() {
+        cpu_series.push(Series::new(format!("cpu{}", index), theme.muted, points(core, now, |v| *v as f64)));
+    }
+    let cpu_chart = ChartSpec { title: "CPU %", series: cpu_series, y_max: 100.0, y_label: percent, legend_entries: Some(1) };
+    draw_chart(frame, layout.cpu, cpu_chart, theme);
+
+    // Memory and swap as a share of their totals.
+    let (memory_total, swap_total) = match &state.memory {
+        Some(MemoryData::Global(metrics)) => (metrics.total_memory_kb, metrics.total_swap_kb),
+        None => (0, 0),
+    };
+    let memory_series = vec![
+        Series::new("memory", theme.accent, points(&state.history.memory_used_kb, now, |v| ratio(*v, memory_total) * 100.0)),
+        Series::new("swap", theme.warn, points(&state.history.swap_used_kb, now, |v| ratio(*v, swap_total) * 100.0)),
+    ];
+    let memory_chart = ChartSpec { title: "Memory %", series: memory_series, y_max: 100.0, y_label: percent, legend_entries: None };
+    draw_chart(frame, layout.memory, memory_chart, theme);
+
+    let mut interfaces: Vec<&String> = state.history.network_rx.keys().collect();
+    interfaces.sort();
+    let mut network_series = Vec::new();
+    for name in interfaces {
+        if let Some(history) = state.history.network_rx.get(name) {
+            network_series.push(Series::new(format!("{} rx", name), theme.ok, points(history, now, |v| *v)));
+        }
+        if let Some(history) = state.history.network_tx.get(name) {
+            network_series.push(Series::new(format!("{} tx", name), theme.warn, points(history, now, |v| *v)));
+        }
+    }
+    let network_max = network_series
+        .iter()
+        .flat_map(|series| series.points.iter().map(|(_, y)| *y))
+        .fold(0.0, f64::max)
+        .max(1024.0);
+    let network_chart = ChartSpec {
+        title: "Network",
+        series: network_series,
+        y_max: network_max * 1.1,
+        y_label: format_rate,
+        legend_entries: None,
+    };
+    draw_chart(frame, layout.network, network_chart, theme);
+
+    let gpu_series = state
+        .history
+        .gpu_utilization
+        .iter()
+        .enumerate()
+        .map(|(index, history)| Series::new(format!("gpu{}", index), theme.accent, points(history, now, |v| *v as f64)))
+        .collect();
+    let gpu_chart = ChartSpec { title: "GPU %", series: gpu_series, y_max: 100.0, y_label: percent, legend_entries: None };
+    draw_chart(frame, layout.gpu, gpu_chart, theme);
+
+    draw_footer(frame, layout.footer, state, ui);
+}
+
+struct Series {
+    name: String,
+    color: Color,
+    // (seconds relative to now, value)
+    points: Vec<(f64, f64)>,
+}
+
+impl Series {
+    fn new(name: impl Into<String>, color: Color, points: Vec<(f64, f64)>) -> Self {
+        Series { name: name.into(), color, points }
+    }
+}
+
+struct ChartSpec<'a> {
+    title: &'a str,
+    series: Vec<Series>,
+    y_max: f64,
+    y_label: fn(f64) -> String,
+    // Only the first N series get a legend entry; `None` labels all of them.
+    legend_entries: Option<usize>,
+}
+
+// History samples within the window as (seconds relative to now, value).
+fn points<T>(history: &History<T>, now: Instant, value: impl Fn(&T) -> f64) -> Vec<(f64, f64)> {
+    history
+        .last_secs(HISTORY_WINDOW_SECS)
+        .map(|(at, v)| (-now.saturating_duration_since(*at).as_secs_f64(), value(v)))
+        .collect()
+}
+
+fn draw_chart(frame: &mut Frame, area: Rect, spec: ChartSpec, theme: &Theme) {
+    let datasets = spec
+        .series
+        .iter()
+        .enumerate()
+        .map(|(index, series)| {
+            let dataset = Dataset::default()
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(series.color))
+                .data(&series.points);
+            if spec.legend_entries.is_none_or(|limit| index < limit) {
+                dataset.name(series.name.clone())
+            } else {
+                dataset
+            }
+        })
+        .collect();
+
+    let window = HISTORY_WINDOW_SECS as f64;
+    let axis_style = Style::default().fg(theme.muted);
+    let y_label = spec.y_label;
+    let chart = Chart::new(datasets)
+        .block(panel(spec.title))
+        .x_axis(
+            Axis::default()
+                .style(axis_style)
+                .bounds([-window, 0.0])
+                .labels([format!("-{}s", HISTORY_WINDOW_SECS), "now".to_string()]),
+        )
+        .y_axis(
+            Axis::default()
+                .style(axis_style)
+                .bounds([0.0, spec.y_max])
+                .labels([y_label(0.0), y_label(spec.y_max / 2.0), y_label(spec.y_max)]),
+        );
+    frame.render_widget(chart, area);
+}
@@ -0,0 +1,97 @@
+use crate::state::data_types::FilesystemMetrics;
+
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilesystemColumn {
+    MountPoint,
+    Device,
+    Type,
+    Size,
+    Used,
+    Available,
+    Usage,
+    InodeUsage,
+}
+
+impl FilesystemColumn {
+    pub const ALL: [FilesystemColumn; 8] = [
+        FilesystemColumn::MountPoint,
+        FilesystemColumn::Device,
+        FilesystemColumn::Type,
+        FilesystemColumn::Size,
+        FilesystemColumn::Used,
+        FilesystemColumn::Available,
+        FilesystemColumn::Usage,
+        FilesystemColumn::InodeUsage,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            FilesystemColumn::MountPoint => "Mount",
+            FilesystemColumn::Device => "Device",
+            FilesystemColumn::Type => "Type",
+            FilesystemColumn::Size => "Size",
+            FilesystemColumn::Used => "Used",
+            FilesystemColumn::Available => "Avail",
+            FilesystemColumn::Usage => "Use%",
+            FilesystemColumn::InodeUsage => "Inodes%",
+        }
+    }
+
+    // Text columns read naturally A-Z, numbers biggest first.
+    fn default_ascending(self) -> bool {
+        matches!(self, FilesystemColumn::MountPoint | FilesystemColumn::Device | FilesystemColumn::Type)
+    }
+
+    fn compare(self, a: &FilesystemMetrics, b: &FilesystemMetrics) -> Ordering {
+        match self {
+            FilesystemColumn::MountPoint => a.mount_point.cmp(&b.mount_point),
+            FilesystemColumn::Device => a.device.cmp(&b.device),
+            FilesystemColumn::Type => a.fs_type.cmp(&b.fs_type),
+            FilesystemColumn::Size => a.total_bytes.cmp(&b.total_bytes),
+            FilesystemColumn::Used => a.used_bytes.cmp(&b.used_bytes),
+            FilesystemColumn::Available => a.available_bytes.cmp(&b.available_bytes),
+            FilesystemColumn::Usage => a.usage_percent().total_cmp(&b.usage_percent()),
+            FilesystemColumn::InodeUsage => a.inode_usage_percent().total_cmp(&b.inode_usage_percent()),
+        }
+    }
+}
+
+/// Sort order of the filesystem table. Fullest filesystems come first by default.
+#[derive(Debug)]
+pub struct FilesystemTableState {
+    pub sort_column: FilesystemColumn,
+    pub ascending: bool,
+}
+
+impl Default for FilesystemTableState {
+    fn default() -> Self {
+        FilesystemTableState { sort_column: FilesystemColumn::Usage, ascending: false }
+    }
+}
+
+impl FilesystemTableState {
+    /// `filesystems` in display order. Ties fall back to the mount point.
+    pub fn sorted<'a>(&self, filesystems: &'a [FilesystemMetrics]) -> Vec<&'a FilesystemMetrics> {
+        let mut sorted: Vec<&FilesystemMetrics> = filesystems.iter().collect();
+        sorted.sort_by(|a, b| {
+            let ordering = self.sort_column.compare(a, b);
+            let ordering = if self.ascending { ordering } else { ordering.reverse() };
+            ordering.then_with(|| a.mount_point.cmp(&b.mount_point))
+        });
+        sorted
+    }
+
+    pub fn cycle_sort(&mut self, forward: bool) {
+        let count = FilesystemColumn::ALL.len();
+        let index = FilesystemColumn::ALL.iter().position(|c| *c == self.sort_column).unwrap_or(0);
+        let next = if forward { (index + 1) % count } else { (index + count - 1) % count };
+        self.sort_column = FilesystemColumn::ALL[next];
+        self.ascending = self.sort_column.default_ascending();
+    }
+
+    pub fn reverse_sort(&mut self) {
+        self.ascending = !self.ascending;
+    }
+}
@@ -0,0 +1,407 @@
+use crate::config::config_error;
+use crate::error::AppError;
+use crate::shutdown::ShutdownSignal;
+
+use crossbeam_channel::Sender;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use std::collections::HashMap;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+// How long the input thread blocks before re-checking the shutdown signal.
+const INPUT_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputAction {
+    Quit,
+    Redraw,
+    // Halve or double every collector's refresh interval.
+    FasterRefresh,
+    SlowerRefresh,
+    ToggleHistory,
+    ToggleSystem,
+    ToggleAlerts,
+    ToggleInternals,
+    // Process table.
+    SortNext,
+    SortPrevious,
+    ReverseSort,
+    Search,
+    FilterStatus,
+    FilterUser,
+    ClearFilters,
+    ToggleTree,
+    ToggleCollapse,
+    ToggleDetails,
+    // Process actions.
+    Tag,
+    ClearTags,
+    SendSignal,
+    Renice,
+    SetAffinity,
+    SelectUp,
+    SelectDown,
+    PageUp,
+    PageDown,
+    SelectFirst,
+    SelectLast,
+    // Replay playback.
+    TogglePause,
+    CycleSpeed,
+    SeekBackward,
+    SeekForward,
+    SeekBackwardFar,
+    SeekForwardFar,
+    None,
+}
+
+impl InputAction {
+    // The action's key in `ui.keybindings`; empty for actions that can't be rebound.
+    fn config_name(&self) -> &'static str {
+        InputAction::BINDABLE.iter().find(|(_, action, _)| action == self).map_or("", |(name, _, _)| name)
+    }
+
+    // Actions that can be rebound through `ui.keybindings`, with their default keys.
+    const BINDABLE: [(&'static str, InputAction, &'static str); 34] = [
+        ("quit", InputAction::Quit, "q"),
+        ("toggle_history", InputAction::ToggleHistory, "g"),
+        ("toggle_system", InputAction::ToggleSystem, "s"),
+        ("toggle_alerts", InputAction::ToggleAlerts, "A"),
+        ("toggle_internals", InputAction::ToggleInternals, "i"),
+        ("faster_refresh", InputAction::FasterRefresh, "+"),
+        ("slower_refresh", InputAction::SlowerRefresh, "-"),
+        ("sort_next", InputAction::SortNext, ">"),
+        ("sort_previous", InputAction::SortPrevious, "<"),
+        ("reverse_sort", InputAction::ReverseSort, "r"),
+        ("search", InputAction::Search, "/"),
+        ("filter_status", InputAction::FilterStatus, "t"),
+        ("filter_user", InputAction::FilterUser, "u"),
+        ("clear_filters", InputAction::ClearFilters, "esc"),
+        ("toggle_tree", InputAction::ToggleTree, "f5"),
+        ("toggle_collapse", InputAction::ToggleCollapse, "space"),
+        ("toggle_details", InputAction::ToggleDetails, "enter"),
+        ("tag", InputAction::Tag, "m"),
+        ("clear_tags", InputAction::ClearTags, "M"),
+        ("send_signal", InputAction::SendSignal, "k"),
+        ("renice", InputAction::Renice, "n"),
+        ("set_affinity", InputAction::SetAffinity, "a"),
+        ("select_up", InputAction::SelectUp, "up"),
+        ("select_down", InputAction::SelectDown, "down"),
+        ("page_up", InputAction::PageUp, "pageup"),
+        ("page_down", InputAction::PageDown, "pagedown"),
+        ("select_first", InputAction::SelectFirst, "home"),
+        ("select_last", InputAction::SelectLast, "end"),
+        ("toggle_pause", InputAction::TogglePause, "p"),
+        ("cycle_speed", InputAction::CycleSpeed, "x"),
+        ("seek_backward", InputAction::SeekBackward, "left"),
+        ("seek_forward", InputAction::SeekForward, "right"),
+        ("seek_backward_far", InputAction::SeekBackwardFar, "["),
+        ("seek_forward_far", InputAction::SeekForwardFar, "]"),
+    ];
+}
+
+/// A single key plus modifiers, parsed from strings like `q`, `ctrl+r` or `f5`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    pub fn parse(spec: &str) -> Result<KeyBinding, String> {
+        let unknown_key = || format!("cannot parse key `{}`", spec);
+        let mut modifiers = KeyModifiers::NONE;
+        let mut parts: Vec<&str> = spec.split('+').collect();
+        // A trailing empty part means the key itself is '+', e.g. "ctrl++".
+        let key = match parts.pop().ok_or_else(unknown_key)? {
+            "" if spec.ends_with('+') => {
+                parts.pop();
+                "+"
+            }
+            key => key,
+        };
+        for modifier in parts {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier `{}` in `{}`", modifier, spec)),
+            };
+        }
+
+        let code = match key.to_ascii_lowercase().as_str() {
+            "esc" | "escape" => KeyCode::Esc,
+            "enter" | "return" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "space" => KeyCode::Char(' '),
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            lower => {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => {
+                        let number = lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok());
+                        match number {
+                            Some(number @ 1..=12) => KeyCode::F(number),
+                            _ => return Err(unknown_key()),
+                        }
+                    }
+                }
+            }
+        };
+        // Terminals report shifted characters as the character itself, so
+        // `shift+a` would never match; the binding has to name `A`.
+        if let KeyCode::Char(c) = code
+            && modifiers.contains(KeyModifiers::SHIFT)
+        {
+            let instead = match c {
+                c if c.is_alphabetic() => format!("`{}`", c.to_uppercase()),
+                ' ' => "`space`".to_string(),
+                _ => "the character shift produces".to_string(),
+            };
+            return Err(format!("`{}` never matches; write {} instead", spec, instead));
+        }
+        Ok(KeyBinding { code, modifiers })
+    }
+
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        // Shift is already folded into the character for printable keys.
+        let modifiers = match key.code {
+            KeyCode::Char(_) => key.modifiers - KeyModifiers::SHIFT,
+            _ => key.modifiers,
+        };
+        self.code == key.code && self.modifiers == modifiers
+    }
+}
+
+impl std::fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            code => write!(f, "{}", format!("{:?}", code).to_lowercase()),
+        }
+    }
+}
+
+/// Maps key presses to actions, built from the defaults plus `ui.keybindings`.
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    bindings: Vec<(KeyBinding, InputAction)>,
+}
+
+impl KeyMap {
+    pub fn from_config(overrides: &HashMap<String, String>) -> Result<KeyMap, AppError> {
+        for name in overrides.keys() {
+            if !InputAction::BINDABLE.iter().any(|(action, _, _)| action == name) {
+                return Err(config_error(format!("ui.keybindings.{}", name), "unknown action"));
+            }
+        }
+
+        let mut bindings: Vec<(KeyBinding, InputAction)> = Vec::new();
+        for (name, action, default_key) in InputAction::BINDABLE {
+            let spec = overrides.get(name).map(String::as_str).unwrap_or(default_key);
+            let binding = KeyBinding::parse(spec).map_err(|e| config_error(format!("ui.keybindings.{}", name), e))?;
+            bindings.push((binding, action));
+        }
+
+        // Only the first action bound to a key would ever run.
+        for (index, (binding, action)) in bindings.iter().enumerate() {
+            let Some((_, other)) = bindings[..index].iter().find(|(earlier, _)| earlier == binding) else {
+                continue;
+            };
+            let (name, other_name) = (action.config_name(), other.config_name());
+            // Blame the action the user rebound, so the error points at their change.
+            let key = if overrides.contains_key(name) { name } else { other_name };
+            return Err(config_error(
+                format!("ui.keybindings.{}", key),
+                format!("`{}` is bound to both {} and {}", binding, other_name, name),
+            ));
+        }
+        Ok(KeyMap { bindings })
+    }
+
+    /// The key currently bound to `action`, for help text.
+    pub fn key_for(&self, action: InputAction) -> Option<KeyBinding> {
+        self.bindings.iter().find(|(_, a)| *a == action).map(|(binding, _)| *binding)
+    }
+
+    pub fn handle_event(&self, event: &Event) -> InputAction {
+        match event {
+            Event::Key(key) => self.handle_key(key),
+            Event::Resize(_, _) => InputAction::Redraw,
+            _ => InputAction::None,
+        }
+    }
+
+    fn handle_key(&self, key: &KeyEvent) -> InputAction {
+        if key.kind != KeyEventKind::Press {
+            return InputAction::None;
+        }
+        if is_ctrl_c(key) {
+            return InputAction::Quit;
+        }
+        self.bindings
+            .iter()
+            .find(|(binding, _)| binding.matches(key))
+            .map(|(_, action)| *action)
+            .unwrap_or(InputAction::None)
+    }
+}
+
+// Ctrl+C always quits, whatever the config or input mode says.
+pub fn is_ctrl_c(key: &KeyEvent) -> bool {
+    key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+/// Reads terminal events on a dedicated thread and forwards them to `sender`
+/// until the shutdown signal is set or the receiving side goes away.
+pub fn spawn_input_thread(sender: Sender<Event>, shutdown_signal: ShutdownSignal) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        log::debug!("Input thread started.");
+        while !shutdown_signal.is_set() {
+            match event::poll(INPUT_POLL_INTERVAL) {
+                Ok(true) => match event::read() {
+                    Ok(event) => {
+                        if sender.send(event).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => log::error!("Failed to read terminal event: {:?}", e),
+                },
+                Ok(false) => {}
+                Err(e) => {
+                    log::error!("Failed to poll terminal events: {:?}", e);
+                    break;
+                }
+            }
+        }
+        log::debug!("Input thread stopped.");
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn parses_keys_and_modifiers() {
+        let binding = |code, modifiers| Ok(KeyBinding { code, modifiers });
+        assert_eq!(KeyBinding::parse("q"), binding(KeyCode::Char('q'), KeyModifiers::NONE));
+        assert_eq!(KeyBinding::parse("A"), binding(KeyCode::Char('A'), KeyModifiers::NONE));
+        assert_eq!(KeyBinding::parse("ctrl+r"), binding(KeyCode::Char('r'), KeyModifiers::CONTROL));
+        assert_eq!(KeyBinding::parse("Ctrl+Alt+x"), binding(KeyCode::Char('x'), KeyModifiers::CONTROL | KeyModifiers::ALT));
+        assert_eq!(KeyBinding::parse("+"), binding(KeyCode::Char('+'), KeyModifiers::NONE));
+        assert_eq!(KeyBinding::parse("ctrl++"), binding(KeyCode::Char('+'), KeyModifiers::CONTROL));
+        assert_eq!(KeyBinding::parse("F5"), binding(KeyCode::F(5), KeyModifiers::NONE));
+        assert_eq!(KeyBinding::parse("esc"), binding(KeyCode::Esc, KeyModifiers::NONE));
+        assert_eq!(KeyBinding::parse("shift+tab"), binding(KeyCode::Tab, KeyModifiers::SHIFT));
+        assert_eq!(KeyBinding::parse("shift+up"), binding(KeyCode::Up, KeyModifiers::SHIFT));
+
+        assert_eq!(KeyBinding::parse("f13"), Err("cannot parse key `f13`".to_string()));
+        assert_eq!(KeyBinding::parse("bogus"), Err("cannot parse key `bogus`".to_string()));
+        assert_eq!(KeyBinding::parse("meta+q"), Err("unknown modifier `meta` in `meta+q`".to_string()));
+    }
+
+    #[test]
+    fn rejects_shifted_characters() {
+        assert_eq!(KeyBinding::parse("shift+x"), Err("`shift+x` never matches; write `X` instead".to_string()));
+        assert_eq!(
+            KeyBinding::parse("ctrl+shift+a"),
+            Err("`ctrl+shift+a` never matches; write `A` instead".to_string())
+        );
+        assert_eq!(
+            KeyBinding::parse("shift+1"),
+            Err("`shift+1` never matches; write the character shift produces instead".to_string())
+        );
+        let overrides = HashMap::from([("tag".to_string(), "shift+m".to_string())]);
+        match KeyMap::from_config(&overrides) {
+            Err(AppError::ConfigError { key, message }) => {
+                assert_eq!(key, "ui.keybindings.tag");
+                assert_eq!(message, "`shift+m` never matches; write `M` instead");
+            }
+            _ => panic!("expected a config error"),
+        }
+    }
+
+    #[test]
+    fn rejects_keys_bound_to_two_actions() {
+        let error = |overrides: &[(&str, &str)]| {
+            let overrides = overrides.iter().map(|(name, key)| (name.to_string(), key.to_string())).collect();
+            match KeyMap::from_config(&overrides) {
+                Err(AppError::ConfigError { key, message }) => (key, message),
+                _ => panic!("expected a config error for {:?}", overrides),
+            }
+        };
+        // Clashing with a default, whichever comes first in the list.
+        assert_eq!(
+            error(&[("quit", "s")]),
+            ("ui.keybindings.quit".to_string(), "`s` is bound to both quit and toggle_system".to_string())
+        );
+        assert_eq!(
+            error(&[("seek_forward_far", "q")]),
+            ("ui.keybindings.seek_forward_far".to_string(), "`q` is bound to both quit and seek_forward_far".to_string())
+        );
+        // Two overrides with the same key.
+        assert_eq!(
+            error(&[("tag", "ctrl+t"), ("renice", "ctrl+t")]),
+            ("ui.keybindings.renice".to_string(), "`ctrl+t` is bound to both tag and renice".to_string())
+        );
+        // Swapping two keys is fine.
+        let swapped = HashMap::from([("quit".to_string(), "s".to_string()), ("toggle_system".to_string(), "q".to_string())]);
+        assert!(KeyMap::from_config(&swapped).is_ok());
+    }
+
+    #[test]
+    fn matches_shifted_characters_by_the_character() {
+        let binding = KeyBinding::parse("M").unwrap();
+        assert!(binding.matches(&key(KeyCode::Char('M'), KeyModifiers::SHIFT)));
+        assert!(binding.matches(&key(KeyCode::Char('M'), KeyModifiers::NONE)));
+        assert!(!binding.matches(&key(KeyCode::Char('m'), KeyModifiers::NONE)));
+
+        let binding = KeyBinding::parse("shift+tab").unwrap();
+        assert!(binding.matches(&key(KeyCode::Tab, KeyModifiers::SHIFT)));
+        assert!(!binding.matches(&key(KeyCode::Tab, KeyModifiers::NONE)));
+    }
+
+    #[test]
+    fn default_keys_map_to_their_actions() {
+        let keymap = KeyMap::from_config(&HashMap::new()).unwrap();
+        let press = |code, modifiers| keymap.handle_event(&Event::Key(key(code, modifiers)));
+        assert_eq!(press(KeyCode::Char('q'), KeyModifiers::NONE), InputAction::Quit);
+        assert_eq!(press(KeyCode::Char('c'), KeyModifiers::CONTROL), InputAction::Quit);
+        assert_eq!(press(KeyCode::Char('A'), KeyModifiers::SHIFT), InputAction::ToggleAlerts);
+        assert_eq!(press(KeyCode::Esc, KeyModifiers::NONE), InputAction::ClearFilters);
+        assert_eq!(press(KeyCode::Char('z'), KeyModifiers::NONE), InputAction::None);
+
+        let overrides = HashMap::from([("quit".to_string(), "ctrl+q".to_string())]);
+        let keymap = KeyMap::from_config(&overrides).unwrap();
+        assert_eq!(keymap.key_for(InputAction::Quit).unwrap().to_string(), "ctrl+q");
+        assert_eq!(keymap.handle_event(&Event::Key(key(KeyCode::Char('q'), KeyModifiers::NONE))), InputAction::None);
+    }
+}
@@ -0,0 +1,142 @@
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+
+/// Screen areas for each dashboard panel.
+pub struct DashboardLayout {
+    pub header: Rect,
+    pub cpu: Rect,
+    pub memory: Rect,
+    pub network: Rect,
+    pub disk: Rect,
+    pub gpu: Rect,
+    pub processes: Rect,
+    pub footer: Rect,
+}
+
+impl DashboardLayout {
+    pub fn new(area: Rect) -> Self {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Percentage(35),
+                Constraint::Percentage(25),
+                Constraint::Min(6),
+                Constraint::Length(1),
+            ])
+            .split(area);
+
+        let top = split_columns(rows[1], 60);
+        let middle = split_columns(rows[2], 50);
+        let devices = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(middle[1]);
+
+        DashboardLayout {
+            header: rows[0],
+            cpu: top[0],
+            memory: top[1],
+            network: middle[0],
+            disk: devices[0],
+            gpu: devices[1],
+            processes: rows[3],
+            footer: rows[4],
+        }
+    }
+}
+
+fn split_columns(area: Rect, left_percent: u16) -> std::rc::Rc<[Rect]> {
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(left_percent),
+            Constraint::Percentage(100 - left_percent),
+        ])
+        .split(area)
+}
+
+/// Screen areas for the history view: one graph per quadrant.
+pub struct HistoryLayout {
+    pub cpu: Rect,
+    pub memory: Rect,
+    pub network: Rect,
+    pub gpu: Rect,
+    pub footer: Rect,
+}
+
+impl HistoryLayout {
+    pub fn new(area: Rect) -> Self {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(50), Constraint::Min(4), Constraint::Length(1)])
+            .split(area);
+
+        let top = split_columns(rows[0], 50);
+        let bottom = split_columns(rows[1], 50);
+
+        HistoryLayout {
+            cpu: top[0],
+            memory: top[1],
+            network: bottom[0],
+            gpu: bottom[1],
+            footer: rows[2],
+        }
+    }
+}
+
+/// Screen areas for the system view.
+pub struct SystemLayout {
+    pub filesystems: Rect,
+    pub sensors: Rect,
+    pub pressure: Rect,
+    pub footer: Rect,
+}
+
+impl SystemLayout {
+    pub fn new(area: Rect) -> Self {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(40), Constraint::Min(4), Constraint::Length(1)])
+            .split(area);
+
+        let bottom = split_columns(rows[1], 50);
+
+        SystemLayout { filesystems: rows[0], sensors: bottom[0], pressure: bottom[1], footer: rows[2] }
+    }
+}
+
+/// Screen areas for the alerts view.
+pub struct AlertsLayout {
+    pub rules: Rect,
+    pub log: Rect,
+    pub footer: Rect,
+}
+
+impl AlertsLayout {
+    pub fn new(area: Rect) -> Self {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(50), Constraint::Min(4), Constraint::Length(1)])
+            .split(area);
+
+        AlertsLayout { rules: rows[0], log: rows[1], footer: rows[2] }
+    }
+}
+
+/// Screen areas for the internals view.
+pub struct InternalsLayout {
+    pub collectors: Rect,
+    pub overhead: Rect,
+    pub footer: Rect,
+}
+
+impl InternalsLayout {
+    pub fn new(area: Rect) -> Self {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(4), Constraint::Length(3), Constraint::Length(1)])
+            .split(area);
+
+        InternalsLayout { collectors: rows[0], overhead: rows[1], footer: rows[2] }
+    }
+}
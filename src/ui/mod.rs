@@ -0,0 +1,228 @@
+pub mod layout;
+pub mod dialog;
+pub mod drawer;
+pub mod filesystem_table;
+pub mod input_handlers;
+pub mod process_table;
+pub mod theme;
+
+use crate::config::UiConfig;
+use crate::error::AppError;
+use crate::process_actions::ProcessAction;
+use crate::state::app_state::AppState;
+use dialog::{Dialog, DialogOutcome, PromptKind};
+use filesystem_table::FilesystemTableState;
+use input_handlers::{InputAction, KeyMap};
+use process_table::ProcessTableState;
+use theme::Theme;
+
+use crossterm::execute;
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::event::{Event, KeyEventKind};
+use ratatui::backend::CrosstermBackend;
+use ratatui::Terminal;
+use std::io::{self, Stdout};
+use std::time::Instant;
+
+/// Full-screen terminal dashboard. Entering raw mode and the alternate screen
+/// happens in `new`; both are undone when the value is dropped.
+pub struct Tui {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+    ui_state: UiState,
+}
+
+/// Presentation state that lives alongside `AppState` but belongs to the UI.
+pub struct UiState {
+    pub theme: Theme,
+    pub keymap: KeyMap,
+    pub view: View,
+    pub processes: ProcessTableState,
+    pub filesystems: FilesystemTableState,
+    pub dialog: Option<Dialog>,
+    // Outcome of the last process action, shown in the footer for a while.
+    pub status: Option<StatusMessage>,
+}
+
+pub struct StatusMessage {
+    pub text: String,
+    pub is_error: bool,
+    pub at: Instant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum View {
+    Dashboard,
+    History,
+    // Storage and hardware details that don't fit on the dashboard.
+    System,
+    // Configured alert rules and their recent transitions.
+    Alerts,
+    // Collector health and monoxide's own overhead.
+    Internals,
+}
+
+impl Tui {
+    pub fn new(config: &UiConfig) -> Result<Self, AppError> {
+        let theme = Theme::from_name(&config.theme).unwrap_or_default();
+        let keymap = KeyMap::from_config(&config.keybindings)?;
+
+        install_panic_hook();
+        terminal::enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        if let Err(e) = execute!(stdout, EnterAlternateScreen) {
+            let _ = terminal::disable_raw_mode();
+            return Err(e.into());
+        }
+        let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+        terminal.hide_cursor()?;
+        terminal.clear()?;
+        Ok(Tui {
+            terminal,
+            ui_state: UiState {
+                theme,
+                keymap,
+                view: View::Dashboard,
+                processes: ProcessTableState::default(),
+                filesystems: FilesystemTableState::default(),
+                dialog: None,
+                status: None,
+            },
+        })
+    }
+
+    pub fn draw(&mut self, state: &AppState) -> Result<(), AppError> {
+        let ui_state = &mut self.ui_state;
+        self.terminal.draw(|frame| drawer::draw(frame, state, ui_state))?;
+        Ok(())
+    }
+
+    /// Handles UI-local actions itself; anything that needs the app core,
+    /// plus `Redraw` when the view changed, is returned to the caller.
+    pub fn handle_event(&mut self, event: &Event, state: &AppState) -> InputAction {
+        // Dialogs and the search box take raw key presses, bypassing the keymap.
+        let table = &mut self.ui_state.processes;
+        let capturing = self.ui_state.dialog.is_some() || table.editing_search;
+        if capturing && let Event::Key(key) = event {
+            if input_handlers::is_ctrl_c(key) {
+                return InputAction::Quit;
+            }
+            if key.kind != KeyEventKind::Press {
+                return InputAction::None;
+            }
+            if let Some(dialog) = &mut self.ui_state.dialog {
+                match dialog.handle_key(key) {
+                    DialogOutcome::Open => {}
+                    DialogOutcome::Cancelled => self.ui_state.dialog = None,
+                    DialogOutcome::Confirmed(action) => {
+                        let targets = self.ui_state.dialog.take().map(|d| d.targets).unwrap_or_default();
+                        self.run_process_action(&action, &targets);
+                    }
+                }
+            } else {
+                table.edit_search(key);
+            }
+            return InputAction::Redraw;
+        }
+
+        let processes = state.processes.as_deref().unwrap_or_default();
+        let targets: Vec<(u32, String)> =
+            table.action_targets(processes).iter().map(|p| (p.pid, p.name.clone())).collect();
+        let page = table.page_size as isize;
+        let view = self.ui_state.view;
+        match self.ui_state.keymap.handle_event(event) {
+            InputAction::ToggleHistory => {
+                self.ui_state.view = if view == View::History { View::Dashboard } else { View::History };
+            }
+            InputAction::ToggleSystem => {
+                self.ui_state.view = if view == View::System { View::Dashboard } else { View::System };
+            }
+            InputAction::ToggleAlerts => {
+                self.ui_state.view = if view == View::Alerts { View::Dashboard } else { View::Alerts };
+            }
+            InputAction::ToggleInternals => {
+                self.ui_state.view = if view == View::Internals { View::Dashboard } else { View::Internals };
+            }
+            // The system view's only sortable table is the filesystem one.
+            InputAction::SortNext if view == View::System => self.ui_state.filesystems.cycle_sort(true),
+            InputAction::SortPrevious if view == View::System => self.ui_state.filesystems.cycle_sort(false),
+            InputAction::ReverseSort if view == View::System => self.ui_state.filesystems.reverse_sort(),
+            InputAction::SortNext => table.cycle_sort(true),
+            InputAction::SortPrevious => table.cycle_sort(false),
+            InputAction::ReverseSort => table.reverse_sort(),
+            InputAction::Search => table.start_search(),
+            InputAction::FilterStatus => table.cycle_status_filter(),
+            InputAction::FilterUser => table.cycle_user_filter(processes),
+            InputAction::ClearFilters => table.clear_filters(),
+            InputAction::ToggleTree => table.toggle_tree(),
+            InputAction::ToggleCollapse => table.toggle_collapsed(),
+            InputAction::ToggleDetails => table.toggle_details(),
+            InputAction::Tag => table.toggle_tag(),
+            InputAction::ClearTags => table.clear_tags(),
+            // Recorded PIDs may belong to unrelated processes by now.
+            InputAction::SendSignal | InputAction::Renice | InputAction::SetAffinity if state.replay.is_some() => {
+                let text = "Process actions are unavailable during replay".to_string();
+                self.ui_state.status = Some(StatusMessage { text, is_error: true, at: Instant::now() });
+            }
+            InputAction::SendSignal if !targets.is_empty() => {
+                self.ui_state.dialog = Some(Dialog::signal_menu(targets));
+            }
+            InputAction::Renice if !targets.is_empty() => {
+                self.ui_state.dialog = Some(Dialog::prompt(targets, PromptKind::Nice));
+            }
+            InputAction::SetAffinity if !targets.is_empty() => {
+                self.ui_state.dialog = Some(Dialog::prompt(targets, PromptKind::Affinity));
+            }
+            InputAction::SelectUp => table.move_selection(processes, -1),
+            InputAction::SelectDown => table.move_selection(processes, 1),
+            InputAction::PageUp => table.move_selection(processes, -page),
+            InputAction::PageDown => table.move_selection(processes, page),
+            InputAction::SelectFirst => table.move_selection(processes, isize::MIN),
+            InputAction::SelectLast => table.move_selection(processes, isize::MAX),
+            action => return action,
+        }
+        InputAction::Redraw
+    }
+
+    // Applies `action` to every target and reports the outcome in the status
+    // line. Tags are cleared afterwards, as the job is done.
+    fn run_process_action(&mut self, action: &ProcessAction, targets: &[(u32, String)]) {
+        let errors: Vec<AppError> = targets.iter().filter_map(|(pid, _)| action.apply(*pid).err()).collect();
+        for error in &errors {
+            log::warn!("{}", error);
+        }
+        let succeeded = targets.len() - errors.len();
+        let text = match (targets, errors.first()) {
+            ([(pid, name)], None) => format!("Done: {} process {} ({})", action.describe(), pid, name),
+            (_, None) => format!("Done: {} {} processes", action.describe(), succeeded),
+            ([_], Some(error)) => error.to_string(),
+            (_, Some(error)) => format!("{} of {} failed; {}", errors.len(), targets.len(), error),
+        };
+        self.ui_state.status = Some(StatusMessage { text, is_error: !errors.is_empty(), at: Instant::now() });
+        self.ui_state.processes.clear_tags();
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        if let Err(e) = restore_terminal() {
+            log::error!("Failed to restore terminal: {:?}", e);
+        }
+        let _ = self.terminal.show_cursor();
+    }
+}
+
+/// Leaves the alternate screen and raw mode. Safe to call more than once.
+pub fn restore_terminal() -> io::Result<()> {
+    terminal::disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen, crossterm::cursor::Show)
+}
+
+// Restore the terminal before the default hook prints the panic message,
+// otherwise it ends up garbled inside the alternate screen.
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal();
+        default_hook(info);
+    }));
+}
@@ -0,0 +1,613 @@
+use crate::state::data_types::{ProcessInfo, ProcessStatus};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::widgets::TableState;
+use regex::{Regex, RegexBuilder};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortColumn {
+    Pid,
+    Name,
+    User,
+    Cpu,
+    Memory,
+    Status,
+}
+
+impl SortColumn {
+    pub const ALL: [SortColumn; 6] = [
+        SortColumn::Pid,
+        SortColumn::Name,
+        SortColumn::User,
+        SortColumn::Cpu,
+        SortColumn::Memory,
+        SortColumn::Status,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            SortColumn::Pid => "PID",
+            SortColumn::Name => "Name",
+            SortColumn::User => "User",
+            SortColumn::Cpu => "CPU%",
+            SortColumn::Memory => "Memory",
+            SortColumn::Status => "Status",
+        }
+    }
+
+    // Text columns read naturally A-Z, numbers biggest first.
+    fn default_ascending(self) -> bool {
+        !matches!(self, SortColumn::Cpu | SortColumn::Memory)
+    }
+
+    fn compare(self, a: &ProcessInfo, b: &ProcessInfo) -> Ordering {
+        match self {
+            SortColumn::Pid => a.pid.cmp(&b.pid),
+            SortColumn::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            SortColumn::User => a.user.cmp(&b.user),
+            SortColumn::Cpu => a.cpu_usage_percent.total_cmp(&b.cpu_usage_percent),
+            SortColumn::Memory => a.memory_usage_kb.cmp(&b.memory_usage_kb),
+            SortColumn::Status => a.status.to_string().cmp(&b.status.to_string()),
+        }
+    }
+}
+
+// Statuses the status filter cycles through, in order.
+const STATUS_FILTERS: [ProcessStatus; 4] =
+    [ProcessStatus::Run, ProcessStatus::Sleep, ProcessStatus::Idle, ProcessStatus::Zombie];
+
+/// One line of the process table. In tree mode `subtree_*` include every
+/// descendant, collapsed or not; in list mode they equal the process's own.
+#[derive(Debug)]
+pub struct ProcessRow<'a> {
+    pub process: &'a ProcessInfo,
+    // Box-drawing prefix showing the row's place in the tree.
+    pub tree_prefix: String,
+    pub has_children: bool,
+    pub collapsed: bool,
+    pub subtree_cpu_percent: f32,
+    pub subtree_memory_kb: u64,
+}
+
+impl<'a> ProcessRow<'a> {
+    fn leaf(process: &'a ProcessInfo) -> Self {
+        ProcessRow {
+            process,
+            tree_prefix: String::new(),
+            has_children: false,
+            collapsed: false,
+            subtree_cpu_percent: process.cpu_usage_percent,
+            subtree_memory_kb: process.memory_usage_kb,
+        }
+    }
+}
+
+// Children of each process among the rows being shown, keyed by parent PID.
+// `None` holds the roots: processes whose parent is absent or filtered out.
+type ChildMap<'a> = HashMap<Option<u32>, Vec<&'a ProcessInfo>>;
+
+/// Sorting, filtering and selection for the process table. The selection is
+/// kept as a PID so it stays on the same process when rows move.
+#[derive(Debug)]
+pub struct ProcessTableState {
+    pub sort_column: SortColumn,
+    pub ascending: bool,
+    pub search: String,
+    pub editing_search: bool,
+    // Compiled from `search`; plain text when it is not a valid regex.
+    search_pattern: Option<Regex>,
+    pub status_filter: Option<ProcessStatus>,
+    pub user_filter: Option<String>,
+    pub tree_mode: bool,
+    pub show_details: bool,
+    // PIDs whose children are hidden in tree mode.
+    collapsed: HashSet<u32>,
+    // PIDs marked for a process action.
+    tagged: HashSet<u32>,
+    selected_pid: Option<u32>,
+    // Where the selection was last seen, to land nearby if its PID goes away.
+    selected_index: usize,
+    pub table_state: TableState,
+    // Rows that fit on screen, as of the last draw.
+    pub page_size: usize,
+}
+
+impl Default for ProcessTableState {
+    fn default() -> Self {
+        ProcessTableState {
+            sort_column: SortColumn::Cpu,
+            ascending: false,
+            search: String::new(),
+            editing_search: false,
+            search_pattern: None,
+            status_filter: None,
+            user_filter: None,
+            tree_mode: false,
+            show_details: false,
+            collapsed: HashSet::new(),
+            tagged: HashSet::new(),
+            selected_pid: None,
+            selected_index: 0,
+            table_state: TableState::default(),
+            page_size: 1,
+        }
+    }
+}
+
+impl ProcessTableState {
+    /// The rows that pass the current filters, in display order. In tree mode
+    /// siblings are sorted among themselves and collapsed subtrees are hidden.
+    pub fn visible<'a>(&self, processes: &'a [ProcessInfo]) -> Vec<ProcessRow<'a>> {
+        let mut matching: Vec<&ProcessInfo> = processes.iter().filter(|p| self.matches(p)).collect();
+        matching.sort_by(|a, b| self.compare(a, b));
+        if !self.tree_mode {
+            return matching.into_iter().map(ProcessRow::leaf).collect();
+        }
+
+        let parents = parent_links(&matching);
+        let mut children = ChildMap::new();
+        for process in matching {
+            children.entry(parents.get(&process.pid).copied()).or_default().push(process);
+        }
+
+        let mut rows = Vec::new();
+        let roots = children.get(&None).map(Vec::as_slice).unwrap_or_default();
+        for root in roots {
+            self.push_subtree(root, &children, String::new(), "", true, &mut rows);
+        }
+        rows
+    }
+
+    fn compare(&self, a: &ProcessInfo, b: &ProcessInfo) -> Ordering {
+        let ordering = self.sort_column.compare(a, b).then(a.pid.cmp(&b.pid));
+        if self.ascending { ordering } else { ordering.reverse() }
+    }
+
+    // Appends `process` and, unless collapsed, its descendants to `rows`.
+    // Returns the subtree's total CPU and memory either way. `indent` is the
+    // prefix for this process's children's branches.
+    fn push_subtree<'a>(
+        &self,
+        process: &'a ProcessInfo,
+        children: &ChildMap<'a>,
+        tree_prefix: String,
+        indent: &str,
+        emit: bool,
+        rows: &mut Vec<ProcessRow<'a>>,
+    ) -> (f32, u64) {
+        let kids = children.get(&Some(process.pid)).map(Vec::as_slice).unwrap_or_default();
+        let collapsed = self.collapsed.contains(&process.pid);
+        let row_index = rows.len();
+        if emit {
+            rows.push(ProcessRow { tree_prefix, has_children: !kids.is_empty(), collapsed, ..ProcessRow::leaf(process) });
+        }
+
+        let (mut cpu, mut memory) = (process.cpu_usage_percent, process.memory_usage_kb);
+        for (index, child) in kids.iter().enumerate() {
+            let (branch, continuation) = if index + 1 == kids.len() { ("└─ ", "   ") } else { ("├─ ", "│  ") };
+            let (child_cpu, child_memory) = self.push_subtree(
+                child,
+                children,
+                format!("{}{}", indent, branch),
+                &format!("{}{}", indent, continuation),
+                emit && !collapsed,
+                rows,
+            );
+            if !child.is_thread {
+                cpu += child_cpu;
+                memory += child_memory;
+            }
+        }
+
+        if emit {
+            rows[row_index].subtree_cpu_percent = cpu;
+            rows[row_index].subtree_memory_kb = memory;
+        }
+        (cpu, memory)
+    }
+
+    fn matches(&self, process: &ProcessInfo) -> bool {
+        if self.status_filter.as_ref().is_some_and(|status| *status != process.status) {
+            return false;
+        }
+        if self.user_filter.is_some() && self.user_filter != process.user {
+            return false;
+        }
+        self.search_pattern.as_ref().is_none_or(|pattern| pattern.is_match(&process.name))
+    }
+
+    pub fn has_filters(&self) -> bool {
+        self.search_pattern.is_some() || self.status_filter.is_some() || self.user_filter.is_some()
+    }
+
+    pub fn selected_pid(&self) -> Option<u32> {
+        self.selected_pid
+    }
+
+    pub fn cycle_sort(&mut self, forward: bool) {
+        let count = SortColumn::ALL.len();
+        let index = SortColumn::ALL.iter().position(|c| *c == self.sort_column).unwrap_or(0);
+        let next = if forward { (index + 1) % count } else { (index + count - 1) % count };
+        self.sort_column = SortColumn::ALL[next];
+        self.ascending = self.sort_column.default_ascending();
+    }
+
+    pub fn reverse_sort(&mut self) {
+        self.ascending = !self.ascending;
+    }
+
+    pub fn start_search(&mut self) {
+        self.editing_search = true;
+    }
+
+    /// Applies a key press to the search box. The filter updates as the user
+    /// types; Enter keeps it, Esc clears it.
+    pub fn edit_search(&mut self, key: &KeyEvent) {
+        match key.code {
+            KeyCode::Char(c) => self.search.push(c),
+            KeyCode::Backspace => {
+                self.search.pop();
+            }
+            KeyCode::Enter => self.editing_search = false,
+            KeyCode::Esc => {
+                self.search.clear();
+                self.editing_search = false;
+            }
+            _ => return,
+        }
+        self.search_pattern = compile_search(&self.search);
+    }
+
+    pub fn cycle_status_filter(&mut self) {
+        let next = match &self.status_filter {
+            None => 0,
+            Some(status) => STATUS_FILTERS.iter().position(|s| s == status).map_or(0, |i| i + 1),
+        };
+        self.status_filter = STATUS_FILTERS.get(next).cloned();
+    }
+
+    /// Steps through the users that currently own a process, then back to all.
+    pub fn cycle_user_filter(&mut self, processes: &[ProcessInfo]) {
+        let mut users: Vec<&String> = processes.iter().filter_map(|p| p.user.as_ref()).collect();
+        users.sort();
+        users.dedup();
+        let next = match &self.user_filter {
+            None => 0,
+            Some(current) => users.iter().position(|u| *u == current).map_or(0, |i| i + 1),
+        };
+        self.user_filter = users.get(next).map(|u| u.to_string());
+    }
+
+    pub fn toggle_tree(&mut self) {
+        self.tree_mode = !self.tree_mode;
+    }
+
+    pub fn toggle_details(&mut self) {
+        self.show_details = !self.show_details;
+    }
+
+    /// Hides or shows the children of the selected process in tree mode.
+    pub fn toggle_collapsed(&mut self) {
+        let Some(pid) = self.selected_pid.filter(|_| self.tree_mode) else { return };
+        if !self.collapsed.remove(&pid) {
+            self.collapsed.insert(pid);
+        }
+    }
+
+    pub fn toggle_tag(&mut self) {
+        let Some(pid) = self.selected_pid else { return };
+        if !self.tagged.remove(&pid) {
+            self.tagged.insert(pid);
+        }
+    }
+
+    pub fn clear_tags(&mut self) {
+        self.tagged.clear();
+    }
+
+    pub fn is_tagged(&self, pid: u32) -> bool {
+        self.tagged.contains(&pid)
+    }
+
+    /// What a process action applies to: the tagged processes that still
+    /// exist, or the selected one when nothing is tagged.
+    pub fn action_targets<'a>(&self, processes: &'a [ProcessInfo]) -> Vec<&'a ProcessInfo> {
+        if self.tagged.is_empty() {
+            processes.iter().filter(|p| Some(p.pid) == self.selected_pid).collect()
+        } else {
+            processes.iter().filter(|p| self.tagged.contains(&p.pid)).collect()
+        }
+    }
+
+    pub fn clear_filters(&mut self) {
+        self.search.clear();
+        self.search_pattern = None;
+        self.status_filter = None;
+        self.user_filter = None;
+    }
+
+    /// Moves the selection by `delta` rows, clamped to the visible list.
+    pub fn move_selection(&mut self, processes: &[ProcessInfo], delta: isize) {
+        let visible = self.visible(processes);
+        self.sync_selection(&visible);
+        if visible.is_empty() {
+            return;
+        }
+        let index = self.selected_index.saturating_add_signed(delta).min(visible.len() - 1);
+        self.select_index(&visible, index);
+    }
+
+    /// Re-finds the selected PID in `visible`. If it exited or was filtered
+    /// out, the row now at the old position is selected instead.
+    pub fn sync_selection(&mut self, visible: &[ProcessRow]) {
+        // Don't leave blank space below the last row after the list shrinks.
+        let max_offset = visible.len().saturating_sub(self.page_size);
+        if self.table_state.offset() > max_offset {
+            *self.table_state.offset_mut() = max_offset;
+        }
+        if visible.is_empty() {
+            self.table_state.select(None);
+            return;
+        }
+        let index = self
+            .selected_pid
+            .and_then(|pid| visible.iter().position(|row| row.process.pid == pid))
+            .unwrap_or(self.selected_index.min(visible.len() - 1));
+        self.select_index(visible, index);
+    }
+
+    fn select_index(&mut self, visible: &[ProcessRow], index: usize) {
+        self.selected_index = index;
+        self.selected_pid = Some(visible[index].process.pid);
+        self.table_state.select(Some(index));
+    }
+}
+
+// Each shown process's parent among the shown ones. A parent that started
+// after its child is a reused PID, not the real parent, and any loop left
+// over is cut so that every process ends up under some root.
+fn parent_links(processes: &[&ProcessInfo]) -> HashMap<u32, u32> {
+    let by_pid: HashMap<u32, &ProcessInfo> = processes.iter().map(|p| (p.pid, *p)).collect();
+    let mut parents: HashMap<u32, u32> = processes
+        .iter()
+        .filter_map(|process| {
+            let parent = by_pid.get(&process.ppid?)?;
+            (parent.pid != process.pid && parent.start_time_secs <= process.start_time_secs)
+                .then_some((process.pid, parent.pid))
+        })
+        .collect();
+    for process in processes {
+        let mut seen = HashSet::new();
+        let mut pid = process.pid;
+        while let Some(&parent) = parents.get(&pid) {
+            if !seen.insert(pid) {
+                parents.remove(&pid);
+                break;
+            }
+            pid = parent;
+        }
+    }
+    parents
+}
+
+// Case-insensitive regex, falling back to a literal match while the user is
+// halfway through typing something like `foo(`.
+fn compile_search(search: &str) -> Option<Regex> {
+    if search.is_empty() {
+        return None;
+    }
+    RegexBuilder::new(search)
+        .case_insensitive(true)
+        .build()
+        .or_else(|_| RegexBuilder::new(&regex::escape(search)).case_insensitive(true).build())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, ppid: Option<u32>, name: &str, cpu_usage_percent: f32) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            ppid,
+            name: name.to_string(),
+            cmd: vec![name.to_string()],
+            exe: None,
+            cwd: None,
+            cpu_usage_percent,
+            memory_usage_kb: 1024,
+            virtual_memory_kb: 4096,
+            status: ProcessStatus::Sleep,
+            is_thread: false,
+            thread_count: Some(1),
+            user: Some("root".to_string()),
+            group: Some("root".to_string()),
+            start_time_secs: 1_700_000_000,
+            run_time_secs: 60,
+            disk_read_bytes_total: 0,
+            disk_written_bytes_total: 0,
+            disk_read_bytes_per_sec: 0.0,
+            disk_written_bytes_per_sec: 0.0,
+        }
+    }
+
+    fn pids(rows: &[ProcessRow]) -> Vec<u32> {
+        rows.iter().map(|row| row.process.pid).collect()
+    }
+
+    fn search(table: &mut ProcessTableState, text: &str) {
+        table.start_search();
+        for c in text.chars() {
+            table.edit_search(&KeyEvent::from(KeyCode::Char(c)));
+        }
+        table.edit_search(&KeyEvent::from(KeyCode::Enter));
+    }
+
+    fn tree() -> ProcessTableState {
+        ProcessTableState { tree_mode: true, sort_column: SortColumn::Pid, ascending: true, ..Default::default() }
+    }
+
+    #[test]
+    fn ties_are_broken_by_pid() {
+        let processes = vec![
+            process(30, None, "c", 5.0),
+            process(10, None, "a", 5.0),
+            process(20, None, "b", 9.0),
+            process(40, None, "d", 5.0),
+        ];
+        let mut table = ProcessTableState::default();
+        assert_eq!(pids(&table.visible(&processes)), [20, 40, 30, 10]);
+
+        // The order doesn't depend on the order the collector listed them in.
+        let mut shuffled = processes.clone();
+        shuffled.reverse();
+        assert_eq!(pids(&table.visible(&shuffled)), [20, 40, 30, 10]);
+
+        table.reverse_sort();
+        assert_eq!(pids(&table.visible(&processes)), [10, 30, 40, 20]);
+    }
+
+    #[test]
+    fn sorts_names_ignoring_case() {
+        let processes =
+            vec![process(1, None, "bash", 0.0), process(2, None, "Xorg", 0.0), process(3, None, "Bash", 0.0)];
+        let mut table = ProcessTableState::default();
+        table.cycle_sort(false);
+        table.cycle_sort(false);
+        assert_eq!(table.sort_column, SortColumn::Name);
+        assert!(table.ascending);
+        assert_eq!(pids(&table.visible(&processes)), [1, 3, 2]);
+    }
+
+    #[test]
+    fn search_is_a_case_insensitive_regex() {
+        let processes = vec![
+            process(1, None, "systemd", 0.0),
+            process(2, None, "Xorg", 0.0),
+            process(3, None, "sshd", 0.0),
+            process(4, None, "foo(bar", 0.0),
+        ];
+        let mut table = ProcessTableState { sort_column: SortColumn::Pid, ascending: true, ..Default::default() };
+        search(&mut table, "XORG");
+        assert_eq!(pids(&table.visible(&processes)), [2]);
+
+        table.edit_search(&KeyEvent::from(KeyCode::Esc));
+        search(&mut table, "^s.*d$");
+        assert_eq!(pids(&table.visible(&processes)), [1, 3]);
+
+        // Not a valid regex, so it is matched literally.
+        table.edit_search(&KeyEvent::from(KeyCode::Esc));
+        search(&mut table, "o(b");
+        assert_eq!(pids(&table.visible(&processes)), [4]);
+
+        table.edit_search(&KeyEvent::from(KeyCode::Esc));
+        assert!(!table.has_filters());
+        assert_eq!(table.visible(&processes).len(), 4);
+    }
+
+    #[test]
+    fn filters_combine() {
+        let mut processes = vec![
+            process(1, None, "nginx", 0.0),
+            process(2, None, "nginx", 0.0),
+            process(3, None, "nginx", 0.0),
+            process(4, None, "postgres", 0.0),
+        ];
+        processes[1].user = Some("www-data".to_string());
+        processes[2].status = ProcessStatus::Run;
+        let mut table = ProcessTableState { sort_column: SortColumn::Pid, ascending: true, ..Default::default() };
+
+        table.cycle_user_filter(&processes);
+        assert_eq!(table.user_filter.as_deref(), Some("root"));
+        assert_eq!(pids(&table.visible(&processes)), [1, 3, 4]);
+
+        table.cycle_status_filter();
+        assert_eq!(table.status_filter, Some(ProcessStatus::Run));
+        assert_eq!(pids(&table.visible(&processes)), [3]);
+
+        search(&mut table, "postgres");
+        assert!(table.visible(&processes).is_empty());
+
+        table.clear_filters();
+        assert_eq!(table.visible(&processes).len(), 4);
+    }
+
+    #[test]
+    fn builds_a_tree_with_subtree_totals() {
+        let processes = vec![
+            process(1, Some(0), "init", 1.0),
+            process(10, Some(1), "sshd", 2.0),
+            process(11, Some(10), "bash", 3.0),
+            process(12, Some(1), "cron", 4.0),
+        ];
+        let mut table = tree();
+        let rows = table.visible(&processes);
+        assert_eq!(pids(&rows), [1, 10, 11, 12]);
+        let prefixes: Vec<&str> = rows.iter().map(|row| row.tree_prefix.as_str()).collect();
+        assert_eq!(prefixes, ["", "├─ ", "│  └─ ", "└─ "]);
+        assert_eq!(rows[0].subtree_cpu_percent, 10.0);
+        assert_eq!(rows[0].subtree_memory_kb, 4096);
+        assert_eq!(rows[1].subtree_cpu_percent, 5.0);
+
+        table.sync_selection(&rows);
+        table.move_selection(&processes, 1);
+        table.toggle_collapsed();
+        let rows = table.visible(&processes);
+        assert_eq!(pids(&rows), [1, 10, 12]);
+        assert!(rows[1].collapsed);
+        assert_eq!(rows[1].subtree_cpu_percent, 5.0);
+    }
+
+    #[test]
+    fn processes_without_a_shown_parent_are_roots() {
+        let processes = vec![
+            process(10, Some(999), "orphan", 0.0),
+            process(11, Some(10), "child", 0.0),
+            process(20, None, "kthreadd", 0.0),
+            process(30, Some(30), "own-parent", 0.0),
+        ];
+        let mut table = tree();
+        let rows = table.visible(&processes);
+        assert_eq!(pids(&rows), [10, 11, 20, 30]);
+        assert_eq!(rows[1].tree_prefix, "└─ ");
+        assert!(rows.iter().filter(|row| row.process.pid != 11).all(|row| row.tree_prefix.is_empty()));
+
+        // Filtering out the parent promotes the child.
+        search(&mut table, "child");
+        let rows = table.visible(&processes);
+        assert_eq!(pids(&rows), [11]);
+        assert!(rows[0].tree_prefix.is_empty());
+    }
+
+    #[test]
+    fn a_reused_pid_is_not_a_parent() {
+        // 10 started under 20; then 20 exited and its PID went to a process
+        // started later under 10, so each names the other as its parent.
+        let mut processes = vec![process(10, Some(20), "worker", 0.0), process(20, Some(10), "helper", 0.0)];
+        processes[1].start_time_secs += 30;
+        let rows = tree().visible(&processes);
+        assert_eq!(pids(&rows), [10, 20]);
+        assert_eq!(rows[1].tree_prefix, "└─ ");
+    }
+
+    #[test]
+    fn loops_are_cut() {
+        // Started in the same second, so the start times can't tell which
+        // link is stale. Every process must still show up, once.
+        let processes = vec![
+            process(10, Some(30), "a", 1.0),
+            process(20, Some(10), "b", 1.0),
+            process(30, Some(20), "c", 1.0),
+            process(40, Some(20), "d", 1.0),
+        ];
+        let rows = tree().visible(&processes);
+        let mut shown = pids(&rows);
+        shown.sort();
+        assert_eq!(shown, [10, 20, 30, 40]);
+        assert!(rows[0].tree_prefix.is_empty());
+        assert_eq!(rows[0].subtree_cpu_percent, 4.0);
+    }
+}
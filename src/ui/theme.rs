@@ -0,0 +1,75 @@
+use ratatui::style::Color;
+
+/// Colours used by the drawer, selected with `ui.theme` in the config.
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    pub accent: Color,
+    pub muted: Color,
+    pub ok: Color,
+    pub warn: Color,
+    pub critical: Color,
+    pub highlight: Color,
+}
+
+impl Theme {
+    pub const NAMES: [&'static str; 3] = ["dark", "light", "mono"];
+
+    pub fn from_name(name: &str) -> Option<Theme> {
+        match name {
+            "dark" => Some(Theme {
+                accent: Color::Cyan,
+                muted: Color::DarkGray,
+                ok: Color::Green,
+                warn: Color::Yellow,
+                critical: Color::Red,
+                highlight: Color::Yellow,
+            }),
+            "light" => Some(Theme {
+                accent: Color::Blue,
+                muted: Color::Gray,
+                ok: Color::Green,
+                warn: Color::Magenta,
+                critical: Color::Red,
+                highlight: Color::Blue,
+            }),
+            "mono" => Some(Theme {
+                accent: Color::Reset,
+                muted: Color::Reset,
+                ok: Color::Reset,
+                warn: Color::Reset,
+                critical: Color::Reset,
+                highlight: Color::Reset,
+            }),
+            _ => None,
+        }
+    }
+
+    // `ok` below 50%, `warn` below 80%, `critical` above.
+    pub fn usage_color(&self, percent: f64) -> Color {
+        if percent >= 80.0 {
+            self.critical
+        } else if percent >= 50.0 {
+            self.warn
+        } else {
+            self.ok
+        }
+    }
+
+    /// Colour for a PSI stall percentage. Any sustained stall is worth a look,
+    /// so the thresholds sit much lower than for utilization.
+    pub fn pressure_color(&self, percent: f64) -> Color {
+        if percent >= 40.0 {
+            self.critical
+        } else if percent >= 10.0 {
+            self.warn
+        } else {
+            self.ok
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::from_name("dark").unwrap()
+    }
+}